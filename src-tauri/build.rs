@@ -1,3 +1,6 @@
 fn main() {
-  tauri_build::build()
+  // 只有图形界面需要生成 Tauri 上下文；命令行单独构建时跳过
+  if std::env::var_os("CARGO_FEATURE_GUI").is_some() {
+    tauri_build::build()
+  }
 }
@@ -0,0 +1,215 @@
+# Backend message catalog (English)
+# Keys match zh-CN.ftl one to one; error keys are also returned to the frontend as AppError.key
+
+## Application and background jobs
+
+app-data-dir = Cannot determine the app data directory: { $detail }
+app-config-dir = Cannot determine the config directory: { $detail }
+locale-save = Failed to save the language setting: { $detail }
+scan-busy = A scan is already running
+move-busy = A move is already running
+job-not-found = Job not found: { $id }
+job-aborted = The job stopped unexpectedly
+journal-write = Failed to write the operation journal: { $detail }
+journal-create = Failed to create the write-ahead journal: { $detail }
+journal-open = Failed to open the write-ahead journal: { $detail }
+journal-read = Failed to read the write-ahead journal: { $detail }
+journal-corrupt = The write-ahead journal is corrupt
+journal-discard-source = Failed to delete the copied source file: { $detail }
+catalog-open = Failed to open the photo catalog: { $detail }
+catalog-migrate = Failed to migrate the photo catalog to v{ $version }: { $detail }
+catalog-db = Photo catalog database error: { $detail }
+task-scan-failed = Scan task failed: { $detail }
+task-plan-failed = Failed to build the move plan: { $detail }
+task-move-failed = Move task failed: { $detail }
+task-preview-failed = Preview task failed: { $detail }
+task-history-failed = Failed to read the operation history: { $detail }
+task-undo-failed = Undo task failed: { $detail }
+task-redo-failed = Redo task failed: { $detail }
+task-journal-failed = Failed to read the operation journal: { $detail }
+task-resume-failed = Resume task failed: { $detail }
+task-rollback-failed = Rollback task failed: { $detail }
+task-query-failed = Query task failed: { $detail }
+task-stop-watch-failed = Failed to stop watching: { $detail }
+watch-busy = Folder watching is already running
+watch-not-running = Folder watching is not running
+watch-no-config = No saved watch configuration
+watch-save-config = Failed to save the watch configuration: { $detail }
+watch-source-missing = The watched folder does not exist: { $path }
+watch-target-unset = No target folder is set
+watch-target-inside-source = The target folder cannot be inside the watched folder
+watch-no-rules = At least one sorting rule is required
+watch-create-failed = Failed to create the file watcher: { $detail }
+watch-folder-failed = Failed to watch the folder: { $detail }
+watch-thread-failed = Failed to start the watch thread: { $detail }
+watch-move-failed = Failed to move the file: { $detail }
+watch-move-failed-unknown = Failed to move the file
+
+## Scanning and metadata
+
+scan-thread-pool = Failed to create the thread pool: { $detail }
+scan-index-save = Failed to save the scan index: { $detail }
+image-open = Cannot open image: { $detail }
+image-decode = Cannot decode image: { $detail }
+thumbnail-encode = Failed to encode thumbnail: { $detail }
+metadata-read = Failed to read image file: { $detail }
+metadata-exif = Failed to parse EXIF: { $detail }
+
+## Move plans and transfers
+
+trash-unsupported = Moving to the trash is not supported on this platform
+trash-no-data-dir = Cannot determine the user data folder
+name-rules-empty-replacement = The replacement for invalid characters cannot be empty
+name-rules-bad-replacement = The replacement itself contains invalid characters: { $replacement }
+name-rules-max-bytes = The folder name length limit must be between 1 and { $max } bytes: { $value }
+name-rules-empty-from = The text to replace in a custom rule cannot be empty
+rename-pattern-missing-n = The rename pattern must contain {"{n}"}: { $pattern }
+rename-pattern-separator = The rename pattern cannot contain path separators: { $pattern }
+rename-pattern-unknown-var = The rename pattern contains an unknown variable: { $pattern }
+destination-outside-target = The destination is outside the target folder: { $path }
+move-create-target = Failed to create the target folder: { $detail }
+move-source-missing = The source file no longer exists
+move-content-changed = The target file has changed, not deduplicated
+move-destination-is-dir = The target is a folder and cannot be overwritten
+move-destination-taken = The target was taken after the plan was made
+move-transfer-failed = Transfer failed: { $detail }
+move-group-reverted = { $name } in the same group failed, this file was reverted
+move-group-skipped = { $name } in the same group failed, this file was not processed
+move-person-reverted = { $name } could not be placed in the primary person's folder, this copy was reverted
+copy-cancelled = Copy cancelled
+copy-size-mismatch = Size mismatch after copying: source { $source } bytes, copy { $copy } bytes
+copy-hash-mismatch = Content hash mismatch after copying
+copy-destination-taken = The target was taken while copying
+reflink-unsupported = Reflink is not supported on this platform
+reflink-unsupported-fs = The file system does not support reflink between these locations ({ $detail }), use copy instead
+
+## Undo
+
+undo-remove-copy-failed = Failed to delete the copy: { $detail }
+undo-create-dir-failed = Failed to create the original folder: { $detail }
+undo-remove-moved-failed = Copied back, but failed to delete the moved file: { $detail }
+undo-restore-failed = Failed to move the file back: { $detail }
+
+## Path templates
+
+template-empty = The path template cannot be empty
+template-absolute = The path template must be a relative path: { $template }
+template-dot-segment = The path template cannot contain "{ $text }"
+template-filename-not-last = {"{filename}"}/{"{stem}"} can only appear in the last part of the template
+template-nested-braces = Braces in the path template cannot be nested
+template-extra-brace = The path template has an unmatched "{"}"}"
+template-unclosed-brace = The path template has an unclosed "{"{"}"
+template-keyword-pattern = {"{keyword:...}"} needs a pattern, for example {"{keyword:Event|*}"}
+template-keyword-stars = A keyword pattern can contain only one *: { $pattern }
+template-unknown-var = Unknown template variable: {"{"}{ $name }{"}"}
+
+## Operation history
+
+history-open = Failed to create the history folder: { $detail }
+history-read = Failed to read the operation history: { $detail }
+history-write = Failed to save the operation history: { $detail }
+history-lock = Failed to lock the operation history: { $detail }
+history-corrupt = The operation history is corrupt: { $detail }
+history-not-found = Operation not found: { $id }
+undo-nothing = There is nothing to undo
+undo-already-undone = The operation has already been undone: { $id }
+undo-blocked = Newer operations changed these files, undo them first: { $ids }
+redo-not-undone = The operation has not been undone and cannot be redone: { $id }
+redo-blocked = Later operations changed these files, cannot redo: { $ids }
+redo-nothing = No files can be redone (sources are gone or targets are taken)
+
+## Command line
+
+cli-error = Error: { $detail }
+cli-warning = Warning: { $detail }
+cli-failure = Failed: { $source } -> { $destination }: { $detail }
+cli-no-data-dir = Cannot determine the data folder, specify it with --data-dir
+cli-current-dir = Cannot get the current folder: { $detail }
+cli-source-missing = The source folder does not exist: { $path }
+cli-source-unreadable = Cannot access the source folder: { $detail }
+cli-file-missing = File not found: { $path }
+cli-found = Found { $count ->
+    [one] { $count } image file
+   *[other] { $count } image files
+}
+cli-plan-summary = { $count ->
+    [one] { $count } transfer
+   *[other] { $count } transfers
+}, about { $bytes } bytes to write; { $conflicts } name conflicts, { $missing } missing sources, { $cross_device } cross-device, { $permission } permission problems
+cli-plan-written = Plan written to { $path } ({ $count ->
+    [one] { $count } file
+   *[other] { $count } files
+})
+cli-plan-write-failed = Failed to write the plan file: { $detail }
+cli-plan-read-failed = Failed to read the plan file: { $detail }
+cli-plan-corrupt = The plan file is malformed: { $detail }
+cli-unknown-mode = Unknown transfer mode: { $value }
+cli-unknown-conflict-policy = Unknown conflict policy: { $value }
+cli-unknown-multi-person = Unknown multi-person strategy: { $value }
+
+## Logs
+
+log-scan-found = Found { $count ->
+    [one] { $count } image
+   *[other] { $count } images
+}, reading metadata in parallel...
+log-scan-cancelled = Scan cancelled by user after { $count ->
+    [one] { $count } image
+   *[other] { $count } images
+}
+log-scan-removed = Incremental scan: { $count ->
+    [one] { $count } file no longer exists
+   *[other] { $count } files no longer exist
+}
+log-scan-finished = Scan finished, { $reused } reused from the index, { $processed } processed
+log-image-failed = Failed to process image: { $detail }
+log-catalog-write-failed = Failed to write the photo catalog: { $detail }
+log-catalog-update-failed = Failed to update the photo catalog: { $detail }
+log-catalog-record-failed = Failed to record the move in the photo catalog: { $detail }
+log-catalog-migrated = Migrated the photo catalog to v{ $version }
+log-scan-index-corrupt = The scan index is corrupt and will be rebuilt: { $detail }
+log-thumbnail-cache-failed = Failed to write the thumbnail cache: { $detail }
+log-watch-config-corrupt = The watch configuration is corrupt: { $detail }
+log-watch-started = Started watching folder: { $path }
+log-watch-stopped = Stopped watching folder: { $path }
+log-watch-error = File watch error: { $detail }
+log-watch-no-rule = No sorting rule matches, left in place: { $path }
+log-watch-dry-run = [dry run] { $path } -> { $destination }
+log-watch-moved = Sorted automatically: { $path } -> { $destination }
+log-messages-parse-failed = Failed to parse the { $locale } message catalog: { $detail }
+log-messages-duplicates = The { $locale } message catalog has duplicate messages: { $detail }
+log-message-format-failed = Failed to render message { $key }: { $detail }
+log-history-save-failed = Failed to save the operation history: { $detail }
+log-cancel-scan = Scan cancellation requested
+log-cancel-move = Move cancellation requested
+log-cancel-job = Cancellation requested for job { $id }
+log-move-cancelled = Move cancelled, { $count ->
+    [one] { $count } file
+   *[other] { $count } files
+} left unprocessed
+log-move-stopped = { $detail }, stopping transfers
+log-group-revert-failed = Failed to revert group member { $path }: { $detail }
+log-trashed = Moved to trash: { $path } -> { $trashed }
+log-source-missing = Source file missing, skipped: { $path }
+log-transfer-failed = Transfer failed { $source } -> { $destination } ({ $mode }): { $detail }
+log-skipped-existing = Target exists, skipped by policy: { $source } -> { $destination }
+log-dedup-changed = Target file changed, deduplication skipped: { $path }
+log-destination-is-dir = Target is a folder and cannot be overwritten: { $path }
+log-overwritten = Overwritten: { $path }
+log-unstage-failed = Failed to revert staged file, it was left at { $path }: { $detail }
+log-staged-restored = Returned staged file to its original location: { $path }
+log-staged-removed = Removed unfinished staged file: { $path }
+log-temp-removed = Removed an unfinished temporary file: { $path }
+log-clone-removed = Removed an unfinished clone: { $path }
+log-journal-remove-failed = Failed to delete the write-ahead journal { $path }: { $detail }
+log-journal-skipped = Skipped unreadable write-ahead journal { $path }: { $detail }
+log-destination-taken = Target taken after planning, skipped: { $path }
+log-xattr-read-failed = Failed to read extended attributes of { $path }: { $detail }
+log-xattr-copy-failed = Failed to copy extended attribute { $name } to { $path }: { $detail }
+log-undo-group-skipped = Group member cannot be undone, skipped: { $path }
+log-restore-failed = Failed to restore { $source } -> { $destination }: { $detail }
+log-undo-missing = File to restore is missing: { $path }
+log-undo-modified = File was modified after the operation, skipped: { $path }
+log-undo-occupied = Original location is taken, skipped: { $path }
+log-remove-dir-failed = Failed to remove empty folder { $path }: { $detail }
+log-history-corrupt = Skipped corrupt history entry { $path }: { $detail }
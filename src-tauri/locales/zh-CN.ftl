@@ -0,0 +1,194 @@
+# 后端消息目录（简体中文）
+# 消息键与 en-US.ftl 一一对应；错误消息的键同时作为 AppError.key 返回给前端
+
+## 应用和后台任务
+
+app-data-dir = 无法获取应用数据目录: { $detail }
+app-config-dir = 无法获取配置目录: { $detail }
+locale-save = 保存语言设置失败: { $detail }
+scan-busy = 已有扫描任务在进行中
+move-busy = 已有移动任务在进行中
+job-not-found = 任务不存在: { $id }
+job-aborted = 任务意外中止
+journal-write = 写入预写日志失败: { $detail }
+journal-create = 创建预写日志失败: { $detail }
+journal-open = 打开预写日志失败: { $detail }
+journal-read = 读取预写日志失败: { $detail }
+journal-corrupt = 预写日志已损坏
+journal-discard-source = 删除已复制的源文件失败: { $detail }
+catalog-open = 打开照片目录失败: { $detail }
+catalog-migrate = 照片目录迁移到 v{ $version } 失败: { $detail }
+catalog-db = 照片目录数据库错误: { $detail }
+task-scan-failed = 扫描任务失败: { $detail }
+task-plan-failed = 生成移动计划失败: { $detail }
+task-move-failed = 移动任务失败: { $detail }
+task-preview-failed = 预览任务失败: { $detail }
+task-history-failed = 读取操作历史失败: { $detail }
+task-undo-failed = 撤销任务失败: { $detail }
+task-redo-failed = 重做任务失败: { $detail }
+task-journal-failed = 读取预写日志失败: { $detail }
+task-resume-failed = 恢复任务失败: { $detail }
+task-rollback-failed = 回滚任务失败: { $detail }
+task-query-failed = 查询任务失败: { $detail }
+task-stop-watch-failed = 停止监视失败: { $detail }
+watch-busy = 监视任务已在运行
+watch-not-running = 监视任务未运行
+watch-no-config = 没有保存的监视配置
+watch-save-config = 保存监视配置失败: { $detail }
+watch-source-missing = 监视文件夹不存在: { $path }
+watch-target-unset = 未设置目标文件夹
+watch-target-inside-source = 目标文件夹不能位于被监视的文件夹内
+watch-no-rules = 至少需要一条分类规则
+watch-create-failed = 创建文件监视器失败: { $detail }
+watch-folder-failed = 监视文件夹失败: { $detail }
+watch-thread-failed = 启动监视线程失败: { $detail }
+watch-move-failed = 移动文件失败: { $detail }
+watch-move-failed-unknown = 移动文件失败
+
+## 扫描和元数据
+
+scan-thread-pool = 创建线程池失败: { $detail }
+scan-index-save = 保存扫描索引失败: { $detail }
+image-open = 无法打开图片: { $detail }
+image-decode = 无法解码图片: { $detail }
+thumbnail-encode = 缩略图编码失败: { $detail }
+metadata-read = 读取图片文件失败: { $detail }
+metadata-exif = EXIF 解析失败: { $detail }
+
+## 移动计划和传输
+
+trash-unsupported = 当前平台不支持移到回收站
+trash-no-data-dir = 无法确定用户数据目录
+name-rules-empty-replacement = 非法字符的替换字符串不能为空
+name-rules-bad-replacement = 替换字符串本身包含非法字符: { $replacement }
+name-rules-max-bytes = 文件夹名长度限制必须在 1 到 { $max } 字节之间: { $value }
+name-rules-empty-from = 自定义替换规则的原文不能为空
+rename-pattern-missing-n = 改名模式必须包含 {"{n}"}: { $pattern }
+rename-pattern-separator = 改名模式不能包含路径分隔符: { $pattern }
+rename-pattern-unknown-var = 改名模式中有未知的变量: { $pattern }
+destination-outside-target = 目标路径超出了目标文件夹: { $path }
+move-create-target = 创建目标文件夹失败: { $detail }
+move-source-missing = 源文件不存在
+move-content-changed = 目标文件内容已变化，未去重
+move-destination-is-dir = 目标是文件夹，无法覆盖
+move-destination-taken = 目标在生成计划后已被占用
+move-transfer-failed = 传输失败: { $detail }
+move-group-reverted = 同组文件 { $name } 传输失败，已回退
+move-group-skipped = 同组文件 { $name } 传输失败，未处理
+move-person-reverted = { $name } 放入主人物文件夹失败，此副本已回退
+copy-cancelled = 复制已取消
+copy-size-mismatch = 复制后大小不一致: 源文件 { $source } 字节，副本 { $copy } 字节
+copy-hash-mismatch = 复制后内容哈希不一致
+copy-destination-taken = 目标在复制期间已被占用
+reflink-unsupported = 当前平台不支持 reflink
+reflink-unsupported-fs = 文件系统不支持在这两个位置之间使用 reflink（{ $detail }），请改用复制
+
+## 撤销
+
+undo-remove-copy-failed = 删除副本失败: { $detail }
+undo-create-dir-failed = 创建原始目录失败: { $detail }
+undo-remove-moved-failed = 已复制回原处，但删除目标文件失败: { $detail }
+undo-restore-failed = 移回原处失败: { $detail }
+
+## 路径模板
+
+template-empty = 路径模板不能为空
+template-absolute = 路径模板必须是相对路径: { $template }
+template-dot-segment = 路径模板不能包含 "{ $text }"
+template-filename-not-last = {"{filename}"}/{"{stem}"} 只能出现在模板的最后一层
+template-nested-braces = 路径模板中的花括号不能嵌套
+template-extra-brace = 路径模板中有多余的 "{"}"}"
+template-unclosed-brace = 路径模板中有未闭合的 "{"{"}"
+template-keyword-pattern = {"{keyword:...}"} 需要指定匹配模式，例如 {"{keyword:Event|*}"}
+template-keyword-stars = 关键字模式只能包含一个 *: { $pattern }
+template-unknown-var = 未知的模板变量: {"{"}{ $name }{"}"}
+
+## 操作历史
+
+history-open = 创建操作历史目录失败: { $detail }
+history-read = 读取操作历史失败: { $detail }
+history-write = 保存操作历史失败: { $detail }
+history-lock = 锁定操作历史失败: { $detail }
+history-corrupt = 操作历史格式错误: { $detail }
+history-not-found = 找不到操作: { $id }
+undo-nothing = 没有可撤销的操作
+undo-already-undone = 操作已撤销: { $id }
+undo-blocked = 较新的操作改动过这些文件，请先撤销: { $ids }
+redo-not-undone = 操作未撤销，无法重做: { $id }
+redo-blocked = 之后的操作改动过这些文件，无法重做: { $ids }
+redo-nothing = 没有可以重做的文件（源文件已不存在或目标已被占用）
+
+## 命令行
+
+cli-error = 错误: { $detail }
+cli-warning = 警告: { $detail }
+cli-failure = 失败: { $source } -> { $destination }: { $detail }
+cli-no-data-dir = 无法确定数据目录，请使用 --data-dir 指定
+cli-current-dir = 无法获取当前目录: { $detail }
+cli-source-missing = 源文件夹不存在: { $path }
+cli-source-unreadable = 无法访问源文件夹: { $detail }
+cli-file-missing = 文件不存在: { $path }
+cli-found = 找到 { $count } 个图片文件
+cli-plan-summary = 共 { $count } 项传输，预计写入 { $bytes } 字节；同名冲突 { $conflicts }，源文件缺失 { $missing }，跨设备 { $cross_device }，权限问题 { $permission }
+cli-plan-written = 计划已写入 { $path }（{ $count } 个文件）
+cli-plan-write-failed = 写入计划文件失败: { $detail }
+cli-plan-read-failed = 读取计划文件失败: { $detail }
+cli-plan-corrupt = 计划文件格式错误: { $detail }
+cli-unknown-mode = 未知的传输方式: { $value }
+cli-unknown-conflict-policy = 未知的冲突处理策略: { $value }
+cli-unknown-multi-person = 未知的多人物策略: { $value }
+
+## 日志
+
+log-scan-found = 找到 { $count } 个图片文件，开始并行处理元数据...
+log-scan-cancelled = 扫描被用户取消，已处理 { $count } 张
+log-scan-removed = 增量扫描：{ $count } 个文件已不存在
+log-scan-finished = 扫描结束，复用索引 { $reused } 张，重新处理 { $processed } 张
+log-image-failed = 处理图片失败: { $detail }
+log-catalog-write-failed = 写入照片目录失败: { $detail }
+log-catalog-update-failed = 更新照片目录失败: { $detail }
+log-catalog-record-failed = 记录移动操作到照片目录失败: { $detail }
+log-catalog-migrated = 照片目录已迁移到 v{ $version }
+log-scan-index-corrupt = 扫描索引损坏，将重新建立: { $detail }
+log-thumbnail-cache-failed = 写入缩略图缓存失败: { $detail }
+log-watch-config-corrupt = 监视配置损坏: { $detail }
+log-watch-started = 开始监视文件夹: { $path }
+log-watch-stopped = 停止监视文件夹: { $path }
+log-watch-error = 文件监视错误: { $detail }
+log-watch-no-rule = 没有匹配的分类规则，保留在原处: { $path }
+log-watch-dry-run = [演练] { $path } -> { $destination }
+log-watch-moved = 自动分类: { $path } -> { $destination }
+log-messages-parse-failed = 消息目录 { $locale } 解析失败: { $detail }
+log-messages-duplicates = 消息目录 { $locale } 有重复的消息: { $detail }
+log-message-format-failed = 消息 { $key } 渲染出错: { $detail }
+log-history-save-failed = 保存操作历史失败: { $detail }
+log-cancel-scan = 收到取消扫描请求
+log-cancel-move = 收到取消移动请求
+log-cancel-job = 收到取消任务请求: { $id }
+log-move-cancelled = 移动已取消，剩余 { $count } 个文件未处理
+log-move-stopped = { $detail }，停止传输
+log-group-revert-failed = 回退同组文件失败 { $path }: { $detail }
+log-trashed = 已移到回收站: { $path } -> { $trashed }
+log-source-missing = 源文件不存在，跳过: { $path }
+log-transfer-failed = 传输文件失败 { $source } -> { $destination } ({ $mode }): { $detail }
+log-skipped-existing = 目标已存在，按策略跳过: { $source } -> { $destination }
+log-dedup-changed = 目标文件内容已变化，跳过去重: { $path }
+log-destination-is-dir = 目标是文件夹，无法覆盖: { $path }
+log-overwritten = 已覆盖: { $path }
+log-unstage-failed = 撤回暂存文件失败，文件保留在 { $path }: { $detail }
+log-staged-restored = 把暂存的文件放回原处: { $path }
+log-staged-removed = 删除未完成的暂存文件: { $path }
+log-temp-removed = 删除未完成的临时文件: { $path }
+log-clone-removed = 删除未完成的克隆: { $path }
+log-journal-remove-failed = 删除预写日志失败 { $path }: { $detail }
+log-journal-skipped = 跳过无法读取的预写日志 { $path }: { $detail }
+log-destination-taken = 目标已存在（计划生成后被占用），跳过: { $path }
+log-xattr-read-failed = 读取扩展属性失败 { $path }: { $detail }
+log-xattr-copy-failed = 复制扩展属性 { $name } 失败 { $path }: { $detail }
+log-undo-group-skipped = 同组文件无法撤销，跳过: { $path }
+log-restore-failed = 恢复文件失败: { $source } -> { $destination }: { $detail }
+log-undo-missing = 要恢复的文件不存在: { $path }
+log-undo-modified = 文件在操作后已被修改，跳过: { $path }
+log-undo-occupied = 原位置已被占用，跳过: { $path }
+log-remove-dir-failed = 删除空文件夹失败 { $path }: { $detail }
+log-history-corrupt = 跳过损坏的操作历史 { $path }: { $detail }
@@ -0,0 +1,497 @@
+// tag2dir 命令行工具
+// 与图形界面共用扫描、元数据和文件操作核心，适合在无界面的 NAS 或定时任务中运行
+// 所有结果以 JSON 输出到 stdout，日志和错误输出到 stderr
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use app_lib::catalog::Catalog;
+use app_lib::history::History;
+use app_lib::models::{
+    CompanionRules, ConflictPolicy, ImageInfo, MoveImageRequest, MoveOptions, MovePlan, MoveProgressEvent,
+    MoveReport, MultiPersonStrategy, NameRules, TransferMode, UndoFileResult, UndoOutcome,
+};
+use app_lib::file_ops::{self, ApplyControl};
+use app_lib::{companions, i18n, journal, scan_index, scanner, tr};
+
+/// 与图形界面相同的应用标识，数据目录与 GUI 共享
+const APP_IDENTIFIER: &str = "com.tag2dir.app";
+
+/// 退出码：运行出错
+const EXIT_ERROR: u8 = 1;
+/// 退出码：部分文件处理失败
+const EXIT_PARTIAL: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "tag2dir-cli", version, about = "按人物标签整理图片（命令行版）")]
+struct Cli {
+    /// 数据目录（照片目录数据库和操作历史），默认与图形界面相同
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// 输出格式化的 JSON
+    #[arg(long, global = true)]
+    pretty: bool,
+
+    /// 错误和日志信息的语言（zh-CN 或 en-US），默认跟随系统
+    #[arg(long, global = true)]
+    lang: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 扫描文件夹，输出每张图片的人物和关键字，并写入照片目录
+    Scan {
+        /// 源文件夹
+        source: String,
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+    /// 生成移动计划（不改动任何文件），列出每个文件的目标路径和冲突等问题
+    Plan {
+        /// 源文件夹
+        source: String,
+        /// 目标文件夹
+        #[arg(long, short)]
+        target: String,
+        #[command(flatten)]
+        scan: ScanArgs,
+        /// 跳过没有人物标签的图片（默认直接移动到目标文件夹）
+        #[arg(long)]
+        skip_unlabeled: bool,
+        /// 传输方式: move / copy / hardlink / symlink / reflink
+        #[arg(long, default_value = "move", value_parser = parse_transfer_mode)]
+        mode: TransferMode,
+        /// 多人物图片的处理策略: primary / copy_all / hardlink_all / combined / group
+        #[arg(long, default_value = "primary", value_parser = parse_multi_person)]
+        multi_person: MultiPersonStrategy,
+        /// group 策略下，人物数超过该值时放入 Group 文件夹
+        #[arg(long, default_value_t = 1)]
+        group_threshold: usize,
+        /// 同名冲突处理: rename / skip / overwrite / keep_larger / keep_newer / deduplicate
+        #[arg(long, default_value = "rename", value_parser = parse_conflict_policy)]
+        on_conflict: ConflictPolicy,
+        /// 改名模式，可用 {stem}、{n}、{ext}，默认 "{stem}_{n}{ext}"
+        #[arg(long)]
+        rename_pattern: Option<String>,
+        /// 目标路径模板，例如 "{person}/{year}/{month}"，默认 "{person}/{filename}"
+        #[arg(long)]
+        template: Option<String>,
+        /// 人物名称中非法字符（路径分隔符、Windows 保留字符等）的替换字符串
+        #[arg(long, default_value = "_")]
+        name_replacement: String,
+        /// 单层文件夹名的最大字节数
+        #[arg(long, default_value_t = 255)]
+        max_name_bytes: usize,
+        /// 复制（包括跨设备移动）后重新读取副本并校验哈希
+        #[arg(long)]
+        verify_checksum: bool,
+        /// 需要删除的文件（跨设备移动后的源文件、被覆盖或去重的文件）移到回收站
+        #[arg(long)]
+        use_trash: bool,
+        /// 同时复制的文件数上限（改名和链接不受影响），1 表示逐个传输
+        #[arg(long, short, default_value_t = 4)]
+        jobs: usize,
+        /// 计划写入的文件，默认输出到 stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// 执行移动计划
+    Apply {
+        /// 由 plan 生成的计划文件，按其中的目标路径原样执行
+        plan: PathBuf,
+        /// 覆盖计划中的并行复制数
+        #[arg(long, short)]
+        jobs: Option<usize>,
+    },
+    /// 撤销一次操作
+    Undo {
+        /// 操作 ID，默认撤销最近一次
+        id: Option<String>,
+    },
+    /// 重做一次已撤销的操作
+    Redo {
+        /// 操作 ID
+        id: String,
+    },
+    /// 列出操作历史（与图形界面共享）
+    History,
+    /// 恢复崩溃或断电时中断的操作；不指定 ID 时列出所有中断的操作
+    Recover {
+        /// 操作 ID
+        id: Option<String>,
+        /// 回滚已完成的部分，而不是继续完成
+        #[arg(long)]
+        rollback: bool,
+    },
+    /// 查看单个文件的元数据
+    Inspect {
+        /// 图片文件
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(clap::Args)]
+struct ScanArgs {
+    /// 包含子文件夹
+    #[arg(long, short)]
+    recursive: bool,
+    /// 按文件头识别图片，而不是扩展名
+    #[arg(long)]
+    detect_by_content: bool,
+    /// 不把同名的 RAW、XMP、JSON、Live Photo 视频等伴随文件与图片归为一组
+    #[arg(long)]
+    no_companions: bool,
+    /// 伴随文件扩展名（可多次指定），默认 xmp、json、aae、常见 RAW 格式、mov 和 mp4
+    #[arg(long = "companion-ext")]
+    companion_ext: Vec<String>,
+}
+
+impl ScanArgs {
+    fn companion_rules(&self) -> CompanionRules {
+        let defaults = CompanionRules::default();
+        CompanionRules {
+            enabled: !self.no_companions,
+            extensions: if self.companion_ext.is_empty() {
+                defaults.extensions
+            } else {
+                self.companion_ext.clone()
+            },
+        }
+    }
+}
+
+/// 撤销结果
+#[derive(Serialize)]
+struct CliUndoResult {
+    operation_id: String,
+    restored_count: usize,
+    total: usize,
+    /// 每个文件的处理结果
+    files: Vec<UndoFileResult>,
+}
+
+impl CliUndoResult {
+    fn new(operation_id: String, files: Vec<UndoFileResult>) -> Self {
+        Self {
+            operation_id,
+            restored_count: files
+                .iter()
+                .filter(|f| f.outcome == UndoOutcome::Restored)
+                .count(),
+            total: files.len(),
+            files,
+        }
+    }
+
+    /// 与操作历史相同的规则：目标文件已不存在也算了结，只有仍可再次撤销的文件算部分失败
+    fn settled(&self) -> bool {
+        self.files.iter().all(|f| f.outcome.is_settled())
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", tr!("cli-error", detail = e));
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, String> {
+    i18n::set_locale(cli.lang.as_deref());
+    let data_dir = match cli.data_dir {
+        Some(dir) => dir,
+        None => dirs::data_dir()
+            .ok_or_else(|| tr!("cli-no-data-dir"))?
+            .join(APP_IDENTIFIER),
+    };
+
+    match cli.command {
+        Command::Scan { source, scan } => {
+            let images = scan_folder(&source, &scan)?;
+            let mut catalog = Catalog::open(&data_dir)?;
+            let records: Vec<_> = images
+                .iter()
+                .map(|info| (info.clone(), scan_index::file_stamp(Path::new(&info.path))))
+                .collect();
+            catalog.upsert_images(&records)?;
+            print_json(&images, cli.pretty)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Plan {
+            source,
+            target,
+            scan,
+            skip_unlabeled,
+            mode,
+            multi_person,
+            group_threshold,
+            on_conflict,
+            rename_pattern,
+            template,
+            name_replacement,
+            max_name_bytes,
+            verify_checksum,
+            use_trash,
+            jobs,
+            output,
+        } => {
+            // 先校验模板，避免扫描完才报错
+            file_ops::parse_template(template.as_deref())?;
+            let requests: Vec<MoveImageRequest> = scan_folder(&source, &scan)?
+                .into_iter()
+                .filter(|info| !skip_unlabeled || info.selected_person.is_some())
+                .map(|info| MoveImageRequest {
+                    path: info.path,
+                    filename: info.filename,
+                    person: info.selected_person.unwrap_or_default(),
+                    persons: info.persons,
+                })
+                .collect();
+            let options = MoveOptions {
+                mode,
+                path_template: template,
+                multi_person,
+                group_threshold,
+                conflict_policy: on_conflict,
+                rename_pattern,
+                name_rules: NameRules {
+                    replacement: name_replacement,
+                    max_bytes: max_name_bytes,
+                    ..NameRules::default()
+                },
+                companions: scan.companion_rules(),
+                verify_checksum,
+                use_trash,
+                max_parallel: jobs,
+            };
+            // 目标文件夹可能尚未创建，不能 canonicalize，只补全为绝对路径
+            let target = std::env::current_dir()
+                .map_err(|e| tr!("cli-current-dir", detail = e.to_string()))?
+                .join(target);
+            let plan = file_ops::plan_move(&requests, &target.to_string_lossy(), &options)?;
+            eprintln!(
+                "{}",
+                tr!(
+                    "cli-plan-summary",
+                    count = plan.items.len(),
+                    bytes = plan.estimated_bytes,
+                    conflicts = plan.conflict_count,
+                    missing = plan.missing_count,
+                    cross_device = plan.cross_device_count,
+                    permission = plan.permission_count,
+                )
+            );
+            match output {
+                Some(path) => {
+                    let data = serde_json::to_vec_pretty(&plan).map_err(|e| e.to_string())?;
+                    fs::write(&path, data)
+                        .map_err(|e| tr!("cli-plan-write-failed", detail = e.to_string()))?;
+                    eprintln!(
+                        "{}",
+                        tr!(
+                            "cli-plan-written",
+                            path = path.display().to_string(),
+                            count = plan.items.len(),
+                        )
+                    );
+                }
+                None => print_json(&plan, cli.pretty)?,
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Apply { plan, jobs } => {
+            let data =
+                fs::read(&plan).map_err(|e| tr!("cli-plan-read-failed", detail = e.to_string()))?;
+            let mut plan: MovePlan = serde_json::from_slice(&data)
+                .map_err(|e| tr!("cli-plan-corrupt", detail = e.to_string()))?;
+            if let Some(jobs) = jobs {
+                plan.max_parallel = jobs;
+            }
+            let on_progress = |event: &MoveProgressEvent| {
+                if event.bytes_done == 0 {
+                    eprintln!("[{}/{}] {}", event.moved_count + 1, event.total, event.current_file);
+                }
+            };
+            let control = ApplyControl {
+                cancel: None,
+                on_progress: Some(&on_progress),
+            };
+            let report = file_ops::apply_plan(&plan, Some(&data_dir), control)?;
+            finish_move(&data_dir, report, cli.pretty)
+        }
+        Command::Undo { id } => {
+            let (log, files) = History::open(&data_dir)?.undo(id.as_deref())?;
+            let marked = Catalog::open(&data_dir).and_then(|mut c| c.mark_operation_undone(&log, &files));
+            if let Err(e) = marked {
+                warn(tr!("log-catalog-update-failed", detail = e.to_string()));
+            }
+            let result = CliUndoResult::new(log.id, files);
+            print_json(&result, cli.pretty)?;
+
+            if result.settled() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::from(EXIT_PARTIAL))
+            }
+        }
+        Command::Redo { id } => {
+            let report = History::open(&data_dir)?.redo(&id)?;
+            if let Err(e) = Catalog::open(&data_dir).and_then(|mut c| c.record_operation(&report.log)) {
+                warn(tr!("log-catalog-record-failed", detail = e.to_string()));
+            }
+            print_json(&report, cli.pretty)?;
+            if report.failures.is_empty() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::from(EXIT_PARTIAL))
+            }
+        }
+        Command::History => {
+            print_json(&History::open(&data_dir)?.list()?, cli.pretty)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Recover { id: None, .. } => {
+            print_json(&journal::interrupted(&data_dir), cli.pretty)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Recover {
+            id: Some(id),
+            rollback: true,
+        } => {
+            let (log, files) = journal::rollback(&data_dir, &id)?;
+            let result = CliUndoResult::new(log.id, files);
+            print_json(&result, cli.pretty)?;
+
+            if result.settled() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::from(EXIT_PARTIAL))
+            }
+        }
+        Command::Recover {
+            id: Some(id),
+            rollback: false,
+        } => {
+            let report = journal::resume(&data_dir, &id, ApplyControl::default())?;
+            finish_move(&data_dir, report, cli.pretty)
+        }
+        Command::Inspect { files } => {
+            let infos = files
+                .iter()
+                .map(|path| {
+                    if !path.is_file() {
+                        return Err(tr!("cli-file-missing", path = path.display().to_string()));
+                    }
+                    scanner::read_image_info(path).map_err(String::from)
+                })
+                .collect::<Result<Vec<ImageInfo>, String>>()?;
+            print_json(&infos, cli.pretty)?;
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// 记录执行结果到操作历史和照片目录并输出；有文件失败时返回部分失败的退出码
+fn finish_move(data_dir: &Path, report: MoveReport, pretty: bool) -> Result<ExitCode, String> {
+    History::open(data_dir)?.record(&report.log)?;
+    if let Err(e) = Catalog::open(data_dir).and_then(|mut c| c.record_operation(&report.log)) {
+        warn(tr!("log-catalog-record-failed", detail = e.to_string()));
+    }
+    for failure in &report.failures {
+        eprintln!(
+            "{}",
+            tr!(
+                "cli-failure",
+                source = failure.source.as_str(),
+                destination = failure.destination.as_str(),
+                detail = failure.message.as_str(),
+            )
+        );
+    }
+    print_json(&report, pretty)?;
+
+    if report.failures.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(EXIT_PARTIAL))
+    }
+}
+
+/// 扫描文件夹并并行读取元数据（不生成缩略图）
+fn scan_folder(source: &str, args: &ScanArgs) -> Result<Vec<ImageInfo>, String> {
+    if !Path::new(source).is_dir() {
+        return Err(tr!("cli-source-missing", path = source));
+    }
+    // 使用绝对路径，计划文件和操作历史不依赖当前工作目录
+    let source = fs::canonicalize(source)
+        .map_err(|e| tr!("cli-source-unreadable", detail = e.to_string()))?;
+    let files = scanner::scan_image_files(
+        &source.to_string_lossy(),
+        args.recursive,
+        args.detect_by_content,
+    );
+    eprintln!("{}", tr!("cli-found", count = files.len()));
+    let groups = companions::group_files(files, &args.companion_rules());
+
+    let pool = scanner::build_worker_pool()?;
+    let mut images: Vec<ImageInfo> = pool.install(|| {
+        groups
+            .par_iter()
+            .filter_map(|(path, companions)| match scanner::read_image_info(path) {
+                Ok(info) => Some(ImageInfo {
+                    companions: companions.iter().map(|c| c.to_string_lossy().to_string()).collect(),
+                    ..info
+                }),
+                Err(e) => {
+                    warn(e.to_string());
+                    None
+                }
+            })
+            .collect()
+    });
+    images.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(images)
+}
+
+fn parse_transfer_mode(value: &str) -> Result<TransferMode, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+        .map_err(|_| tr!("cli-unknown-mode", value = value))
+}
+
+fn parse_conflict_policy(value: &str) -> Result<ConflictPolicy, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase().replace('-', "_")))
+        .map_err(|_| tr!("cli-unknown-conflict-policy", value = value))
+}
+
+fn parse_multi_person(value: &str) -> Result<MultiPersonStrategy, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_lowercase().replace('-', "_")))
+        .map_err(|_| tr!("cli-unknown-multi-person", value = value))
+}
+
+fn warn(message: String) {
+    eprintln!("{}", tr!("cli-warning", detail = message));
+}
+
+fn print_json<T: Serialize>(value: &T, pretty: bool) -> Result<(), String> {
+    let text = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+    .map_err(|e| e.to_string())?;
+    println!("{}", text);
+    Ok(())
+}
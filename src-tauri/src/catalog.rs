@@ -0,0 +1,664 @@
+// 照片目录模块
+// 将扫描结果和移动记录保存到本地 SQLite 数据库，支持按人物/关键字组合查询，
+// 重启后仍可使用，也便于脚本直接读取
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::{tr, FluentValue};
+use crate::models::{
+    FaceRegion, FileStamp, ImageInfo, OperationLog, TransferMode, UndoFileResult, UndoOutcome,
+};
+
+/// 数据库文件名
+pub const CATALOG_FILE_NAME: &str = "catalog.db";
+
+/// 数据库结构迁移脚本，按顺序执行；PRAGMA user_version 记录已执行到第几个
+const MIGRATIONS: &[&str] = &[
+    // v1: 文件、人物、关键字、人脸区域、移动记录
+    r#"
+    CREATE TABLE files (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        filename TEXT NOT NULL,
+        size INTEGER,
+        mtime_ms INTEGER,
+        detected_format TEXT,
+        extension_mismatch INTEGER NOT NULL DEFAULT 0,
+        scanned_at TEXT NOT NULL
+    );
+    CREATE TABLE persons (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE file_persons (
+        file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+        person_id INTEGER NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+        PRIMARY KEY (file_id, person_id)
+    );
+    CREATE INDEX idx_file_persons_person ON file_persons(person_id);
+    CREATE TABLE keywords (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE file_keywords (
+        file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+        keyword_id INTEGER NOT NULL REFERENCES keywords(id) ON DELETE CASCADE,
+        PRIMARY KEY (file_id, keyword_id)
+    );
+    CREATE INDEX idx_file_keywords_keyword ON file_keywords(keyword_id);
+    CREATE TABLE regions (
+        id INTEGER PRIMARY KEY,
+        file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+        person_id INTEGER NOT NULL REFERENCES persons(id) ON DELETE CASCADE,
+        x REAL NOT NULL,
+        y REAL NOT NULL,
+        w REAL NOT NULL,
+        h REAL NOT NULL
+    );
+    CREATE INDEX idx_regions_file ON regions(file_id);
+    CREATE TABLE operations (
+        id TEXT PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        target_dir TEXT NOT NULL,
+        undone INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE moves (
+        id INTEGER PRIMARY KEY,
+        operation_id TEXT NOT NULL REFERENCES operations(id) ON DELETE CASCADE,
+        original_path TEXT NOT NULL,
+        new_path TEXT NOT NULL,
+        filename TEXT NOT NULL
+    );
+    CREATE INDEX idx_moves_operation ON moves(operation_id);
+    "#,
+];
+
+/// 目录查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CatalogQuery {
+    /// 必须同时包含的人物
+    pub all_persons: Vec<String>,
+    /// 至少包含其中一个的人物（为空时不限制）
+    pub any_persons: Vec<String>,
+    /// 不能包含的人物
+    pub exclude_persons: Vec<String>,
+    /// 必须同时包含的关键字
+    pub keywords: Vec<String>,
+    /// 文件夹路径（只查询这个文件夹及其子文件夹下的文件），末尾的分隔符可有可无
+    pub path_prefix: Option<String>,
+    /// 最多返回条数
+    pub limit: Option<u32>,
+    /// 跳过条数（分页）
+    pub offset: Option<u32>,
+}
+
+/// 目录中的文件记录
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogFile {
+    pub path: String,
+    pub filename: String,
+    pub size: Option<u64>,
+    pub mtime_ms: Option<i64>,
+    pub detected_format: Option<String>,
+    pub extension_mismatch: bool,
+    pub persons: Vec<String>,
+    pub keywords: Vec<String>,
+    pub regions: Vec<FaceRegion>,
+}
+
+/// 人物及其照片数量
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonCount {
+    pub name: String,
+    pub file_count: usize,
+}
+
+/// SQLite 照片目录
+pub struct Catalog {
+    conn: Connection,
+}
+
+impl Catalog {
+    /// 打开（或创建）数据目录中的目录数据库，并执行未完成的迁移
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| AppError::io("catalog-open", &e).with_path(data_dir))?;
+        let path = data_dir.join(CATALOG_FILE_NAME);
+        let conn = Connection::open(&path)
+            .map_err(|e| AppError::internal("catalog-open", e).with_path(&path))?;
+        // WAL 模式允许脚本在 GUI 写入时并发读取
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA busy_timeout = 5000;")
+            .map_err(db_err)?;
+        Self::with_connection(conn)
+    }
+
+    /// 在已打开的连接上启用外键并执行未完成的迁移
+    fn with_connection(conn: Connection) -> AppResult<Self> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;").map_err(db_err)?;
+        let mut catalog = Self { conn };
+        catalog.migrate()?;
+        Ok(catalog)
+    }
+
+    /// 依次执行尚未执行的迁移脚本
+    fn migrate(&mut self) -> AppResult<()> {
+        let version: usize = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(db_err)?;
+
+        for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction().map_err(db_err)?;
+            tx.execute_batch(sql).map_err(|e| {
+                let args = [
+                    ("version", FluentValue::from(i + 1)),
+                    ("detail", FluentValue::from(e.to_string())),
+                ];
+                AppError::with_args(ErrorKind::Internal, "catalog-migrate", &args)
+            })?;
+            tx.pragma_update(None, "user_version", i + 1).map_err(db_err)?;
+            tx.commit().map_err(db_err)?;
+            log::info!("{}", tr!("log-catalog-migrated", version = i + 1));
+        }
+        Ok(())
+    }
+
+    /// 写入一批扫描结果（已存在的文件会覆盖其人物、关键字和区域）
+    pub fn upsert_images(&mut self, images: &[(ImageInfo, Option<FileStamp>)]) -> AppResult<()> {
+        let scanned_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let tx = self.conn.transaction().map_err(db_err)?;
+        for (info, stamp) in images {
+            let file_id: i64 = tx
+                .query_row(
+                    "INSERT INTO files (path, filename, size, mtime_ms, detected_format, extension_mismatch, scanned_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT(path) DO UPDATE SET
+                        filename = excluded.filename,
+                        size = excluded.size,
+                        mtime_ms = excluded.mtime_ms,
+                        detected_format = excluded.detected_format,
+                        extension_mismatch = excluded.extension_mismatch,
+                        scanned_at = excluded.scanned_at
+                     RETURNING id",
+                    params![
+                        info.path,
+                        info.filename,
+                        stamp.map(|s| s.size as i64),
+                        stamp.map(|s| s.mtime_ms),
+                        info.detected_format,
+                        info.extension_mismatch,
+                        scanned_at,
+                    ],
+                    |row| row.get(0),
+                )
+                .map_err(db_err)?;
+
+            tx.execute("DELETE FROM file_persons WHERE file_id = ?1", [file_id])
+                .map_err(db_err)?;
+            tx.execute("DELETE FROM file_keywords WHERE file_id = ?1", [file_id])
+                .map_err(db_err)?;
+            tx.execute("DELETE FROM regions WHERE file_id = ?1", [file_id])
+                .map_err(db_err)?;
+
+            for person in &info.persons {
+                let person_id = ensure_name(&tx, "persons", person)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO file_persons (file_id, person_id) VALUES (?1, ?2)",
+                    [file_id, person_id],
+                )
+                .map_err(db_err)?;
+            }
+            for keyword in &info.keywords {
+                let keyword_id = ensure_name(&tx, "keywords", keyword)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO file_keywords (file_id, keyword_id) VALUES (?1, ?2)",
+                    [file_id, keyword_id],
+                )
+                .map_err(db_err)?;
+            }
+            for region in &info.regions {
+                let person_id = ensure_name(&tx, "persons", &region.name)?;
+                tx.execute(
+                    "INSERT INTO regions (file_id, person_id, x, y, w, h) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![file_id, person_id, region.x, region.y, region.w, region.h],
+                )
+                .map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// 删除已不存在的文件记录
+    pub fn remove_files(&mut self, paths: &[String]) -> AppResult<()> {
+        let tx = self.conn.transaction().map_err(db_err)?;
+        for path in paths {
+            tx.execute("DELETE FROM files WHERE path = ?1", [path])
+                .map_err(db_err)?;
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// 记录一次移动操作，并把目录中对应文件的路径更新为新位置
+    pub fn record_operation(&mut self, log: &OperationLog) -> AppResult<()> {
+        let tx = self.conn.transaction().map_err(db_err)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO operations (id, timestamp, target_dir, undone) VALUES (?1, ?2, ?3, 0)",
+            params![log.id, log.timestamp, log.target_dir],
+        )
+        .map_err(db_err)?;
+        tx.execute("DELETE FROM moves WHERE operation_id = ?1", [&log.id])
+            .map_err(db_err)?;
+        for record in &log.records {
+            tx.execute(
+                "INSERT INTO moves (operation_id, original_path, new_path, filename) VALUES (?1, ?2, ?3, ?4)",
+                params![log.id, record.original_path, record.new_path, record.filename],
+            )
+            .map_err(db_err)?;
+            // 复制/链接方式或被跳过时原文件仍在原处
+            if record.mode == TransferMode::Move && record.needs_undo() {
+                relocate_file(&tx, &record.original_path, &record.new_path)?;
+            }
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// 按撤销结果把已移回的文件路径改回原位置；所有文件都已处理时标记操作已撤销
+    pub fn mark_operation_undone(&mut self, log: &OperationLog, files: &[UndoFileResult]) -> AppResult<()> {
+        let tx = self.conn.transaction().map_err(db_err)?;
+        if files.iter().all(|f| f.outcome.is_settled()) {
+            tx.execute("UPDATE operations SET undone = 1 WHERE id = ?1", [&log.id])
+                .map_err(db_err)?;
+        }
+        let restored: HashSet<&str> = files
+            .iter()
+            .filter(|f| f.outcome == UndoOutcome::Restored)
+            .map(|f| f.new_path.as_str())
+            .collect();
+        for record in log.records.iter().filter(|r| r.mode == TransferMode::Move) {
+            if restored.contains(record.new_path.as_str()) {
+                relocate_file(&tx, &record.new_path, &record.original_path)?;
+            }
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// 按条件查询文件
+    pub fn query(&self, query: &CatalogQuery) -> AppResult<Vec<CatalogFile>> {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut args: Vec<String> = Vec::new();
+
+        let person_exists = "EXISTS (SELECT 1 FROM file_persons fp JOIN persons p ON p.id = fp.person_id
+                             WHERE fp.file_id = f.id AND p.name = ?)";
+        for person in &query.all_persons {
+            conditions.push(person_exists.to_string());
+            args.push(person.clone());
+        }
+        for person in &query.exclude_persons {
+            conditions.push(format!("NOT {}", person_exists));
+            args.push(person.clone());
+        }
+        if !query.any_persons.is_empty() {
+            let placeholders = vec!["?"; query.any_persons.len()].join(", ");
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM file_persons fp JOIN persons p ON p.id = fp.person_id
+                 WHERE fp.file_id = f.id AND p.name IN ({}))",
+                placeholders
+            ));
+            args.extend(query.any_persons.iter().cloned());
+        }
+        for keyword in &query.keywords {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM file_keywords fk JOIN keywords k ON k.id = fk.keyword_id
+                 WHERE fk.file_id = f.id AND k.name = ?)"
+                    .to_string(),
+            );
+            args.push(keyword.clone());
+        }
+        if let Some(prefix) = query.path_prefix.as_deref().filter(|p| !p.is_empty()) {
+            // 按完整的路径层级匹配："/photos/a" 不能匹配到 "/photos/ab" 下的文件
+            let dir = prefix.trim_end_matches(['/', '\\']);
+            conditions.push("(f.path = ? OR substr(f.path, 1, length(?)) IN (?, ?))".to_string());
+            args.push(dir.to_string());
+            args.push(format!("{}/", dir));
+            args.push(format!("{}/", dir));
+            args.push(format!("{}\\", dir));
+        }
+
+        let mut sql = String::from(
+            "SELECT f.id, f.path, f.filename, f.size, f.mtime_ms, f.detected_format, f.extension_mismatch
+             FROM files f",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY f.path");
+        sql.push_str(&format!(
+            " LIMIT {} OFFSET {}",
+            query.limit.map(i64::from).unwrap_or(-1),
+            query.offset.unwrap_or(0)
+        ));
+
+        let mut stmt = self.conn.prepare(&sql).map_err(db_err)?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), file_from_row)
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        rows.into_iter()
+            .map(|(file_id, mut file)| {
+                self.fill_details(file_id, &mut file)?;
+                Ok(file)
+            })
+            .collect()
+    }
+
+    /// 查询单个文件
+    pub fn get_file(&self, path: &str) -> AppResult<Option<CatalogFile>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, path, filename, size, mtime_ms, detected_format, extension_mismatch
+                 FROM files WHERE path = ?1",
+                [path],
+                file_from_row,
+            )
+            .optional()
+            .map_err(db_err)?;
+
+        match row {
+            Some((file_id, mut file)) => {
+                self.fill_details(file_id, &mut file)?;
+                Ok(Some(file))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// 列出所有人物及其照片数量（按数量降序）
+    pub fn persons(&self) -> AppResult<Vec<PersonCount>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT p.name, COUNT(fp.file_id) AS cnt FROM persons p
+                 JOIN file_persons fp ON fp.person_id = p.id
+                 GROUP BY p.id ORDER BY cnt DESC, p.name",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(PersonCount {
+                    name: row.get(0)?,
+                    file_count: row.get::<_, i64>(1)? as usize,
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(rows)
+    }
+
+    /// 补全文件的人物、关键字和区域
+    fn fill_details(&self, file_id: i64, file: &mut CatalogFile) -> AppResult<()> {
+        file.persons = self.names_for(
+            "SELECT p.name FROM file_persons fp JOIN persons p ON p.id = fp.person_id
+             WHERE fp.file_id = ?1 ORDER BY p.name",
+            file_id,
+        )?;
+        file.keywords = self.names_for(
+            "SELECT k.name FROM file_keywords fk JOIN keywords k ON k.id = fk.keyword_id
+             WHERE fk.file_id = ?1 ORDER BY k.name",
+            file_id,
+        )?;
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT p.name, r.x, r.y, r.w, r.h FROM regions r JOIN persons p ON p.id = r.person_id
+                 WHERE r.file_id = ?1 ORDER BY r.id",
+            )
+            .map_err(db_err)?;
+        file.regions = stmt
+            .query_map([file_id], |row| {
+                Ok(FaceRegion {
+                    name: row.get(0)?,
+                    x: row.get(1)?,
+                    y: row.get(2)?,
+                    w: row.get(3)?,
+                    h: row.get(4)?,
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    fn names_for(&self, sql: &str, file_id: i64) -> AppResult<Vec<String>> {
+        let mut stmt = self.conn.prepare_cached(sql).map_err(db_err)?;
+        let names = stmt
+            .query_map([file_id], |row| row.get(0))
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(names)
+    }
+}
+
+/// 从 files 表的一行构造记录（人物等明细稍后由 fill_details 补全）
+fn file_from_row(row: &rusqlite::Row) -> rusqlite::Result<(i64, CatalogFile)> {
+    Ok((
+        row.get(0)?,
+        CatalogFile {
+            path: row.get(1)?,
+            filename: row.get(2)?,
+            size: row.get::<_, Option<i64>>(3)?.map(|s| s as u64),
+            mtime_ms: row.get(4)?,
+            detected_format: row.get(5)?,
+            extension_mismatch: row.get(6)?,
+            persons: Vec::new(),
+            keywords: Vec::new(),
+            regions: Vec::new(),
+        },
+    ))
+}
+
+/// 取得 persons/keywords 表中某个名称的 id，不存在则插入
+fn ensure_name(conn: &Connection, table: &str, name: &str) -> AppResult<i64> {
+    conn.execute(
+        &format!("INSERT OR IGNORE INTO {} (name) VALUES (?1)", table),
+        [name],
+    )
+    .map_err(db_err)?;
+    conn.query_row(
+        &format!("SELECT id FROM {} WHERE name = ?1", table),
+        [name],
+        |row| row.get(0),
+    )
+    .map_err(db_err)
+}
+
+/// 文件被移动后更新其路径和文件名（目标路径已有旧记录时先删除）
+fn relocate_file(conn: &Connection, from: &str, to: &str) -> AppResult<()> {
+    let filename = Path::new(to)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    conn.execute("DELETE FROM files WHERE path = ?1 AND path <> ?2", [to, from])
+        .map_err(db_err)?;
+    conn.execute(
+        "UPDATE files SET path = ?1, filename = ?2 WHERE path = ?3",
+        [to, filename.as_str(), from],
+    )
+    .map_err(db_err)?;
+    Ok(())
+}
+
+fn db_err(e: rusqlite::Error) -> AppError {
+    AppError::internal("catalog-db", e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ImageStatus, MoveRecord};
+
+    fn image(path: &str, persons: &[&str], keywords: &[&str]) -> (ImageInfo, Option<FileStamp>) {
+        let info = ImageInfo {
+            id: path.to_string(),
+            path: path.to_string(),
+            filename: Path::new(path).file_name().unwrap().to_string_lossy().to_string(),
+            persons: persons.iter().map(|p| p.to_string()).collect(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            regions: Vec::new(),
+            thumbnail: String::new(),
+            selected_person: None,
+            status: ImageStatus::Scanned,
+            detected_format: None,
+            extension_mismatch: false,
+            companions: Vec::new(),
+        };
+        (info, Some(FileStamp { size: 1, mtime_ms: 0 }))
+    }
+
+    fn open_in_memory() -> Catalog {
+        Catalog::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn paths(catalog: &Catalog, query: CatalogQuery) -> Vec<String> {
+        catalog.query(&query).unwrap().into_iter().map(|f| f.path).collect()
+    }
+
+    #[test]
+    fn test_query() {
+        let mut catalog = open_in_memory();
+        catalog
+            .upsert_images(&[
+                image("/photos/a/1.jpg", &["Alice", "Bob"], &["beach"]),
+                image("/photos/a/sub/2.jpg", &["Alice"], &[]),
+                image("/photos/ab/3.jpg", &["Bob"], &["beach"]),
+                image("/photos/a%/4.jpg", &[], &[]),
+            ])
+            .unwrap();
+
+        let prefix = |p: &str| CatalogQuery {
+            path_prefix: Some(p.to_string()),
+            ..CatalogQuery::default()
+        };
+        assert_eq!(paths(&catalog, prefix("/photos/a")), ["/photos/a/1.jpg", "/photos/a/sub/2.jpg"]);
+        assert_eq!(paths(&catalog, prefix("/photos/a/")), ["/photos/a/1.jpg", "/photos/a/sub/2.jpg"]);
+        assert_eq!(paths(&catalog, prefix("/photos/a%")), ["/photos/a%/4.jpg"]);
+        assert_eq!(paths(&catalog, prefix("/photos/a/1.jpg")), ["/photos/a/1.jpg"]);
+        assert_eq!(paths(&catalog, prefix("")).len(), 4);
+        assert_eq!(paths(&catalog, prefix("/")).len(), 4);
+
+        let query = CatalogQuery {
+            all_persons: vec!["Alice".to_string()],
+            exclude_persons: vec!["Bob".to_string()],
+            ..CatalogQuery::default()
+        };
+        assert_eq!(paths(&catalog, query), ["/photos/a/sub/2.jpg"]);
+        let query = CatalogQuery {
+            any_persons: vec!["Bob".to_string(), "Carol".to_string()],
+            keywords: vec!["beach".to_string()],
+            limit: Some(1),
+            offset: Some(1),
+            ..CatalogQuery::default()
+        };
+        assert_eq!(paths(&catalog, query), ["/photos/ab/3.jpg"]);
+
+        let file = catalog.get_file("/photos/a/1.jpg").unwrap().unwrap();
+        assert_eq!(file.persons, ["Alice", "Bob"]);
+        assert_eq!(file.keywords, ["beach"]);
+        let counts: Vec<(String, usize)> =
+            catalog.persons().unwrap().into_iter().map(|p| (p.name, p.file_count)).collect();
+        assert_eq!(counts, [("Alice".to_string(), 2), ("Bob".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_record_and_undo_operation() {
+        let mut catalog = open_in_memory();
+        catalog.upsert_images(&[image("/in/a.jpg", &["Alice"], &[])]).unwrap();
+        let log = OperationLog {
+            id: "op".to_string(),
+            timestamp: String::new(),
+            target_dir: "/out".to_string(),
+            records: vec![MoveRecord {
+                original_path: "/in/a.jpg".to_string(),
+                new_path: "/out/Alice/a.jpg".to_string(),
+                filename: "a.jpg".to_string(),
+                mode: TransferMode::Move,
+                dest_stamp: None,
+                conflict: None,
+                dest_hash: None,
+                group: None,
+            }],
+            created_dirs: Vec::new(),
+            verify_checksum: false,
+            use_trash: false,
+            max_parallel: 1,
+        };
+        catalog.record_operation(&log).unwrap();
+        assert!(catalog.get_file("/in/a.jpg").unwrap().is_none());
+        assert_eq!(catalog.get_file("/out/Alice/a.jpg").unwrap().unwrap().persons, ["Alice"]);
+
+        let result = |outcome| UndoFileResult {
+            original_path: "/in/a.jpg".to_string(),
+            new_path: "/out/Alice/a.jpg".to_string(),
+            outcome,
+            error: None,
+            error_key: None,
+            error_kind: None,
+        };
+        let undone = |catalog: &Catalog| -> bool {
+            catalog
+                .conn
+                .query_row("SELECT undone FROM operations WHERE id = 'op'", [], |row| row.get(0))
+                .unwrap()
+        };
+        // 未能移回的文件保持在新位置，操作也不算撤销
+        catalog.mark_operation_undone(&log, &[result(UndoOutcome::SkippedOccupied)]).unwrap();
+        assert!(catalog.get_file("/out/Alice/a.jpg").unwrap().is_some());
+        assert!(!undone(&catalog));
+        catalog.mark_operation_undone(&log, &[result(UndoOutcome::Restored)]).unwrap();
+        assert!(catalog.get_file("/in/a.jpg").unwrap().is_some());
+        assert!(undone(&catalog));
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        // 用 v1 的结构建库并写入数据，再按当前版本打开
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO files (path, filename, scanned_at) VALUES ('/photos/a.jpg', 'a.jpg', '')",
+            [],
+        )
+        .unwrap();
+
+        let catalog = Catalog::with_connection(conn).unwrap();
+        let version: usize = catalog
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        assert!(catalog.get_file("/photos/a.jpg").unwrap().is_some());
+
+        // 全新的库从头执行所有迁移
+        let catalog = open_in_memory();
+        let version: usize = catalog
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
@@ -1,148 +1,365 @@
 // Tauri 命令模块
 // 暴露给前端调用的所有命令，处理扫描、移动、撤销等操作
 
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
 use parking_lot::Mutex;
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::catalog::{Catalog, CatalogFile, CatalogQuery, PersonCount};
+use crate::companions;
+use crate::error::{AppError, AppResult, ErrorKind};
 use crate::file_ops;
+use crate::history::{History, HistorySummary};
+use crate::i18n::{self, tr, LocaleInfo, LocaleSetting};
+use crate::jobs::{self, JobHandle, JobInfo, JobKind, JobRegistry};
+use crate::journal::{self, InterruptedOperation};
 use crate::models::*;
+use crate::progress::{self, Batcher, Throttle};
+use crate::sanitize;
+use crate::scan_index::{self, ScanIndex};
 use crate::scanner;
+use crate::watcher::{self, WatchConfig, WatchHandle, WatchStatus};
 
 /// 全局应用状态
 pub struct AppState {
-    /// 最近一次操作日志（用于撤销）
-    pub last_operation: Mutex<Option<OperationLog>>,
-    /// 是否正在扫描
-    pub scanning: Mutex<bool>,
-    /// 取消扫描标志位（原子操作，跨线程安全，无需 Mutex）
-    pub cancel_scan: Arc<AtomicBool>,
+    /// 扫描、移动等后台任务（每个任务有自己的 ID、进度和取消标志）
+    pub jobs: Arc<JobRegistry>,
+    /// SQLite 照片目录（首次使用时打开）
+    pub catalog: Mutex<Option<Catalog>>,
+    /// 正在运行的监视文件夹任务
+    pub watcher: Mutex<Option<WatchHandle>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
-            last_operation: Mutex::new(None),
-            scanning: Mutex::new(false),
-            cancel_scan: Arc::new(AtomicBool::new(false)),
+            jobs: Arc::new(JobRegistry::new()),
+            catalog: Mutex::new(None),
+            watcher: Mutex::new(None),
         }
     }
 }
 
+/// 扫描时每累积多少条结果写一次照片目录
+const CATALOG_BATCH_SIZE: usize = 500;
+
+/// 路径模板预览默认渲染的图片数量
+const PREVIEW_SAMPLE_SIZE: usize = 20;
+
+/// 应用数据目录（照片目录、操作历史和预写日志都保存在这里）
+fn app_data_dir(app: &AppHandle) -> AppResult<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::internal("app-data-dir", e))
+}
+
+/// 应用配置目录（监视配置保存在这里）
+fn app_config_dir(app: &AppHandle) -> AppResult<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .map_err(|e| AppError::internal("app-config-dir", e))
+}
+
+/// 后台线程启动失败或 panic
+fn task_failed(key: &'static str, err: tokio::task::JoinError) -> AppError {
+    AppError::internal(key, err)
+}
+
+fn watch_not_running() -> AppError {
+    AppError::new(ErrorKind::NotFound, "watch-not-running")
+}
+
+/// 打开应用数据目录下的操作历史
+fn open_history(app: &AppHandle) -> AppResult<History> {
+    History::open(&app_data_dir(app)?)
+}
+
+/// 在照片目录上执行操作；数据库在首次使用时打开
+fn with_catalog<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut Catalog) -> AppResult<T>,
+) -> AppResult<T> {
+    let state = app.state::<AppState>();
+    let mut guard = state.catalog.lock();
+    if guard.is_none() {
+        *guard = Some(Catalog::open(&app_data_dir(app)?)?);
+    }
+    f(guard.as_mut().expect("catalog opened above"))
+}
+
 /// 扫描图片命令
-/// 异步递归扫描指定文件夹，通过事件流式推送结果到前端
+/// 异步递归扫描指定文件夹：先查找图片（进度通过 "scan-discovery" 事件推送），再处理图片；
+/// 扫描结果通过 "scan-batch" 事件分批推送，进度通过 "scan-progress" 事件按固定间隔推送，
+/// `events` 指定两者的发送频率，以及是否边查找边处理
 #[tauri::command]
 pub async fn scan_images(
     app: AppHandle,
     source_dir: String,
     include_subdirs: bool,
-) -> Result<ScanStats, String> {
-    // 检查是否已在扫描
-    let state = app.state::<AppState>();
-    {
-        let mut scanning = state.scanning.lock();
-        if *scanning {
-            return Err("已有扫描任务在进行中".to_string());
-        }
-        *scanning = true;
-    }
-    // 重置取消标志
-    state.cancel_scan.store(false, Ordering::Relaxed);
-    let cancel_flag = state.cancel_scan.clone();
+    detect_by_content: Option<bool>,
+    incremental: Option<bool>,
+    companions: Option<CompanionRules>,
+    events: Option<ScanEventOptions>,
+) -> AppResult<ScanStats> {
+    // 登记扫描任务；同时只能有一个扫描任务
+    let job = Arc::new(app.state::<AppState>().jobs.start(JobKind::Scan)?);
+    let incremental = incremental.unwrap_or(false);
+    let detect_by_content = detect_by_content.unwrap_or(false);
+
+    // 扫描索引保存在应用数据目录中
+    let data_dir = app_data_dir(&app)?;
 
     // 在后台线程中执行扫描
     let app_handle = app.clone();
-    let result = tokio::task::spawn_blocking(move || -> Result<ScanStats, String> {
+    let scan_job = job.clone();
+    let result = tokio::task::spawn_blocking(move || -> AppResult<ScanStats> {
         use rayon::prelude::*;
-
-        // 1. 扫描图片文件列表
-        let files = scanner::scan_image_files(&source_dir, include_subdirs);
-        let total = files.len();
-
-        log::info!("找到 {} 个图片文件，开始并行处理元数据...", total);
+        let job = scan_job;
+        let job_id = job.id().to_string();
+        let cancel_flag = job.cancel_flag();
+
+        // 加载扫描索引：增量模式下未变化的文件直接复用索引结果
+        let mut index = ScanIndex::load(&data_dir);
+        // 已找到的图片数量，即需要处理的总数；边查找边处理时查找结束前还会增加
+        let discovered = AtomicUsize::new(0);
+        let discovering = AtomicBool::new(true);
+        let scanned_count = AtomicUsize::new(0);
+        let index_updates = Mutex::new(Vec::new());
+        let catalog_batch: Mutex<Vec<(ImageInfo, Option<FileStamp>)>> =
+            Mutex::new(Vec::new());
+        let flush_catalog = |batch: Vec<(ImageInfo, Option<FileStamp>)>| {
+            if let Err(e) = with_catalog(&app_handle, |catalog| catalog.upsert_images(&batch)) {
+                log::warn!("{}", tr!("log-catalog-write-failed", detail = e.to_string()));
+            }
+        };
+        let reused_count = AtomicUsize::new(0);
+        let failed_count = AtomicUsize::new(0);
+
+        // 扫描结果分批发送，进度按间隔发送
+        let events = events.unwrap_or_default();
+        let batcher: Batcher<Result<ImageInfo, ScanFailure>> = Batcher::new(
+            events.batch_size,
+            Duration::from_millis(events.batch_interval_ms),
+        );
+        let throttle = Throttle::new(Duration::from_millis(events.progress_interval_ms));
+        let started = Instant::now();
+        let emit_batch = |batch: Vec<Result<ImageInfo, ScanFailure>>| {
+            if batch.is_empty() {
+                return;
+            }
+            let mut event = ScanBatchEvent {
+                job_id: job_id.clone(),
+                images: Vec::new(),
+                failures: Vec::new(),
+            };
+            for result in batch {
+                match result {
+                    Ok(info) => event.images.push(info),
+                    Err(failure) => event.failures.push(failure),
+                }
+            }
+            let _ = app_handle.emit("scan-batch", &event);
+        };
+        let emit_progress = |scanned: usize, done: bool, cancelled: bool| {
+            let elapsed = started.elapsed();
+            let total = discovered.load(Ordering::SeqCst);
+            let discovering = discovering.load(Ordering::SeqCst);
+            // 总数还在增加时无法估算剩余时间
+            let eta = match discovering {
+                true => None,
+                false => progress::eta(elapsed, scanned, total),
+            };
+            let event = ScanProgressEvent {
+                job_id: job_id.clone(),
+                scanned,
+                total,
+                discovering,
+                elapsed_ms: elapsed.as_millis() as u64,
+                eta_ms: eta.map(|eta| eta.as_millis() as u64),
+                done,
+                cancelled,
+            };
+            let _ = app_handle.emit("scan-progress", &event);
+        };
+        let discovery_throttle = Throttle::new(Duration::from_millis(events.progress_interval_ms));
+        let emit_discovery = |dirs_scanned: usize, current_dir: &str, done: bool| {
+            let event = ScanDiscoveryEvent {
+                job_id: job_id.clone(),
+                dirs_scanned,
+                found: discovered.load(Ordering::SeqCst),
+                current_dir: current_dir.to_string(),
+                done,
+            };
+            let _ = app_handle.emit("scan-discovery", &event);
+        };
 
         // 用于统计人物（并行安全容器）
         let person_buckets = dashmap::DashSet::new();
-        let scanned_count = std::sync::atomic::AtomicUsize::new(0);
-
-        // 限制并行线程数，避免 100% 占用导致电脑卡顿
-        // 设置为逻辑核心数的一半，但至少 1 个线程，最多 6 个线程
-        let num_cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
-        let worker_threads = (num_cpus / 2).clamp(1, 6);
-        
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(worker_threads)
-            .build()
-            .map_err(|e| format!("创建线程池失败: {}", e))?;
-
-        pool.install(|| {
-            files.into_par_iter().for_each(|path| {
-                // 检查取消标志
-                if cancel_flag.load(Ordering::Relaxed) {
-                    return;
-                }
-
-                let result = scanner::process_single_image(&path);
-                let current_count = scanned_count.fetch_add(1, Ordering::SeqCst) + 1;
 
-                match result {
-                    Ok(info) => {
-                        for person in &info.persons {
-                            person_buckets.insert(person.clone());
-                        }
-                        let done = current_count >= total;
-                        let event = ScanProgressEvent {
-                            scanned: current_count,
-                            image: Some(info),
-                            done,
-                            cancelled: false,
-                            error: None,
-                        };
-                        let _ = app_handle.emit("scan-progress", &event);
-                    }
-                    Err(e) => {
-                        log::warn!("处理图片失败: {}", e);
-                        let done = current_count >= total;
-                        let event = ScanProgressEvent {
-                            scanned: current_count,
-                            image: None,
-                            done,
-                            cancelled: false,
-                            error: Some(e),
-                        };
-                        let _ = app_handle.emit("scan-progress", &event);
+        // 1. 查找图片文件：每查找完一个目录就把其中的图片交给 `sink`，
+        // 同名的 RAW、XMP 等伴随文件归入图片，不单独显示。返回找到的所有文件
+        let companion_rules = companions.unwrap_or_default();
+        let discover = |sink: &mut dyn FnMut(Vec<(PathBuf, Vec<PathBuf>)>)| {
+            let mut seen_paths = HashSet::new();
+            let mut dirs_scanned = 0;
+            let mut current_dir = String::new();
+            scanner::discover_image_files(
+                &source_dir,
+                include_subdirs,
+                detect_by_content,
+                cancel_flag,
+                |dir, files| {
+                    dirs_scanned += 1;
+                    current_dir = dir.to_string_lossy().to_string();
+                    seen_paths.extend(files.iter().map(|p| p.to_string_lossy().to_string()));
+                    let groups = companions::group_files(files, &companion_rules);
+                    let count = groups.len();
+                    let found = discovered.fetch_add(count, Ordering::SeqCst) + count;
+                    job.progress(scanned_count.load(Ordering::SeqCst), found);
+                    if discovery_throttle.ready() {
+                        emit_discovery(dirs_scanned, &current_dir, false);
                     }
+                    sink(groups);
+                },
+            );
+            discovering.store(false, Ordering::SeqCst);
+            emit_discovery(dirs_scanned, &current_dir, true);
+            let total = discovered.load(Ordering::SeqCst);
+            log::info!("{}", tr!("log-scan-found", count = total));
+            seen_paths
+        };
+
+        // 2. 处理单张图片：读取元数据、生成缩略图，结果分批发送
+        let process = |(path, companions): (PathBuf, Vec<PathBuf>)| {
+            // 检查取消标志
+            if cancel_flag.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let stamp = scan_index::file_stamp(&path);
+            let cached = match stamp {
+                Some(stamp) if incremental => index.cached_image(&path, stamp),
+                _ => None,
+            };
+            let result = match cached {
+                Some(info) => {
+                    reused_count.fetch_add(1, Ordering::Relaxed);
+                    Ok(info)
                 }
+                None => scanner::process_single_image(&path).inspect(|info| {
+                    if let Some(stamp) = stamp {
+                        index_updates.lock().push(index.make_entry(info, stamp, detect_by_content));
+                    }
+                }),
+            };
+            let current_count = scanned_count.fetch_add(1, Ordering::SeqCst) + 1;
+            job.progress(current_count, discovered.load(Ordering::SeqCst));
+            let result = result.map(|info| ImageInfo {
+                companions: companions
+                    .iter()
+                    .map(|c| c.to_string_lossy().to_string())
+                    .collect(),
+                ..info
             });
-        });
 
-        // 检查是否是被取消的
-        let final_count = scanned_count.load(Ordering::SeqCst);
-        if cancel_flag.load(Ordering::Relaxed) {
-            log::info!("扫描被用户取消，已处理 {} 张", final_count);
-            let event = ScanProgressEvent {
-                scanned: final_count,
-                image: None,
-                done: true,
-                cancelled: true,
-                error: None,
-            };
-            let _ = app_handle.emit("scan-progress", &event);
-        } else if total == 0 {
-            // 如果 files 为空，也发送完成事件
-            let event = ScanProgressEvent {
-                scanned: 0,
-                image: None,
-                done: true,
-                cancelled: false,
-                error: None,
+            let item = match result {
+                Ok(info) => {
+                    for person in &info.persons {
+                        person_buckets.insert(person.clone());
+                    }
+                    // 照片目录不保存缩略图
+                    let record = ImageInfo {
+                        thumbnail: String::new(),
+                        ..info.clone()
+                    };
+                    let full_batch = {
+                        let mut batch = catalog_batch.lock();
+                        batch.push((record, stamp));
+                        (batch.len() >= CATALOG_BATCH_SIZE).then(|| std::mem::take(&mut *batch))
+                    };
+                    if let Some(batch) = full_batch {
+                        flush_catalog(batch);
+                    }
+                    Ok(info)
+                }
+                Err(e) => {
+                    log::warn!("{}", tr!("log-image-failed", detail = e.to_string()));
+                    failed_count.fetch_add(1, Ordering::Relaxed);
+                    Err(ScanFailure {
+                        path: path.to_string_lossy().to_string(),
+                        error: e.to_string(),
+                    })
+                }
             };
-            let _ = app_handle.emit("scan-progress", &event);
+            if let Some(batch) = batcher.push(item) {
+                emit_batch(batch);
+            }
+            if throttle.ready() {
+                emit_progress(current_count, false, false);
+            }
+        };
+
+        // 默认查找结束后再处理；边查找边处理时查找和处理在两个线程中同时进行
+        let pool = scanner::build_worker_pool()?;
+        let seen_paths = if events.process_during_discovery {
+            let (sender, receiver) = mpsc::channel();
+            std::thread::scope(|scope| {
+                let discovery = scope.spawn(move || {
+                    discover(&mut |groups| {
+                        for group in groups {
+                            let _ = sender.send(group);
+                        }
+                    })
+                });
+                pool.install(|| receiver.into_iter().par_bridge().for_each(process));
+                discovery.join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+        } else {
+            let mut groups = Vec::new();
+            let seen_paths = discover(&mut |found| groups.extend(found));
+            pool.install(|| groups.into_par_iter().for_each(process));
+            seen_paths
+        };
+
+        // 发送剩余的结果，再发送一次完成事件（正常完成、取消和没有文件时都会发送）
+        emit_batch(batcher.take());
+        let final_count = scanned_count.load(Ordering::SeqCst);
+        let cancelled = cancel_flag.load(Ordering::Relaxed);
+        if cancelled {
+            log::info!("{}", tr!("log-scan-cancelled", count = final_count));
+        }
+        emit_progress(final_count, true, cancelled);
+
+        // 更新扫描索引和照片目录；只有完整扫描后才能确定哪些文件已被删除
+        flush_catalog(catalog_batch.into_inner());
+        index.apply_updates(index_updates.into_inner());
+        if !cancel_flag.load(Ordering::Relaxed) {
+            let removed = index.prune_missing(&source_dir, include_subdirs, &seen_paths);
+            if let Err(e) = with_catalog(&app_handle, |catalog| catalog.remove_files(&removed)) {
+                log::warn!("{}", tr!("log-catalog-update-failed", detail = e.to_string()));
+            }
+            if incremental && !removed.is_empty() {
+                log::info!("{}", tr!("log-scan-removed", count = removed.len()));
+                let event = ScanRemovedEvent {
+                    job_id: job_id.clone(),
+                    paths: removed,
+                };
+                let _ = app_handle.emit("scan-removed", &event);
+            }
         }
+        if let Err(e) = index.save() {
+            log::warn!("{}", e);
+        }
+        let reused = reused_count.load(Ordering::Relaxed);
+        log::info!(
+            "{}",
+            tr!("log-scan-finished", reused = reused, processed = final_count - reused)
+        );
 
         let mut person_names: Vec<String> = person_buckets.into_iter().collect();
         person_names.sort();
@@ -151,24 +368,22 @@ pub async fn scan_images(
             total_images: final_count,
             person_count: person_names.len(),
             person_names,
+            failed_count: failed_count.into_inner(),
         })
     })
     .await
-    .map_err(|e| format!("扫描任务失败: {}", e))??;
+    .map_err(|e| task_failed("task-scan-failed", e))
+    .and_then(|r| r);
+    job.finish(&result, job.is_cancelled());
 
-    // 重置扫描状态
-    let state = app.state::<AppState>();
-    *state.scanning.lock() = false;
-
-    Ok(result)
+    result
 }
 
 /// 取消正在进行的扫描
 #[tauri::command]
-pub async fn cancel_scan(app: AppHandle) -> Result<(), String> {
-    let state = app.state::<AppState>();
-    state.cancel_scan.store(true, Ordering::Relaxed);
-    log::info!("收到取消扫描请求");
+pub async fn cancel_scan(app: AppHandle) -> AppResult<()> {
+    app.state::<AppState>().jobs.cancel_kind(JobKind::Scan);
+    log::info!("{}", tr!("log-cancel-scan"));
     Ok(())
 }
 
@@ -179,104 +394,471 @@ pub async fn move_images(
     app: AppHandle,
     images: Vec<MoveImageRequest>,
     target_dir: String,
-) -> Result<MoveResult, String> {
-    let app_handle = app.clone();
+    options: Option<MoveOptions>,
+) -> AppResult<MoveResult> {
+    let plan = plan_move(images, target_dir, options).await?;
+    apply_plan(app, plan).await
+}
 
+/// 生成移动计划（不改动磁盘）
+/// 返回每个文件的源 → 目标路径以及同名冲突、源文件缺失、跨设备、权限问题和预计写入字节数
+#[tauri::command]
+pub async fn plan_move(
+    images: Vec<MoveImageRequest>,
+    target_dir: String,
+    options: Option<MoveOptions>,
+) -> AppResult<MovePlan> {
+    tokio::task::spawn_blocking(move || {
+        file_ops::plan_move(&images, &target_dir, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| task_failed("task-plan-failed", e))?
+}
+
+/// 按 `plan_move` 生成的计划执行移动
+/// 逐个文件推送进度（跨设备复制时包含字节数），可通过 `cancel_move` 取消
+#[tauri::command]
+pub async fn apply_plan(app: AppHandle, plan: MovePlan) -> AppResult<MoveResult> {
+    let job = Arc::new(app.state::<AppState>().jobs.start(JobKind::Move)?);
+    let data_dir = app_data_dir(&app)?;
+
+    let app_handle = app.clone();
+    let move_job = job.clone();
     let result = tokio::task::spawn_blocking(move || {
-        let total = images.len();
+        let job = move_job;
+        let total = plan.items.len();
+        let on_progress = |event: &MoveProgressEvent| emit_move_progress(&app_handle, &job, event);
+        let control = file_ops::ApplyControl {
+            cancel: Some(job.cancel_flag()),
+            on_progress: Some(&on_progress),
+        };
+
+        // 执行批量移动（边移动边写预写日志，崩溃后可恢复）
+        let report = file_ops::apply_plan(&plan, Some(&data_dir), control)?;
+
+        // 推送完成事件
+        emit_move_done(&app_handle, &job, &report, total);
+        Ok::<MoveReport, AppError>(report)
+    })
+    .await
+    .map_err(|e| task_failed("task-move-failed", e))
+    .and_then(|r| r)
+    .and_then(|report| finish_move(&app, report));
+    let cancelled = result.as_ref().is_ok_and(|r| r.cancelled);
+    job.finish(&result, cancelled);
+
+    result
+}
+
+/// 推送移动进度并更新任务进度
+fn emit_move_progress(app: &AppHandle, job: &JobHandle, event: &MoveProgressEvent) {
+    job.progress(event.moved_count, event.total);
+    let event = MoveProgressEvent {
+        job_id: job.id().to_string(),
+        ..event.clone()
+    };
+    let _ = app.emit("move-progress", &event);
+}
+
+/// 推送移动完成事件
+fn emit_move_done(app: &AppHandle, job: &JobHandle, report: &MoveReport, total: usize) {
+    let event = MoveProgressEvent {
+        job_id: String::new(),
+        moved_count: report.log.records.len() + report.failures.len(),
+        total,
+        current_file: String::new(),
+        bytes_done: 0,
+        bytes_total: 0,
+        done: true,
+        cancelled: report.cancelled,
+        error: None,
+    };
+    emit_move_progress(app, job, &event);
+}
 
-        // 转换为内部格式
-        let move_items: Vec<(String, String, String)> = images
+/// 移动结束后写入照片目录和操作历史（取消时已完成的部分同样可以撤销）
+fn finish_move(app: &AppHandle, report: MoveReport) -> AppResult<MoveResult> {
+    if let Err(e) = with_catalog(app, |catalog| catalog.record_operation(&report.log)) {
+        log::warn!("{}", tr!("log-catalog-record-failed", detail = e.to_string()));
+    }
+    let history = open_history(app)?;
+    history.record(&report.log)?;
+    Ok(MoveResult::from_report(report, history.has_undo()))
+}
+
+/// 取消正在进行的移动；当前文件处理完（或复制中断）后停止
+#[tauri::command]
+pub async fn cancel_move(app: AppHandle) -> AppResult<()> {
+    app.state::<AppState>().jobs.cancel_kind(JobKind::Move);
+    log::info!("{}", tr!("log-cancel-move"));
+    Ok(())
+}
+
+/// 列出所有后台任务（正在运行的和最近结束的）
+#[tauri::command]
+pub async fn list_jobs(app: AppHandle) -> AppResult<Vec<JobInfo>> {
+    Ok(app.state::<AppState>().jobs.list())
+}
+
+/// 查询任务的状态、进度和结果
+#[tauri::command]
+pub async fn get_job(app: AppHandle, job_id: String) -> AppResult<JobInfo> {
+    app.state::<AppState>()
+        .jobs
+        .get(&job_id)
+        .ok_or_else(|| jobs::job_not_found(&job_id))
+}
+
+/// 按任务 ID 取消任务
+#[tauri::command]
+pub async fn cancel_job(app: AppHandle, job_id: String) -> AppResult<()> {
+    app.state::<AppState>().jobs.cancel(&job_id)?;
+    log::info!("{}", tr!("log-cancel-job", id = job_id.as_str()));
+    Ok(())
+}
+
+/// 预览路径模板：对前若干张图片渲染目标路径，不移动任何文件
+/// 模板或文件夹名清理规则无效时直接返回错误信息
+#[tauri::command]
+pub async fn preview_path_template(
+    template: String,
+    images: Vec<MoveImageRequest>,
+    target_dir: String,
+    sample: Option<usize>,
+    name_rules: Option<NameRules>,
+) -> AppResult<Vec<PathPreview>> {
+    let template = file_ops::parse_template(Some(&template))?;
+    let sample = sample.unwrap_or(PREVIEW_SAMPLE_SIZE);
+    let rules = name_rules.unwrap_or_default();
+    sanitize::validate_rules(&rules)?;
+
+    tokio::task::spawn_blocking(move || {
+        let target_path = std::path::Path::new(&target_dir);
+        images
             .iter()
-            .map(|img| (img.path.clone(), img.filename.clone(), img.person.clone()))
-            .collect();
+            .take(sample)
+            .map(|img| {
+                let source = std::path::Path::new(&img.path);
+                let destination = file_ops::destination_path(
+                    template.as_ref(),
+                    target_path,
+                    source,
+                    &img.person,
+                    &rules,
+                );
+                PathPreview {
+                    source: img.path.clone(),
+                    destination: destination.to_string_lossy().to_string(),
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| task_failed("task-preview-failed", e))
+}
 
-        // 推送初始进度
-        let _ = app_handle.emit(
-            "move-progress",
-            &MoveProgressEvent {
-                moved_count: 0,
-                total,
-                current_file: String::new(),
-                done: false,
-                error: None,
-            },
-        );
+/// 列出操作历史（从新到旧）
+#[tauri::command]
+pub async fn list_history(app: AppHandle) -> AppResult<Vec<HistorySummary>> {
+    let history = tokio::task::spawn_blocking(move || open_history(&app)?.list())
+        .await
+        .map_err(|e| task_failed("task-history-failed", e))??;
+    Ok(history)
+}
+
+/// 撤销移动操作；未指定 ID 时撤销最近一次
+#[tauri::command]
+pub async fn undo_move(app: AppHandle, operation_id: Option<String>) -> AppResult<UndoResult> {
+    let app_handle = app.clone();
+    let (log, files, has_undo) = tokio::task::spawn_blocking(move || {
+        let history = open_history(&app_handle)?;
+        let (log, files) = history.undo(operation_id.as_deref())?;
+        Ok::<_, AppError>((log, files, history.has_undo()))
+    })
+    .await
+    .map_err(|e| task_failed("task-undo-failed", e))??;
 
-        // 执行批量移动
-        let operation_log = file_ops::move_images(&move_items, &target_dir)?;
-        let moved = operation_log.records.len();
+    if let Err(e) = with_catalog(&app, |catalog| catalog.mark_operation_undone(&log, &files)) {
+        log::warn!("{}", tr!("log-catalog-update-failed", detail = e.to_string()));
+    }
 
-        // 推送完成事件
-        let _ = app_handle.emit(
-            "move-progress",
-            &MoveProgressEvent {
-                moved_count: moved,
-                total,
-                current_file: String::new(),
-                done: true,
-                error: None,
-            },
-        );
+    Ok(UndoResult::new(files, has_undo))
+}
 
-        Ok::<(OperationLog, usize), String>((operation_log, moved))
+/// 重做一次已撤销的操作
+#[tauri::command]
+pub async fn redo_move(app: AppHandle, operation_id: String) -> AppResult<MoveResult> {
+    let app_handle = app.clone();
+    let (report, has_undo) = tokio::task::spawn_blocking(move || {
+        let history = open_history(&app_handle)?;
+        let report = history.redo(&operation_id)?;
+        Ok::<_, AppError>((report, history.has_undo()))
     })
     .await
-    .map_err(|e| format!("移动任务失败: {}", e))??;
+    .map_err(|e| task_failed("task-redo-failed", e))??;
 
-    let (operation_log, moved) = result;
+    if let Err(e) = with_catalog(&app, |catalog| catalog.record_operation(&report.log)) {
+        log::warn!("{}", tr!("log-catalog-record-failed", detail = e.to_string()));
+    }
 
-    // 保存操作日志用于撤销
-    let state = app.state::<AppState>();
-    *state.last_operation.lock() = Some(operation_log);
+    Ok(MoveResult::from_report(report, has_undo))
+}
+
+/// 列出上次崩溃或断电时未完成的移动操作
+#[tauri::command]
+pub async fn list_interrupted(app: AppHandle) -> AppResult<Vec<InterruptedOperation>> {
+    let data_dir = app_data_dir(&app)?;
+    tokio::task::spawn_blocking(move || journal::interrupted(&data_dir))
+        .await
+        .map_err(|e| task_failed("task-journal-failed", e))
+}
 
-    Ok(MoveResult {
-        moved_count: moved,
-        has_undo: true,
+/// 继续完成中断的移动操作，完成后写入操作历史
+#[tauri::command]
+pub async fn resume_interrupted(app: AppHandle, operation_id: String) -> AppResult<MoveResult> {
+    let job = Arc::new(app.state::<AppState>().jobs.start(JobKind::Move)?);
+    let data_dir = app_data_dir(&app)?;
+
+    let app_handle = app.clone();
+    let move_job = job.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let job = move_job;
+        let on_progress = |event: &MoveProgressEvent| emit_move_progress(&app_handle, &job, event);
+        let control = file_ops::ApplyControl {
+            cancel: Some(job.cancel_flag()),
+            on_progress: Some(&on_progress),
+        };
+        journal::resume(&data_dir, &operation_id, control)
     })
+    .await
+    .map_err(|e| task_failed("task-resume-failed", e))
+    .and_then(|r| r)
+    .and_then(|report| finish_move(&app, report));
+    let cancelled = result.as_ref().is_ok_and(|r| r.cancelled);
+    job.finish(&result, cancelled);
+
+    result
 }
 
-/// 撤销上次移动操作
+/// 回滚中断的移动操作，把已移动的文件放回原位
 #[tauri::command]
-pub async fn undo_move(app: AppHandle) -> Result<UndoResult, String> {
-    let state = app.state::<AppState>();
-    let operation_log = {
-        let mut lock = state.last_operation.lock();
-        lock.take()
+pub async fn rollback_interrupted(app: AppHandle, operation_id: String) -> AppResult<UndoResult> {
+    let app_handle = app.clone();
+    let data_dir = app_data_dir(&app)?;
+    let (files, has_undo) = tokio::task::spawn_blocking(move || {
+        let (_, files) = journal::rollback(&data_dir, &operation_id)?;
+        Ok::<_, AppError>((files, open_history(&app_handle)?.has_undo()))
+    })
+    .await
+    .map_err(|e| task_failed("task-rollback-failed", e))??;
+
+    Ok(UndoResult::new(files, has_undo))
+}
+
+/// 按人物/关键字组合查询照片目录
+#[tauri::command]
+pub async fn catalog_query(app: AppHandle, query: CatalogQuery) -> AppResult<Vec<CatalogFile>> {
+    let found = tokio::task::spawn_blocking(move || with_catalog(&app, |catalog| catalog.query(&query)))
+        .await
+        .map_err(|e| task_failed("task-query-failed", e))??;
+    Ok(found)
+}
+
+/// 查询照片目录中的单个文件
+#[tauri::command]
+pub async fn catalog_get_file(app: AppHandle, path: String) -> AppResult<Option<CatalogFile>> {
+    let found = tokio::task::spawn_blocking(move || with_catalog(&app, |catalog| catalog.get_file(&path)))
+        .await
+        .map_err(|e| task_failed("task-query-failed", e))??;
+    Ok(found)
+}
+
+/// 列出照片目录中的所有人物及照片数量
+#[tauri::command]
+pub async fn catalog_persons(app: AppHandle) -> AppResult<Vec<PersonCount>> {
+    let found = tokio::task::spawn_blocking(move || with_catalog(&app, |catalog| catalog.persons()))
+        .await
+        .map_err(|e| task_failed("task-query-failed", e))??;
+    Ok(found)
+}
+
+/// 读取保存的监视配置
+#[tauri::command]
+pub async fn get_watch_config(app: AppHandle) -> AppResult<Option<WatchConfig>> {
+    let config_dir = app_config_dir(&app)?;
+    Ok(watcher::load_config(&config_dir))
+}
+
+/// 启动监视文件夹自动分类
+/// 传入配置时先保存再启动，否则使用上次保存的配置
+#[tauri::command]
+pub async fn start_watch(app: AppHandle, config: Option<WatchConfig>) -> AppResult<WatchStatus> {
+    let config_dir = app_config_dir(&app)?;
+    let config = match config {
+        Some(config) => {
+            // 先检查再保存，无效的配置不写入磁盘
+            watcher::validate_config(&config)?;
+            watcher::save_config(&config_dir, &config)?;
+            config
+        }
+        None => watcher::load_config(&config_dir).ok_or_else(|| {
+            AppError::new(ErrorKind::NotFound, "watch-no-config")
+        })?,
     };
 
-    match operation_log {
-        Some(log) => {
-            let restored = tokio::task::spawn_blocking(move || file_ops::undo_move(&log))
-                .await
-                .map_err(|e| format!("撤销任务失败: {}", e))??;
+    let state = app.state::<AppState>();
+    let mut current = state.watcher.lock();
+    if current.is_some() {
+        return Err(AppError::new(ErrorKind::Busy, "watch-busy"));
+    }
 
-            Ok(UndoResult {
-                restored_count: restored,
-                success: true,
-            })
+    let app_handle = app.clone();
+    let data_dir = app_data_dir(&app)?;
+    let handle = watcher::start(config, Some(data_dir), move |action, operation_log| {
+        if let Some(log) = operation_log {
+            if let Err(e) = with_catalog(&app_handle, |catalog| catalog.record_operation(&log)) {
+                log::warn!("{}", tr!("log-catalog-record-failed", detail = e.to_string()));
+            }
+            // 自动分类的移动同样可以撤销
+            if let Err(e) = open_history(&app_handle).and_then(|history| history.record(&log)) {
+                log::warn!("{}", tr!("log-history-save-failed", detail = e.to_string()));
+            }
         }
-        None => Err("没有可撤销的操作".to_string()),
+        let _ = app_handle.emit("watch-action", &action);
+    })?;
+    let status = handle.status();
+    *current = Some(handle);
+    Ok(status)
+}
+
+/// 停止监视文件夹
+#[tauri::command]
+pub async fn stop_watch(app: AppHandle) -> AppResult<()> {
+    let handle = app.state::<AppState>().watcher.lock().take();
+    match handle {
+        Some(handle) => {
+            tokio::task::spawn_blocking(move || handle.stop())
+                .await
+                .map_err(|e| task_failed("task-stop-watch-failed", e))?;
+            Ok(())
+        }
+        None => Err(watch_not_running()),
     }
 }
 
-// === 请求/响应数据结构 ===
+/// 暂停自动分类
+#[tauri::command]
+pub async fn pause_watch(app: AppHandle) -> AppResult<WatchStatus> {
+    let state = app.state::<AppState>();
+    let current = state.watcher.lock();
+    let handle = current.as_ref().ok_or_else(watch_not_running)?;
+    handle.pause();
+    Ok(handle.status())
+}
+
+/// 恢复自动分类
+#[tauri::command]
+pub async fn resume_watch(app: AppHandle) -> AppResult<WatchStatus> {
+    let state = app.state::<AppState>();
+    let current = state.watcher.lock();
+    let handle = current.as_ref().ok_or_else(watch_not_running)?;
+    handle.resume();
+    Ok(handle.status())
+}
+
+/// 查询监视状态
+#[tauri::command]
+pub async fn watch_status(app: AppHandle) -> AppResult<WatchStatus> {
+    let state = app.state::<AppState>();
+    let current = state.watcher.lock();
+    Ok(match current.as_ref() {
+        Some(handle) => handle.status(),
+        None => WatchStatus {
+            running: false,
+            paused: false,
+            processed_count: 0,
+            config: None,
+        },
+    })
+}
 
-#[derive(Debug, Clone, serde::Deserialize)]
-pub struct MoveImageRequest {
-    pub path: String,
-    pub filename: String,
-    pub person: String,
+/// 查询当前语言、系统语言和可选语言
+#[tauri::command]
+pub async fn get_locale(app: AppHandle) -> AppResult<LocaleInfo> {
+    let setting = i18n::load_setting(&app_config_dir(&app)?);
+    Ok(LocaleInfo {
+        current: i18n::current(),
+        system: i18n::system_locale(),
+        setting: setting.locale,
+        available: i18n::LOCALES.to_vec(),
+    })
 }
 
+/// 设置后端消息的语言并保存；传空时跟随系统语言
+#[tauri::command]
+pub async fn set_locale(app: AppHandle, locale: Option<String>) -> AppResult<LocaleInfo> {
+    let setting = LocaleSetting { locale };
+    i18n::save_setting(&app_config_dir(&app)?, &setting)?;
+    i18n::set_locale(setting.locale.as_deref());
+    get_locale(app).await
+}
+
+// === 请求/响应数据结构 ===
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct MoveResult {
     pub moved_count: usize,
     pub has_undo: bool,
+    /// 实际传输了的源文件路径
+    pub moved: Vec<String>,
+    /// 失败的文件及原因
+    pub failures: Vec<MoveFailure>,
+    /// 是否被取消
+    pub cancelled: bool,
+}
+
+impl MoveResult {
+    fn from_report(report: MoveReport, has_undo: bool) -> Self {
+        // 按冲突策略跳过的文件也在日志中，不计入移动数
+        let moved: Vec<String> = report
+            .log
+            .records
+            .iter()
+            .filter(|r| r.needs_undo())
+            .map(|r| r.original_path.clone())
+            .collect();
+        Self {
+            moved_count: moved.len(),
+            has_undo,
+            moved,
+            failures: report.failures,
+            cancelled: report.cancelled,
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct UndoResult {
     pub restored_count: usize,
+    /// 所有文件都已恢复
     pub success: bool,
+    /// 是否还有可撤销的操作
+    pub has_undo: bool,
+    /// 每个文件的处理结果
+    pub files: Vec<UndoFileResult>,
+}
+
+impl UndoResult {
+    fn new(files: Vec<UndoFileResult>, has_undo: bool) -> Self {
+        let restored_count = files
+            .iter()
+            .filter(|f| f.outcome == UndoOutcome::Restored)
+            .count();
+        Self {
+            restored_count,
+            success: restored_count == files.len(),
+            has_undo,
+            files,
+        }
+    }
 }
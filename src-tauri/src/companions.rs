@@ -0,0 +1,243 @@
+// 伴随文件模块
+// 找出与图片同目录、同名的伴随文件：RAW+JPEG 中的 RAW、XMP 边车文件、
+// Google Takeout 的 IMG_1.JPG.json、iPhone 实况照片的 MOV 和编辑记录 AAE。
+// 伴随文件与图片组成一组，一起移动、改名和撤销
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::CompanionRules;
+
+/// 按目录缓存文件列表的伴随文件查找器。
+/// 每个伴随文件只归属于第一个认领它的图片
+pub struct CompanionIndex {
+    enabled: bool,
+    extensions: HashSet<String>,
+    /// 目录 -> (小写的"去掉最后一个扩展名的文件名" -> 候选伴随文件)
+    dirs: HashMap<PathBuf, HashMap<String, Vec<PathBuf>>>,
+    /// 已归属某张图片的文件（包括图片本身）
+    claimed: HashSet<PathBuf>,
+}
+
+impl CompanionIndex {
+    pub fn new(rules: &CompanionRules) -> Self {
+        Self {
+            enabled: rules.enabled,
+            extensions: rules
+                .extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+            dirs: HashMap::new(),
+            claimed: HashSet::new(),
+        }
+    }
+
+    /// 扩展名是否在伴随文件列表中
+    pub fn is_companion_ext(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| self.extensions.contains(&ext.to_string_lossy().to_lowercase()))
+    }
+
+    /// 文件是否已作为伴随文件归属于其他图片
+    pub fn is_claimed(&self, path: &Path) -> bool {
+        self.claimed.contains(path)
+    }
+
+    /// 认领图片的伴随文件，按路径排序返回；
+    /// 既匹配 "IMG_1.CR2" 这类同名文件，也匹配 "IMG_1.JPG.json" 这类以完整文件名为前缀的文件
+    pub fn claim(&mut self, primary: &Path) -> Vec<PathBuf> {
+        if !self.enabled || !self.claimed.insert(primary.to_path_buf()) {
+            return Vec::new();
+        }
+        let (Some(dir), Some(name)) = (primary.parent(), primary.file_name()) else {
+            return Vec::new();
+        };
+        let name = name.to_string_lossy().to_lowercase();
+        let stem = primary
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let extensions = &self.extensions;
+        let listing = self
+            .dirs
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| list_candidates(dir, extensions));
+        let mut found: Vec<PathBuf> = [name, stem]
+            .iter()
+            .filter_map(|key| listing.get(key))
+            .flatten()
+            .filter(|path| path.as_path() != primary && !self.claimed.contains(path.as_path()))
+            .cloned()
+            .collect();
+        found.sort();
+        found.dedup();
+        self.claimed.extend(found.iter().cloned());
+        found
+    }
+}
+
+/// 列出目录中扩展名在列表内的文件，按去掉最后一个扩展名后的小写文件名分组
+fn list_candidates(dir: &Path, extensions: &HashSet<String>) -> HashMap<String, Vec<PathBuf>> {
+    let mut map: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return map;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_ok_and(|t| t.is_file()) {
+            continue;
+        }
+        let matches_ext = path
+            .extension()
+            .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()));
+        if let (true, Some(stem)) = (matches_ext, path.file_stem()) {
+            map.entry(stem.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(path);
+        }
+    }
+    map
+}
+
+/// 把图片列表分组：每张图片带上它的伴随文件，已被其他图片认领的文件不再单独作为图片。
+/// 扩展名不在伴随列表中的图片优先认领，使 RAW 即使被识别为图片也跟随同名 JPEG
+pub fn group_files(files: Vec<PathBuf>, rules: &CompanionRules) -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let mut index = CompanionIndex::new(rules);
+    let mut files = files;
+    files.sort_by_key(|path| index.is_companion_ext(path));
+
+    let mut groups = Vec::with_capacity(files.len());
+    for path in files {
+        if index.is_claimed(&path) {
+            continue;
+        }
+        let companions = index.claim(&path);
+        groups.push((path, companions));
+    }
+    groups
+}
+
+/// 图片改名后伴随文件的新文件名：保留伴随文件在图片文件名（或主干名）之后的部分，
+/// 如 IMG_1.JPG -> IMG_1_1.JPG 时，IMG_1.CR2 -> IMG_1_1.CR2，IMG_1.JPG.json -> IMG_1_1.JPG.json
+pub fn companion_name(primary_name: &str, companion: &str, new_primary_name: &str) -> String {
+    let primary_stem = Path::new(primary_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let new_stem = Path::new(new_primary_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if let Some(rest) = strip_prefix_ignore_case(companion, primary_name) {
+        return format!("{}{}", new_primary_name, rest);
+    }
+    match strip_prefix_ignore_case(companion, &primary_stem) {
+        Some(rest) => format!("{}{}", new_stem, rest),
+        None => companion.to_string(),
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    let rest = &text[prefix.len()..];
+    (head.to_lowercase() == prefix.to_lowercase() && (rest.is_empty() || rest.starts_with('.')))
+        .then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// 在临时目录中创建空文件
+    fn files(dir: &TempDir, names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|name| dir.write(name, b"")).collect()
+    }
+
+    #[test]
+    fn test_group_files() {
+        let dir = TempDir::new();
+        files(
+            &dir,
+            &[
+                "IMG_1.xmp",
+                "IMG_1.JPG.json",
+                "IMG_2.MOV",
+                "IMG_2.AAE",
+                "IMG_10.xmp",
+                "notes.txt",
+                "sub/IMG_1.xmp",
+            ],
+        );
+        // RAW 也被识别为图片，排在同名 JPEG 前面时仍归属 JPEG
+        let images = files(&dir, &["IMG_1.CR2", "IMG_1.JPG", "IMG_2.HEIC", "IMG_3.CR2"]);
+        let groups = group_files(images, &CompanionRules::default());
+        let name = |path: &PathBuf| path.strip_prefix(dir.path()).unwrap().to_string_lossy().to_string();
+        let groups: Vec<(String, Vec<String>)> = groups
+            .iter()
+            .map(|(image, companions)| (name(image), companions.iter().map(name).collect()))
+            .collect();
+        let expected: Vec<(String, Vec<String>)> = [
+            ("IMG_1.JPG", vec!["IMG_1.CR2", "IMG_1.JPG.json", "IMG_1.xmp"]),
+            ("IMG_2.HEIC", vec!["IMG_2.AAE", "IMG_2.MOV"]),
+            ("IMG_3.CR2", vec![]),
+        ]
+        .iter()
+        .map(|(image, companions)| (image.to_string(), companions.iter().map(|c| c.to_string()).collect()))
+        .collect();
+        assert_eq!(groups, expected);
+
+        // 关闭分组时每个文件单独成组
+        let rules = CompanionRules {
+            enabled: false,
+            ..CompanionRules::default()
+        };
+        let images = vec![dir.join("IMG_1.JPG"), dir.join("IMG_1.CR2")];
+        let groups = group_files(images, &rules);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|(_, companions)| companions.is_empty()));
+    }
+
+    #[test]
+    fn test_claim() {
+        let dir = TempDir::new();
+        let paths = files(&dir, &["IMG_4.JPG", "IMG_4.HEIC", "IMG_4.XMP", "IMG_4.mov"]);
+        let (jpeg, heic, xmp, mov) = (&paths[0], &paths[1], &paths[2], &paths[3]);
+
+        // 扩展名列表不区分大小写，可以带点
+        let rules = CompanionRules {
+            enabled: true,
+            extensions: vec![".xmp".to_string(), "MOV".to_string()],
+        };
+        let mut index = CompanionIndex::new(&rules);
+        assert!(index.is_companion_ext(xmp) && !index.is_companion_ext(jpeg));
+
+        // 同名的伴随文件只归属第一个认领的图片
+        assert_eq!(index.claim(jpeg), [xmp.as_path(), mov.as_path()]);
+        assert!(index.is_claimed(xmp) && index.is_claimed(jpeg));
+        assert!(index.claim(heic).is_empty());
+        // 同一张图片不会认领两次
+        assert!(index.claim(jpeg).is_empty());
+
+        // 缓存的文件列表不影响之后新建索引时看到的文件
+        fs::remove_file(mov).unwrap();
+        let mut index = CompanionIndex::new(&rules);
+        assert_eq!(index.claim(heic), [xmp.as_path()]);
+    }
+
+    #[test]
+    fn test_companion_name() {
+        assert_eq!(companion_name("IMG_1.JPG", "IMG_1.CR2", "IMG_1_1.JPG"), "IMG_1_1.CR2");
+        assert_eq!(companion_name("IMG_1.JPG", "img_1.xmp", "IMG_1_1.JPG"), "IMG_1_1.xmp");
+        assert_eq!(
+            companion_name("IMG_1.JPG", "IMG_1.JPG.json", "2024_IMG_1.jpg"),
+            "2024_IMG_1.jpg.json"
+        );
+        assert_eq!(companion_name("IMG_1.HEIC", "IMG_1.MOV", "IMG_1.HEIC"), "IMG_1.MOV");
+        assert_eq!(companion_name("IMG_1.JPG", "IMG_10.xmp", "a.JPG"), "IMG_10.xmp");
+    }
+}
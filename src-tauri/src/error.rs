@@ -0,0 +1,157 @@
+// 错误模块
+// 命令返回给前端的结构化错误：错误类型、消息键、相关路径和底层 IO 错误类型。
+// 前端按错误类型区分"已有任务在进行中""没有权限"等情况；
+// message 是按当前语言渲染好的完整错误信息，消息键一并返回，前端可以自行翻译
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, FluentValue};
+
+/// 错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 已有同类任务在进行中
+    Busy,
+    /// 文件或文件夹不存在
+    NotFound,
+    /// 没有权限
+    PermissionDenied,
+    /// 目标已存在
+    AlreadyExists,
+    /// 参数无效（模板、改名模式、计划文件等）
+    InvalidInput,
+    /// 当前平台不支持
+    Unsupported,
+    /// 被用户取消
+    Cancelled,
+    /// 被较新的操作阻止（需要先撤销较新的操作）
+    Blocked,
+    /// 图片无法解码
+    Decode,
+    /// 其他读写错误
+    Io,
+    /// 数据库、历史记录、后台任务等内部错误
+    Internal,
+}
+
+impl ErrorKind {
+    /// 按 IO 错误类型归类
+    fn of_io(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+            io::ErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            io::ErrorKind::Unsupported => ErrorKind::Unsupported,
+            io::ErrorKind::Interrupted => ErrorKind::Cancelled,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+/// 应用错误
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    /// 消息键（如 "scan-busy"），前端用它查找本地化文本
+    pub key: &'static str,
+    /// 完整的错误信息
+    pub message: String,
+    /// 出错的文件或文件夹
+    pub path: Option<String>,
+    /// 底层错误的原因（不含上下文），本地化文本中作为参数使用
+    pub detail: Option<String>,
+    /// 底层 IO 错误类型（如 "PermissionDenied"）
+    pub io_kind: Option<String>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// 按消息键渲染当前语言的错误信息
+    pub fn new(kind: ErrorKind, key: &'static str) -> Self {
+        Self::with_args(kind, key, &[])
+    }
+
+    pub fn with_args(kind: ErrorKind, key: &'static str, args: &[(&str, FluentValue)]) -> Self {
+        Self {
+            kind,
+            key,
+            message: i18n::message(key, args),
+            path: None,
+            detail: None,
+            io_kind: None,
+        }
+    }
+
+    /// 带底层原因的错误，原因作为消息参数 `$detail`
+    pub fn caused(kind: ErrorKind, key: &'static str, detail: impl fmt::Display) -> Self {
+        let detail = detail.to_string();
+        Self {
+            detail: Some(detail.clone()),
+            ..Self::with_args(kind, key, &[("detail", FluentValue::from(detail))])
+        }
+    }
+
+    /// IO 错误，错误类型按 IO 错误类型归类
+    pub fn io(key: &'static str, err: &io::Error) -> Self {
+        Self {
+            io_kind: Some(format!("{:?}", err.kind())),
+            ..Self::caused(ErrorKind::of_io(err.kind()), key, err)
+        }
+    }
+
+    /// 参数无效
+    pub fn invalid(key: &'static str, detail: impl fmt::Display) -> Self {
+        Self::caused(ErrorKind::InvalidInput, key, detail)
+    }
+
+    /// 内部错误
+    pub fn internal(key: &'static str, detail: impl fmt::Display) -> Self {
+        Self::caused(ErrorKind::Internal, key, detail)
+    }
+
+    pub fn with_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_string_lossy().to_string());
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// 命令行工具和仍使用字符串错误的模块直接取错误信息
+impl From<AppError> for String {
+    fn from(err: AppError) -> Self {
+        err.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_kind() {
+        let err = io::Error::from(io::ErrorKind::PermissionDenied);
+        let app = AppError::io("move-create-target", &err).with_path("/a");
+        assert_eq!(app.kind, ErrorKind::PermissionDenied);
+        assert_eq!(app.io_kind.as_deref(), Some("PermissionDenied"));
+        assert_eq!(app.path.as_deref(), Some("/a"));
+        assert!(app.message.ends_with(&err.to_string()));
+        assert_eq!(
+            serde_json::to_value(&app).unwrap()["kind"],
+            serde_json::json!("permission_denied")
+        );
+    }
+}
@@ -1,151 +1,1654 @@
 // 文件操作模块
 // 负责文件移动、撤销操作、操作日志管理
 
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use crate::models::{MoveRecord, OperationLog};
+use parking_lot::Mutex;
 
-/// 将图片移动到目标文件夹中对应人物的子文件夹；
-/// 如果人物为空，则直接移动到目标文件夹。
-/// 返回操作日志用于撤销
+use crate::models::{
+    ConflictOutcome, ConflictPolicy, FileStamp, MoveErrorKind, MoveFailure, MoveImageRequest,
+    MoveOptions, MovePlan, MoveProgressEvent, MoveRecord, MoveReport, MultiPersonStrategy,
+    NameRules, OperationLog, PlanIssue, PlannedTransfer, TransferMode, UndoFileResult, UndoOutcome,
+};
+use crate::companions::{self, CompanionIndex};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::{self, tr, FluentValue};
+use crate::journal::JournalWriter;
+use crate::path_template::{PathTemplate, TemplateContext};
+use crate::sanitize;
+use crate::trash;
+
+/// 多人物图片使用 Group 策略时的合影文件夹名称
+const GROUP_FOLDER_NAME: &str = "Group";
+
+/// 默认改名模式：photo.jpg -> photo_1.jpg
+const DEFAULT_RENAME_PATTERN: &str = "{stem}_{n}{ext}";
+
+/// 分块复制时每块的大小（每块报告一次进度）
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// 将图片传输到目标文件夹中对应人物的子文件夹；
+/// 如果人物为空，则直接放到目标文件夹。
+/// 等价于先 `plan_move` 再 `apply_plan`。
+/// 返回操作日志（用于撤销）和失败的文件
 pub fn move_images(
-    images: &[(String, String, String)], // (path, filename, selected_person)
+    images: &[MoveImageRequest],
     target_dir: &str,
-) -> Result<OperationLog, String> {
+    options: &MoveOptions,
+    journal_dir: Option<&Path>,
+) -> AppResult<MoveReport> {
+    let plan = plan_move(images, target_dir, options)?;
+    apply_plan(&plan, journal_dir, ApplyControl::default())
+}
+
+/// 生成移动计划：计算每个文件的目标路径并检查同名冲突、源文件缺失、
+/// 跨设备和权限问题，不改动磁盘。
+/// 指定路径模板时按模板计算目标路径，模板无效时直接返回错误。
+/// `options.mode` 决定移动、复制还是创建链接，
+/// `options.multi_person` 决定含多个人物的图片放到哪些文件夹，
+/// `options.conflict_policy` 决定目标已有同名文件时的处理方式。
+pub fn plan_move(
+    images: &[MoveImageRequest],
+    target_dir: &str,
+    options: &MoveOptions,
+) -> AppResult<MovePlan> {
     let target_path = Path::new(target_dir);
+    let template = parse_template(options.path_template.as_deref())?;
+    let rename_pattern = options.rename_pattern.as_deref().unwrap_or(DEFAULT_RENAME_PATTERN);
+    validate_rename_pattern(rename_pattern)?;
+    sanitize::validate_rules(&options.name_rules)?;
+    if options.use_trash && !trash::is_supported() {
+        return Err(AppError::new(ErrorKind::Unsupported, "trash-unsupported"));
+    }
+
+    let mut items = Vec::new();
+    // 计划内已占用的目标路径，避免两个文件被分到同一路径
+    let mut reserved: HashSet<PathBuf> = HashSet::new();
+    let mut companion_index = CompanionIndex::new(&options.companions);
+
+    for image in images {
+        let source = Path::new(&image.path);
+        let source_meta = fs::metadata(source).ok();
+        let original_filename = source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let companions: Vec<(PathBuf, String)> = companion_index
+            .claim(source)
+            .into_iter()
+            .map(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                (path, name)
+            })
+            .collect();
+
+        // 副本在前、主文件夹在最后：移动模式下源文件要留到最后才能移走
+        for (folder, mode) in person_targets(image, options) {
+            // 计算目标目录：默认有标签则进入人物子文件夹，无标签则直接进目标文件夹
+            let planned = destination_path(template.as_ref(), target_path, source, &folder, &options.name_rules);
+            let dest_dir = planned.parent().unwrap_or(target_path).to_path_buf();
+            let dest_name = planned
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| original_filename.clone());
+
+            // 图片与伴随文件共用一个基础名，整组的名称都可用才算不冲突
+            let group_paths = |name: &str| -> Vec<PathBuf> {
+                let mut paths = vec![dest_dir.join(name)];
+                paths.extend(companions.iter().map(|(_, companion)| {
+                    dest_dir.join(companions::companion_name(&original_filename, companion, name))
+                }));
+                paths
+            };
+            let is_free = |name: &str| {
+                group_paths(name)
+                    .iter()
+                    .all(|path| path.symlink_metadata().is_err() && !reserved.contains(path))
+            };
+
+            // 处理文件名冲突
+            let mut issues = transfer_issues(source, source_meta.as_ref(), &dest_dir, mode);
+            let wanted = group_paths(&dest_name);
+            let mut conflict = None;
+            let mut dest_path = wanted[0].clone();
+            if wanted.iter().any(|path| reserved.contains(path)) {
+                // 计划内重名时目标文件尚不存在，无法比较内容，只能改名
+                issues.push(PlanIssue::DuplicateDestination);
+                conflict = Some(ConflictOutcome::Renamed);
+                dest_path = resolve_filename_conflict(&dest_dir, &dest_name, rename_pattern, &is_free);
+            } else if !is_free(&dest_name) {
+                issues.push(PlanIssue::NameConflict);
+                let sources: Vec<&Path> = companions.iter().map(|(path, _)| path.as_path()).collect();
+                let outcome = resolve_group_conflict(options.conflict_policy, source, &sources, &wanted);
+                if outcome == ConflictOutcome::Renamed {
+                    dest_path = resolve_filename_conflict(&dest_dir, &dest_name, rename_pattern, &is_free);
+                }
+                conflict = Some(outcome);
+            }
+            let final_name = dest_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let member_paths = group_paths(&final_name);
+            // 名称已清理过，这里再兜底确认一次不会写到目标文件夹之外
+            for path in &member_paths {
+                sanitize::ensure_within(target_path, path)?;
+            }
+            reserved.extend(member_paths.iter().cloned());
+
+            let group = (!companions.is_empty()).then(|| dest_path.to_string_lossy().to_string());
+            items.push(PlannedTransfer {
+                source: image.path.clone(),
+                destination: dest_path.to_string_lossy().to_string(),
+                filename: original_filename.clone(),
+                mode,
+                size: source_meta.as_ref().map(|m| m.len()).unwrap_or(0),
+                conflict,
+                issues,
+                group: group.clone(),
+            });
+            for ((companion, name), dest) in companions.iter().zip(member_paths.iter().skip(1)) {
+                let meta = fs::metadata(companion).ok();
+                items.push(PlannedTransfer {
+                    source: companion.to_string_lossy().to_string(),
+                    destination: dest.to_string_lossy().to_string(),
+                    filename: name.clone(),
+                    mode,
+                    size: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+                    conflict: companion_outcome(conflict, dest),
+                    issues: transfer_issues(companion, meta.as_ref(), &dest_dir, mode),
+                    group: group.clone(),
+                });
+            }
+        }
+    }
+
+    let count = |issue: PlanIssue| items.iter().filter(|i| i.issues.contains(&issue)).count();
+    let estimated_bytes = items
+        .iter()
+        .filter(|item| match item.mode {
+            TransferMode::Copy => true,
+            TransferMode::Move => item.issues.contains(&PlanIssue::CrossDevice),
+            _ => false,
+        })
+        .map(|item| item.size)
+        .sum();
+
+    Ok(MovePlan {
+        target_dir: target_dir.to_string(),
+        estimated_bytes,
+        conflict_count: count(PlanIssue::NameConflict) + count(PlanIssue::DuplicateDestination),
+        missing_count: count(PlanIssue::SourceMissing),
+        cross_device_count: count(PlanIssue::CrossDevice),
+        permission_count: count(PlanIssue::PermissionDenied),
+        verify_checksum: options.verify_checksum,
+        use_trash: options.use_trash,
+        max_parallel: options.max_parallel,
+        items,
+    })
+}
+
+/// 单个传输与冲突无关的问题：源文件缺失、跨设备、权限不足
+fn transfer_issues(
+    source: &Path,
+    source_meta: Option<&fs::Metadata>,
+    dest_dir: &Path,
+    mode: TransferMode,
+) -> Vec<PlanIssue> {
+    let mut issues = Vec::new();
+    match source_meta {
+        None => issues.push(PlanIssue::SourceMissing),
+        Some(meta) => {
+            let needs_same_device = matches!(
+                mode,
+                TransferMode::Move | TransferMode::Hardlink | TransferMode::Reflink
+            );
+            if needs_same_device && !same_device(meta, dest_dir) {
+                issues.push(PlanIssue::CrossDevice);
+            }
+        }
+    }
+    let source_locked = mode == TransferMode::Move && source.parent().is_some_and(is_readonly_dir);
+    if source_locked || is_readonly_dir(dest_dir) {
+        issues.push(PlanIssue::PermissionDenied);
+    }
+    issues
+}
+
+/// 执行计划时的进度回调和取消标志
+#[derive(Default, Clone, Copy)]
+pub struct ApplyControl<'a> {
+    /// 置为 true 后在当前文件完成（或复制中断）后停止，已完成部分仍记入日志
+    pub cancel: Option<&'a AtomicBool>,
+    /// 每个文件开始时以及跨设备复制每写入一块时调用（并行传输时从多个线程调用）
+    pub on_progress: Option<&'a (dyn Fn(&MoveProgressEvent) + Sync)>,
+}
+
+impl ApplyControl<'_> {
+    fn cancelled(&self) -> bool {
+        self.cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    fn report(&self, event: MoveProgressEvent) {
+        if let Some(on_progress) = self.on_progress {
+            on_progress(&event);
+        }
+    }
+}
+
+/// 按计划执行传输：每个文件严格使用计划中的目标路径，目标不在目标文件夹之内时拒绝整个计划；
+/// 源文件已不存在或目标在计划之后被占用的条目会记为失败，不会再自动改名。
+/// 指定 `journal_dir` 时每个文件传输前后都写入预写日志，崩溃后可以继续或回滚。
+/// 返回操作日志（用于撤销）和失败的文件
+pub fn apply_plan(
+    plan: &MovePlan,
+    journal_dir: Option<&Path>,
+    control: ApplyControl,
+) -> AppResult<MoveReport> {
+    // 计划可能来自前端或用户编辑过的计划文件，执行前确认每个目标都在目标文件夹之内
+    let target_dir = Path::new(&plan.target_dir);
+    for item in &plan.items {
+        sanitize::ensure_within(target_dir, Path::new(&item.destination))?;
+    }
 
     // 确保目标文件夹存在
-    if !target_path.exists() {
-        fs::create_dir_all(target_path)
-            .map_err(|e| format!("创建目标文件夹失败: {}", e))?;
+    let mut dirs = DirTracker::default();
+    dirs.ensure(Path::new(&plan.target_dir))
+        .map_err(|e| AppError::io("move-create-target", &e).with_path(&plan.target_dir))?;
+    let created_dirs = dirs.take();
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut journal = match journal_dir {
+        Some(dir) => {
+            let mut journal = JournalWriter::begin(dir, &id, &timestamp, plan)?;
+            journal.dirs(&created_dirs)?;
+            Some(journal)
+        }
+        None => None,
+    };
+    let log = OperationLog {
+        id,
+        timestamp,
+        target_dir: plan.target_dir.clone(),
+        records: Vec::new(),
+        created_dirs,
+        verify_checksum: plan.verify_checksum,
+        use_trash: plan.use_trash,
+        max_parallel: plan.max_parallel,
+    };
+
+    Ok(apply_items(plan, &HashSet::new(), journal.as_mut(), control, log))
+}
+
+/// 执行计划中除 `processed` 以外的传输，成功的记录按计划顺序追加到 `log`。
+/// 同组的图片和伴随文件作为一个整体：任一文件失败时，同组已传输的文件会被回退。
+/// 只改名或创建链接的文件按源/目标设备分成几条队列依次执行，需要复制内容的文件
+/// 由最多 `plan.max_parallel` 个线程并行传输；同一源文件的多次传输（多人物副本）保持顺序。
+/// 取消时各线程在当前组处理完后停止；
+/// 预写日志写入失败（如磁盘断开）时立即停止，不再继续传输
+pub(crate) fn apply_items(
+    plan: &MovePlan,
+    processed: &HashSet<usize>,
+    journal: Option<&mut JournalWriter>,
+    control: ApplyControl,
+    mut log: OperationLog,
+) -> MoveReport {
+    let pending: Vec<usize> = (0..plan.items.len()).filter(|i| !processed.contains(i)).collect();
+    let tasks = schedule(plan, &pending);
+    let shared = ApplyShared {
+        plan,
+        control,
+        settings: TransferSettings::of(plan),
+        journal: Mutex::new(journal),
+        dirs: Mutex::new(DirTracker::default()),
+        results: Mutex::new(ApplyResults::default()),
+        finished: AtomicUsize::new(0),
+        aborted: AtomicBool::new(false),
+    };
+
+    let workers = plan.max_parallel.clamp(1, tasks.len().max(1));
+    if workers == 1 {
+        for task in &tasks {
+            shared.run_task(task);
+        }
+    } else {
+        let next = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while let Some(task) = tasks.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        shared.run_task(task);
+                    }
+                });
+            }
+        });
+    }
+
+    let finished = shared.finished.load(Ordering::Relaxed);
+    let aborted = shared.aborted.load(Ordering::Relaxed);
+    let mut results = shared.results.into_inner();
+    if finished == pending.len() && !aborted {
+        if let Some(journal) = shared.journal.into_inner() {
+            if let Err(e) = journal.end() {
+                log::error!("{}", e);
+            }
+        }
+    } else if !aborted {
+        log::info!("{}", tr!("log-move-cancelled", count = pending.len() - finished));
+    }
+
+    // 并行完成的顺序不固定，按计划顺序输出
+    results.records.sort_by_key(|(index, _)| *index);
+    results.failures.sort_by_key(|(index, _)| *index);
+    log.records.extend(results.records.into_iter().map(|(_, record)| record));
+    log.created_dirs.extend(results.created_dirs);
+    let failures: Vec<MoveFailure> = results.failures.into_iter().map(|(_, f)| f).collect();
+    // 复制被取消时同组其他文件也记为失败，取消原因不一定是最后一条
+    let cancelled = (!aborted && finished < pending.len())
+        || failures.iter().any(|f| f.kind == MoveErrorKind::Cancelled);
+    MoveReport {
+        log,
+        failures,
+        cancelled,
+    }
+}
+
+/// 一条链：共用源文件、必须依次执行的若干组（计划中的连续区间），
+/// 即同一图片放入各人物文件夹的副本，最后一组是主文件夹
+type Chain = Vec<Range<usize>>;
+
+/// 一个并行任务：依次执行的若干条链
+type Task = Vec<Chain>;
+
+/// 把待执行的项分成并行任务：每个任务内的组依次执行。
+/// 先按组切分，再把共用源文件的相邻组（多人物的副本和最终移动）连成一条链；
+/// 不复制内容的链按源/目标设备合并为一个任务，复制内容的链各自成为一个任务
+fn schedule(plan: &MovePlan, pending: &[usize]) -> Vec<Task> {
+    let mut units: Vec<Range<usize>> = Vec::new();
+    for &index in pending {
+        let group = &plan.items[index].group;
+        match units.last_mut() {
+            Some(unit) if unit.end == index && group.is_some() && plan.items[unit.start].group == *group => {
+                unit.end += 1;
+            }
+            _ => units.push(index..index + 1),
+        }
     }
 
-    let mut records = Vec::new();
-    let mut person_dirs: HashMap<String, PathBuf> = HashMap::new();
+    let mut chains: Vec<Chain> = Vec::new();
+    let mut chain_sources: HashSet<&str> = HashSet::new();
+    for unit in units {
+        let items = &plan.items[unit.clone()];
+        let continues = items.iter().any(|item| chain_sources.contains(item.source.as_str()));
+        match chains.last_mut() {
+            Some(chain) if continues => chain.push(unit),
+            _ => {
+                chain_sources.clear();
+                chains.push(vec![unit]);
+            }
+        }
+        chain_sources.extend(items.iter().map(|item| item.source.as_str()));
+    }
 
-    for (path, _filename, person) in images {
-        let source = Path::new(path);
-        if !source.exists() {
-            log::warn!("源文件不存在，跳过: {}", path);
+    let mut lanes: Vec<((u64, u64), Task)> = Vec::new();
+    let mut copies = Vec::new();
+    for chain in chains {
+        let copies_bytes = chain
+            .iter()
+            .flat_map(|unit| &plan.items[unit.clone()])
+            .any(copies_content);
+        if copies_bytes {
+            copies.push(vec![chain]);
             continue;
         }
+        let first = &plan.items[chain[0].start];
+        let key = (device_id(Path::new(&first.source)), device_id(Path::new(&first.destination)));
+        match lanes.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, lane)) => lane.push(chain),
+            None => lanes.push((key, vec![chain])),
+        }
+    }
+    // 改名队列先开始，很快就能完成，不会被大文件复制挡住
+    lanes.into_iter().map(|(_, lane)| lane).chain(copies).collect()
+}
 
-        // 获取或创建目标目录：有标签则进入人物子文件夹，无标签则直接进目标文件夹
-        let person_dir = if person.is_empty() {
-            target_path.to_path_buf()
-        } else {
-            person_dirs
-                .entry(person.clone())
-                .or_insert_with(|| {
-                    let dir = target_path.join(person);
-                    if !dir.exists() {
-                        let _ = fs::create_dir_all(&dir);
-                    }
-                    dir
+/// 传输是否需要复制文件内容（复制，或跨设备的移动）
+fn copies_content(item: &PlannedTransfer) -> bool {
+    let transfers = !matches!(
+        item.conflict,
+        Some(ConflictOutcome::Skipped | ConflictOutcome::KeptExisting | ConflictOutcome::Deduplicated)
+    );
+    let copies = match item.mode {
+        TransferMode::Copy => true,
+        TransferMode::Move => item.issues.contains(&PlanIssue::CrossDevice),
+        _ => false,
+    };
+    transfers && copies
+}
+
+/// 路径（或其最近的已存在上级）所在设备；无法判断时为 0
+#[cfg(unix)]
+fn device_id(path: &Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    existing_ancestor(path)
+        .and_then(|p| fs::metadata(p).ok())
+        .map(|meta| meta.dev())
+        .unwrap_or(0)
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> u64 {
+    0
+}
+
+/// 执行过程中收集的结果，附带计划中的序号以便排序
+#[derive(Default)]
+struct ApplyResults {
+    records: Vec<(usize, MoveRecord)>,
+    failures: Vec<(usize, MoveFailure)>,
+    created_dirs: Vec<String>,
+}
+
+/// 各工作线程共享的执行状态
+struct ApplyShared<'a, 'j> {
+    plan: &'a MovePlan,
+    control: ApplyControl<'a>,
+    settings: TransferSettings,
+    journal: Mutex<Option<&'j mut JournalWriter>>,
+    dirs: Mutex<DirTracker>,
+    results: Mutex<ApplyResults>,
+    /// 已有结果的项数（用于进度和判断是否全部处理完）
+    finished: AtomicUsize,
+    /// 预写日志写入失败，所有线程停止
+    aborted: AtomicBool,
+}
+
+impl ApplyShared<'_, '_> {
+    fn run_task(&self, task: &[Chain]) {
+        for chain in task {
+            if self.stopped() {
+                return;
+            }
+            self.run_chain(chain);
+        }
+    }
+
+    /// 预写日志写入失败或已取消，不再开始新的传输
+    fn stopped(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed) || self.control.cancelled()
+    }
+
+    /// 执行一条链。主文件夹（最后一组）传输失败时回退为其他人物创建的副本，
+    /// 不留下只有副本、原图却没有归档的结果；中途停止时保留已完成的部分
+    fn run_chain(&self, chain: &[Range<usize>]) {
+        let Some((primary, extras)) = chain.split_last() else {
+            return;
+        };
+        let mut applied = Vec::new();
+        for unit in extras {
+            if self.stopped() {
+                self.results.lock().records.extend(applied);
+                return;
+            }
+            applied.extend(self.apply_group(unit.clone()).unwrap_or_default());
+        }
+        if self.stopped() {
+            self.results.lock().records.extend(applied);
+            return;
+        }
+        match self.apply_group(primary.clone()) {
+            Some(records) => {
+                let mut results = self.results.lock();
+                results.records.extend(applied);
+                results.records.extend(records);
+            }
+            None if self.aborted.load(Ordering::Relaxed) => {
+                self.results.lock().records.extend(applied);
+            }
+            None => {
+                let name = [("name", FluentValue::from(self.plan.items[primary.start].filename.as_str()))];
+                let mut reverted = ApplyResults::default();
+                self.revert(applied, "move-person-reverted", &name, &mut reverted);
+                let mut results = self.results.lock();
+                results.records.extend(reverted.records);
+                results.failures.extend(reverted.failures);
+            }
+        }
+    }
+
+    /// 写入预写日志；没有日志时什么都不做
+    fn journal(&self, write: impl FnOnce(&mut JournalWriter) -> AppResult<()>) -> AppResult<()> {
+        match self.journal.lock().as_deref_mut() {
+            Some(journal) => write(journal),
+            None => Ok(()),
+        }
+    }
+
+    /// 预写日志写入失败：记下已完成的部分，通知所有线程停止
+    fn abort(&self, index: usize, applied: Vec<(usize, MoveRecord)>, error: AppError) {
+        let message = error.to_string();
+        log::error!("{}", tr!("log-move-stopped", detail = message.as_str()));
+        self.aborted.store(true, Ordering::Relaxed);
+        let mut results = self.results.lock();
+        results.records.extend(applied);
+        results
+            .failures
+            .push((index, error_failure(&self.plan.items[index], MoveErrorKind::Journal, error)));
+    }
+
+    /// 执行一组传输（图片及其伴随文件），全部成功时返回完成的记录；
+    /// 任一失败时回退同组已完成的文件，结果直接写入共享结果并返回 `None`
+    fn apply_group(&self, unit: Range<usize>) -> Option<Vec<(usize, MoveRecord)>> {
+        let plan = self.plan;
+        let total = plan.items.len();
+        let mut applied: Vec<(usize, MoveRecord)> = Vec::new();
+        let mut failed_at = None;
+        for index in unit.clone() {
+            let item = &plan.items[index];
+            let progress = |bytes_done: u64| {
+                self.control.report(MoveProgressEvent {
+                    job_id: String::new(),
+                    moved_count: self.finished.load(Ordering::Relaxed),
+                    total,
+                    current_file: item.filename.clone(),
+                    bytes_done,
+                    bytes_total: item.size,
+                    done: false,
+                    cancelled: false,
+                    error: None,
                 })
-                .clone()
+            };
+            progress(0);
+
+            if let Err(e) = self.journal(|journal| journal.intent(index)) {
+                self.abort(index, applied, e);
+                return None;
+            }
+
+            let result = apply_item(item, &self.dirs, self.settings, self.control.cancel, &progress);
+
+            let new_dirs = self.dirs.lock().take();
+            let written = self.journal(|journal| {
+                journal.dirs(&new_dirs)?;
+                match &result {
+                    Ok(record) => journal.done(index, record),
+                    Err(_) => journal.failed(index),
+                }
+            });
+            self.results.lock().created_dirs.extend(new_dirs);
+            self.finished.fetch_add(1, Ordering::Relaxed);
+            if let Err(e) = written {
+                match result {
+                    Ok(record) => applied.push((index, record)),
+                    Err(f) => self.results.lock().failures.push((index, f)),
+                }
+                self.abort(index, applied, e);
+                return None;
+            }
+            match result {
+                Ok(record) => applied.push((index, record)),
+                Err(f) => {
+                    self.results.lock().failures.push((index, f));
+                    failed_at = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let Some(failed_index) = failed_at else {
+            return Some(applied);
         };
+        let failed_name = &plan.items[failed_index].filename;
+        let name = [("name", FluentValue::from(failed_name.as_str()))];
+        let mut reverted = ApplyResults::default();
+        self.revert(applied, "move-group-reverted", &name, &mut reverted);
+        for index in failed_index + 1..unit.end {
+            if let Err(e) = self.journal(|journal| journal.failed(index)) {
+                log::error!("{}", e);
+            }
+            self.finished.fetch_add(1, Ordering::Relaxed);
+            reverted.failures.push((
+                index,
+                failure(&plan.items[index], MoveErrorKind::CompanionFailed, "move-group-skipped", &name),
+            ));
+        }
+        let mut shared = self.results.lock();
+        shared.records.extend(reverted.records);
+        shared.failures.extend(reverted.failures);
+        None
+    }
 
-        // 处理文件名冲突
-        let original_filename = source
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let dest_path = resolve_filename_conflict(&person_dir, &original_filename);
-
-        // 执行移动
-        match fs::rename(source, &dest_path) {
-            Ok(()) => {
-                records.push(MoveRecord {
-                    original_path: path.clone(),
-                    new_path: dest_path.to_string_lossy().to_string(),
-                    filename: original_filename,
-                });
+    /// 回退已完成的传输，回退成功的记为失败（原因为消息 `key`）；
+    /// 无法回退的保留在记录中，撤销时仍可处理
+    fn revert(
+        &self,
+        applied: Vec<(usize, MoveRecord)>,
+        key: &'static str,
+        args: &[(&str, FluentValue)],
+        into: &mut ApplyResults,
+    ) {
+        for (index, record) in applied {
+            if !record.needs_undo() {
+                into.records.push((index, record));
+                continue;
             }
-            Err(e) => {
-                // rename 跨卷失败时，用 copy + delete
-                match fs::copy(source, &dest_path) {
-                    Ok(_) => {
-                        let _ = fs::remove_file(source);
-                        records.push(MoveRecord {
-                            original_path: path.clone(),
-                            new_path: dest_path.to_string_lossy().to_string(),
-                            filename: original_filename,
-                        });
-                    }
-                    Err(e2) => {
-                        log::error!("移动文件失败 {} -> {}: rename={}, copy={}", path, dest_path.display(), e, e2);
+            match undo_record(&record) {
+                Ok(UndoOutcome::Restored) => {
+                    if let Err(e) = self.journal(|journal| journal.failed(index)) {
+                        log::error!("{}", e);
                     }
+                    into.failures.push((
+                        index,
+                        failure(&self.plan.items[index], MoveErrorKind::CompanionFailed, key, args),
+                    ));
+                }
+                outcome => {
+                    log::error!(
+                        "{}",
+                        tr!(
+                            "log-group-revert-failed",
+                            path = record.new_path.as_str(),
+                            detail = format!("{:?}", outcome),
+                        )
+                    );
+                    into.records.push((index, record));
                 }
             }
         }
     }
+}
+
+/// 按消息键生成单个文件的失败结果
+fn failure(
+    item: &PlannedTransfer,
+    kind: MoveErrorKind,
+    key: &'static str,
+    args: &[(&str, FluentValue)],
+) -> MoveFailure {
+    MoveFailure {
+        source: item.source.clone(),
+        destination: item.destination.clone(),
+        kind,
+        key: key.to_string(),
+        message: i18n::message(key, args),
+    }
+}
 
-    let log = OperationLog {
-        id: uuid::Uuid::new_v4().to_string(),
-        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-        target_dir: target_dir.to_string(),
-        records,
+/// 由底层错误生成单个文件的失败结果，保留错误的消息键
+fn error_failure(item: &PlannedTransfer, kind: MoveErrorKind, error: AppError) -> MoveFailure {
+    MoveFailure {
+        source: item.source.clone(),
+        destination: item.destination.clone(),
+        kind,
+        key: error.key.to_string(),
+        message: error.message,
+    }
+}
+
+/// 执行时新建的文件夹，写入操作日志供撤销时删除
+#[derive(Default)]
+struct DirTracker {
+    /// 已确认存在的文件夹
+    checked: HashSet<PathBuf>,
+    /// 新建的文件夹（由浅到深），尚未写入日志
+    created: Vec<PathBuf>,
+}
+
+impl DirTracker {
+    /// 确保文件夹存在，并记下其中新建的每一层
+    fn ensure(&mut self, dir: &Path) -> io::Result<()> {
+        if self.checked.contains(dir) {
+            return Ok(());
+        }
+        let missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_err())
+            .map(Path::to_path_buf)
+            .collect();
+        fs::create_dir_all(dir)?;
+        self.checked.insert(dir.to_path_buf());
+        self.created.extend(missing.into_iter().rev());
+        Ok(())
+    }
+
+    /// 取出尚未写入日志的新建文件夹
+    fn take(&mut self) -> Vec<String> {
+        self.created
+            .drain(..)
+            .map(|dir| dir.to_string_lossy().to_string())
+            .collect()
+    }
+}
+
+/// 计划中对所有传输都生效的设置
+#[derive(Clone, Copy)]
+struct TransferSettings {
+    verify_checksum: bool,
+    use_trash: bool,
+}
+
+impl TransferSettings {
+    fn of(plan: &MovePlan) -> Self {
+        Self {
+            verify_checksum: plan.verify_checksum,
+            use_trash: plan.use_trash,
+        }
+    }
+}
+
+/// 删除不再需要的文件（跨设备移动后的源文件、被覆盖的文件、去重的源文件）；
+/// `use_trash` 时移到回收站而不是直接删除
+pub(crate) fn discard_file(path: &Path, use_trash: bool) -> io::Result<()> {
+    if !use_trash {
+        return fs::remove_file(path);
+    }
+    let trashed = trash::move_to_trash(path)?;
+    log::info!(
+        "{}",
+        tr!(
+            "log-trashed",
+            path = path.display().to_string(),
+            trashed = trashed.display().to_string(),
+        )
+    );
+    Ok(())
+}
+
+/// 执行计划中的单个传输
+fn apply_item(
+    item: &PlannedTransfer,
+    dirs: &Mutex<DirTracker>,
+    settings: TransferSettings,
+    cancel: Option<&AtomicBool>,
+    on_bytes: &dyn Fn(u64),
+) -> Result<MoveRecord, MoveFailure> {
+    let source = Path::new(&item.source);
+    let dest_path = Path::new(&item.destination);
+    if !source.exists() {
+        log::warn!("{}", tr!("log-source-missing", path = item.source.as_str()));
+        return Err(failure(item, MoveErrorKind::SourceMissing, "move-source-missing", &[]));
+    }
+
+    // 按策略不传输的文件也记入日志，便于查看每个文件的处理结果
+    let untouched = MoveRecord {
+        original_path: item.source.clone(),
+        new_path: item.destination.clone(),
+        filename: item.filename.clone(),
+        mode: item.mode,
+        dest_stamp: None,
+        conflict: item.conflict,
+        dest_hash: None,
+        group: item.group.clone(),
+    };
+    let io_failure = |e: io::Error| {
+        log::error!(
+            "{}",
+            tr!(
+                "log-transfer-failed",
+                source = item.source.as_str(),
+                destination = item.destination.as_str(),
+                mode = format!("{:?}", item.mode),
+                detail = e.to_string(),
+            )
+        );
+        error_failure(item, error_kind(&e), AppError::io("move-transfer-failed", &e))
     };
+    let mut overwrite = false;
+    match item.conflict {
+        Some(ConflictOutcome::Skipped | ConflictOutcome::KeptExisting) => {
+            log::info!(
+                "{}",
+                tr!(
+                    "log-skipped-existing",
+                    source = item.source.as_str(),
+                    destination = item.destination.as_str(),
+                )
+            );
+            return Ok(untouched);
+        }
+        Some(ConflictOutcome::Deduplicated) => {
+            // 计划生成后目标可能又被改动，删除源文件前再确认一次内容
+            if !files_identical(source, dest_path) {
+                log::warn!("{}", tr!("log-dedup-changed", path = item.destination.as_str()));
+                return Err(failure(item, MoveErrorKind::ContentChanged, "move-content-changed", &[]));
+            }
+            if item.mode == TransferMode::Move {
+                discard_file(source, settings.use_trash).map_err(io_failure)?;
+            }
+            return Ok(untouched);
+        }
+        Some(ConflictOutcome::Overwritten) => {
+            if let Ok(meta) = dest_path.symlink_metadata() {
+                if meta.is_dir() {
+                    log::warn!("{}", tr!("log-destination-is-dir", path = item.destination.as_str()));
+                    return Err(failure(item, MoveErrorKind::DestinationExists, "move-destination-is-dir", &[]));
+                }
+                overwrite = true;
+            }
+        }
+        _ => {
+            if dest_path.symlink_metadata().is_ok() {
+                log::warn!("{}", tr!("log-destination-taken", path = item.destination.as_str()));
+                return Err(failure(item, MoveErrorKind::DestinationExists, "move-destination-taken", &[]));
+            }
+        }
+    }
+
+    if let Some(dest_dir) = dest_path.parent() {
+        dirs.lock().ensure(dest_dir).map_err(io_failure)?;
+    }
+
+    if overwrite {
+        replace_file(source, dest_path, item.mode, settings, cancel, on_bytes).map_err(io_failure)?;
+        log::warn!("{}", tr!("log-overwritten", path = item.destination.as_str()));
+    } else {
+        transfer_file(source, dest_path, item.mode, settings, cancel, on_bytes).map_err(io_failure)?;
+    }
+    Ok(MoveRecord {
+        dest_stamp: link_stamp(dest_path),
+        dest_hash: content_hash(dest_path, item.mode),
+        ..untouched
+    })
+}
 
-    Ok(log)
+/// 把 I/O 错误归类为前端可区分的失败原因
+fn error_kind(e: &io::Error) -> MoveErrorKind {
+    match e.kind() {
+        io::ErrorKind::NotFound => MoveErrorKind::SourceMissing,
+        io::ErrorKind::AlreadyExists => MoveErrorKind::DestinationExists,
+        io::ErrorKind::PermissionDenied => MoveErrorKind::PermissionDenied,
+        io::ErrorKind::Unsupported => MoveErrorKind::Unsupported,
+        io::ErrorKind::Interrupted => MoveErrorKind::Cancelled,
+        io::ErrorKind::InvalidData => MoveErrorKind::VerifyFailed,
+        _ if is_storage_full(e) => MoveErrorKind::NoSpace,
+        _ => MoveErrorKind::Io,
+    }
 }
 
-/// 撤销移动操作：将文件移回原处
-pub fn undo_move(operation_log: &OperationLog) -> Result<usize, String> {
-    let mut restored = 0;
+/// 磁盘空间不足（io::ErrorKind::StorageFull 需要更新的 Rust 版本，这里按系统错误码判断）
+fn is_storage_full(e: &io::Error) -> bool {
+    // ENOSPC 在 Linux、macOS 和 BSD 上都是 28；Windows 为 ERROR_HANDLE_DISK_FULL / ERROR_DISK_FULL
+    if cfg!(windows) {
+        matches!(e.raw_os_error(), Some(39 | 112))
+    } else {
+        e.raw_os_error() == Some(28)
+    }
+}
 
-    for record in &operation_log.records {
-        let new_path = Path::new(&record.new_path);
-        let original_path = Path::new(&record.original_path);
+/// 按策略决定目标已有同名文件时的处理结果（只读取，不改动磁盘）
+fn resolve_conflict(policy: ConflictPolicy, source: &Path, existing: &Path) -> ConflictOutcome {
+    // 源文件就是目标文件本身（已在正确位置）时不能覆盖或删除
+    if is_same_file(source, existing) {
+        return ConflictOutcome::Skipped;
+    }
+    let (source_meta, existing_meta) = match (fs::metadata(source), fs::metadata(existing)) {
+        (Ok(s), Ok(e)) if e.is_file() => (s, e),
+        // 目标是文件夹或失效的链接时只能改名
+        _ => return ConflictOutcome::Renamed,
+    };
 
-        if !new_path.exists() {
-            log::warn!("要恢复的文件不存在: {}", record.new_path);
-            continue;
+    match policy {
+        ConflictPolicy::Rename => ConflictOutcome::Renamed,
+        ConflictPolicy::Skip => ConflictOutcome::Skipped,
+        ConflictPolicy::Overwrite => ConflictOutcome::Overwritten,
+        ConflictPolicy::KeepLarger => {
+            if source_meta.len() > existing_meta.len() {
+                ConflictOutcome::Overwritten
+            } else {
+                ConflictOutcome::KeptExisting
+            }
+        }
+        ConflictPolicy::KeepNewer => match (source_meta.modified(), existing_meta.modified()) {
+            (Ok(s), Ok(e)) if s > e => ConflictOutcome::Overwritten,
+            _ => ConflictOutcome::KeptExisting,
+        },
+        ConflictPolicy::Deduplicate => {
+            if files_identical(source, existing) {
+                ConflictOutcome::Deduplicated
+            } else {
+                ConflictOutcome::Renamed
+            }
+        }
+    }
+}
+
+/// 整组的冲突处理结果（`wanted` 第一项为图片的目标路径，其余与 `companions` 一一对应）：
+/// 图片的目标已存在时按策略决定，伴随文件跟随图片；
+/// 只有伴随文件的目标被占用，或伴随文件内容不同而无法一起去重时，整组改名
+fn resolve_group_conflict(
+    policy: ConflictPolicy,
+    source: &Path,
+    companions: &[&Path],
+    wanted: &[PathBuf],
+) -> ConflictOutcome {
+    if wanted[0].symlink_metadata().is_err() {
+        return ConflictOutcome::Renamed;
+    }
+    let outcome = resolve_conflict(policy, source, &wanted[0]);
+    let companions_differ = companions
+        .iter()
+        .zip(&wanted[1..])
+        .any(|(companion, dest)| dest.symlink_metadata().is_ok() && !files_identical(companion, dest));
+    if outcome == ConflictOutcome::Deduplicated && companions_differ {
+        return ConflictOutcome::Renamed;
+    }
+    outcome
+}
+
+/// 伴随文件跟随图片的冲突处理结果：跳过时整组跳过，
+/// 覆盖和去重只作用于目标处已存在的伴随文件
+fn companion_outcome(group: Option<ConflictOutcome>, dest: &Path) -> Option<ConflictOutcome> {
+    match group {
+        Some(ConflictOutcome::Overwritten | ConflictOutcome::Deduplicated) => {
+            dest.symlink_metadata().is_ok().then_some(group).flatten()
         }
+        other => other,
+    }
+}
+
+/// 两个路径是否指向同一个文件（同一路径、符号链接或硬链接）
+#[cfg(unix)]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => (a.dev(), a.ino()) == (b.dev(), b.ino()),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 两个文件内容是否相同：先比较大小，再比较 BLAKE3 哈希
+pub(crate) fn files_identical(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(ma), Ok(mb)) if ma.is_file() && mb.is_file() && ma.len() == mb.len() => {}
+        _ => return false,
+    }
+    match (file_hash(a), file_hash(b)) {
+        (Ok(ha), Ok(hb)) => ha == hb,
+        _ => false,
+    }
+}
+
+/// 传输后目标文件的内容哈希，供撤销时确认；链接不含独立内容，不计算
+pub(crate) fn content_hash(path: &Path, mode: TransferMode) -> Option<String> {
+    match mode {
+        TransferMode::Hardlink | TransferMode::Symlink => None,
+        _ => file_hash(path).ok().map(|hash| hash.to_hex().to_string()),
+    }
+}
+
+fn file_hash(path: &Path) -> io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(fs::File::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+/// 校验改名模式：必须包含 {n}，只能使用 {stem}、{n}、{ext}，且不能包含路径分隔符
+fn validate_rename_pattern(pattern: &str) -> AppResult<()> {
+    let invalid = |key| {
+        AppError::with_args(ErrorKind::InvalidInput, key, &[("pattern", FluentValue::from(pattern))])
+    };
+    if !pattern.contains("{n}") {
+        return Err(invalid("rename-pattern-missing-n"));
+    }
+    if pattern.contains(['/', '\\']) {
+        return Err(invalid("rename-pattern-separator"));
+    }
+    let rest = pattern
+        .replace("{stem}", "")
+        .replace("{n}", "")
+        .replace("{ext}", "");
+    if rest.contains(['{', '}']) {
+        return Err(invalid("rename-pattern-unknown-var"));
+    }
+    Ok(())
+}
+
+/// 找到路径自身或最近的已存在上级目录（计划阶段目标文件夹可能尚未创建）
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|p| p.exists())
+}
+
+/// 源文件与目标目录是否在同一设备上；无法判断时视为相同
+#[cfg(unix)]
+fn same_device(source_meta: &fs::Metadata, dest_dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match existing_ancestor(dest_dir).and_then(|dir| fs::metadata(dir).ok()) {
+        Some(meta) => meta.dev() == source_meta.dev(),
+        None => true,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_device(_source_meta: &fs::Metadata, _dest_dir: &Path) -> bool {
+    true
+}
+
+/// 当前用户能否在目录（或其最近的已存在上级）中创建文件。
+/// 权限位只反映文件所有者的写权限，这里按实际用户检查，同时能发现只读挂载
+#[cfg(unix)]
+fn is_readonly_dir(dir: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
 
-        // 确保原始目录存在
-        if let Some(parent) = original_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| format!("创建原始目录失败: {}", e))?;
+    existing_ancestor(dir)
+        .and_then(|dir| CString::new(dir.as_os_str().as_bytes()).ok())
+        // SAFETY: path 是以 NUL 结尾的有效字符串，调用期间一直存活
+        .is_some_and(|path| unsafe { libc::access(path.as_ptr(), libc::W_OK) } != 0)
+}
+
+#[cfg(not(unix))]
+fn is_readonly_dir(dir: &Path) -> bool {
+    existing_ancestor(dir)
+        .and_then(|dir| fs::metadata(dir).ok())
+        .is_some_and(|meta| meta.permissions().readonly())
+}
+
+/// 按多人物策略计算一张图片要放入的文件夹（即模板中的 {person}）及各自的传输方式；
+/// 主文件夹总是最后一项，使用 `options.mode`
+pub fn person_targets(image: &MoveImageRequest, options: &MoveOptions) -> Vec<(String, TransferMode)> {
+    let mode = options.mode;
+    let primary = if image.person.is_empty() {
+        image.persons.first().cloned().unwrap_or_default()
+    } else {
+        image.person.clone()
+    };
+    let mut persons: Vec<&String> = Vec::new();
+    for person in &image.persons {
+        if !person.is_empty() && !persons.contains(&person) {
+            persons.push(person);
+        }
+    }
+    if persons.len() < 2 {
+        return vec![(primary, mode)];
+    }
+
+    match options.multi_person {
+        MultiPersonStrategy::Primary => vec![(primary, mode)],
+        MultiPersonStrategy::CopyAll | MultiPersonStrategy::HardlinkAll => {
+            let extra_mode = if options.multi_person == MultiPersonStrategy::CopyAll {
+                TransferMode::Copy
+            } else {
+                TransferMode::Hardlink
+            };
+            let mut targets: Vec<(String, TransferMode)> = persons
+                .into_iter()
+                .filter(|p| **p != primary)
+                .map(|p| (p.clone(), extra_mode))
+                .collect();
+            targets.push((primary, mode));
+            targets
+        }
+        MultiPersonStrategy::Combined => {
+            let mut names: Vec<&str> = persons.iter().map(|p| p.as_str()).collect();
+            names.sort();
+            vec![(names.join("+"), mode)]
+        }
+        MultiPersonStrategy::Group => {
+            if persons.len() > options.group_threshold {
+                vec![(GROUP_FOLDER_NAME.to_string(), mode)]
+            } else {
+                vec![(primary, mode)]
             }
         }
+    }
+}
 
-        // 移回原处
-        match fs::rename(new_path, original_path) {
-            Ok(()) => {
-                restored += 1;
+/// 解析可选的路径模板；未指定或为空时返回 None
+pub fn parse_template(template: Option<&str>) -> AppResult<Option<PathTemplate>> {
+    match template.map(str::trim) {
+        Some(t) if !t.is_empty() => PathTemplate::parse(t).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 计算单个文件的目标路径（不处理同名冲突）；
+/// 没有模板时为 target_dir/<person>/<filename>，人物名称按 `rules` 清理为安全的文件夹名
+pub fn destination_path(
+    template: Option<&PathTemplate>,
+    target_dir: &Path,
+    source: &Path,
+    person: &str,
+    rules: &NameRules,
+) -> PathBuf {
+    match template {
+        Some(template) => {
+            let ctx = TemplateContext::from_source(source, person, template.needs_metadata());
+            target_dir.join(template.render(&ctx, rules))
+        }
+        None => {
+            let filename = source.file_name().unwrap_or_default();
+            let folder = sanitize::sanitize_component(person, rules);
+            if folder.is_empty() {
+                target_dir.join(filename)
+            } else {
+                target_dir.join(folder).join(filename)
             }
-            Err(_) => {
-                // 跨卷时用 copy + delete
-                match fs::copy(new_path, original_path) {
-                    Ok(_) => {
-                        let _ = fs::remove_file(new_path);
-                        restored += 1;
-                    }
-                    Err(e) => {
-                        log::error!("恢复文件失败: {} -> {}: {}", record.new_path, record.original_path, e);
-                    }
+        }
+    }
+}
+
+/// 按指定方式把单个文件传输到目标路径
+fn transfer_file(
+    source: &Path,
+    dest: &Path,
+    mode: TransferMode,
+    settings: TransferSettings,
+    cancel: Option<&AtomicBool>,
+    on_bytes: &dyn Fn(u64),
+) -> io::Result<()> {
+    match mode {
+        TransferMode::Move => match fs::rename(source, dest) {
+            Ok(()) => Ok(()),
+            Err(e) if is_cross_device(&e) => {
+                // 跨卷移动：确认副本完整后才删除源文件，删除失败时撤回副本
+                copy_verified(source, dest, settings.verify_checksum, cancel, on_bytes)?;
+                if let Err(e) = discard_file(source, settings.use_trash) {
+                    let _ = fs::remove_file(dest);
+                    return Err(e);
                 }
+                Ok(())
             }
+            Err(e) => Err(e),
+        },
+        TransferMode::Copy => copy_verified(source, dest, settings.verify_checksum, cancel, on_bytes),
+        TransferMode::Hardlink => fs::hard_link(source, dest),
+        TransferMode::Symlink => {
+            // 链接指向绝对路径，避免目标文件夹移动后失效
+            let target = fs::canonicalize(source)?;
+            create_symlink(&target, dest)
         }
+        TransferMode::Reflink => reflink_file(source, dest),
     }
+}
 
-    // 清理可能留下的空人物文件夹
-    cleanup_empty_dirs(&operation_log.target_dir);
+/// 覆盖已有文件：先把新文件完整传输到目标旁的暂存文件，再替换目标，
+/// 新文件就位之前旧文件不会被删除（使用回收站时在替换前一刻移到回收站）。
+/// 传输或替换失败时撤回暂存文件，目标保持原样
+fn replace_file(
+    source: &Path,
+    dest: &Path,
+    mode: TransferMode,
+    settings: TransferSettings,
+    cancel: Option<&AtomicBool>,
+    on_bytes: &dyn Fn(u64),
+) -> io::Result<()> {
+    let staged = staged_path(dest);
+    transfer_file(source, &staged, mode, settings, cancel, on_bytes)?;
+    let replaced = if settings.use_trash {
+        discard_file(dest, true).and_then(|_| fs::rename(&staged, dest))
+    } else {
+        // rename 直接替换已有文件，替换前旧文件一直完好
+        fs::rename(&staged, dest)
+    };
+    if let Err(e) = replaced {
+        if let Err(revert) = unstage(source, &staged, mode) {
+            log::error!(
+                "{}",
+                tr!(
+                    "log-unstage-failed",
+                    path = staged.display().to_string(),
+                    detail = revert.to_string(),
+                )
+            );
+        }
+        return Err(e);
+    }
+    Ok(())
+}
 
-    Ok(restored)
+/// 撤回暂存文件：移动模式下把文件放回源位置，其他模式删除暂存的副本或链接
+pub(crate) fn unstage(source: &Path, staged: &Path, mode: TransferMode) -> io::Result<()> {
+    if mode != TransferMode::Move {
+        return fs::remove_file(staged);
+    }
+    match fs::rename(staged, source) {
+        Err(e) if is_cross_device(&e) => {
+            copy_verified(staged, source, false, None, &|_| {})?;
+            fs::remove_file(staged)
+        }
+        result => result,
+    }
 }
 
-/// 解决文件名冲突：如果目标已存在同名文件，添加数字后缀
-fn resolve_filename_conflict(dir: &Path, filename: &str) -> PathBuf {
-    let dest = dir.join(filename);
-    if !dest.exists() {
-        return dest;
+/// rename 是否因源和目标不在同一设备而失败
+/// （io::ErrorKind::CrossesDevices 需要更新的 Rust 版本，这里按系统错误码判断）
+fn is_cross_device(e: &io::Error) -> bool {
+    // EXDEV 在 Linux、macOS 和 BSD 上都是 18；Windows 为 ERROR_NOT_SAME_DEVICE
+    if cfg!(windows) {
+        e.raw_os_error() == Some(17)
+    } else {
+        e.raw_os_error() == Some(18)
+    }
+}
+
+/// 复制过程中使用的临时文件后缀
+const TEMP_SUFFIX: &str = "tag2dir-tmp";
+/// 覆盖已有文件时暂存新文件的后缀
+const STAGED_SUFFIX: &str = "tag2dir-staged";
+
+/// 复制过程中使用的临时文件：与目标同目录的隐藏文件，校验通过后才改名为目标
+pub(crate) fn temp_path(dest: &Path) -> PathBuf {
+    work_path(dest, TEMP_SUFFIX)
+}
+
+/// 覆盖已有文件时暂存新文件的路径：与目标同目录的隐藏文件，内容完整后才替换目标
+pub(crate) fn staged_path(dest: &Path) -> PathBuf {
+    work_path(dest, STAGED_SUFFIX)
+}
+
+/// 目标旁残留的临时文件（中断的复制留下的）
+pub(crate) fn temp_files(dest: &Path) -> Vec<PathBuf> {
+    work_files(dest, TEMP_SUFFIX)
+}
+
+/// 目标旁残留的暂存文件（中断的覆盖留下的）
+pub(crate) fn staged_files(dest: &Path) -> Vec<PathBuf> {
+    work_files(dest, STAGED_SUFFIX)
+}
+
+/// 工作文件路径 `.<目标文件名>.<随机串>.<后缀>`：每次传输都用新名称，
+/// 上次中断残留的文件不会挡住之后的传输
+fn work_path(dest: &Path, suffix: &str) -> PathBuf {
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let id = uuid::Uuid::new_v4().simple();
+    dest.with_file_name(format!(".{}.{}.{}", name, id, suffix))
+}
+
+/// 按 `work_path` 的命名规则查找目标旁已有的工作文件
+fn work_files(dest: &Path, suffix: &str) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (dest.parent(), dest.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!(".{}.", name.to_string_lossy());
+    let suffix = format!(".{}", suffix);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(&suffix))
+                .is_some_and(|id| id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit()))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// 可靠复制：分块写入临时文件（每写入一块报告一次进度），落盘后校验大小和可选的哈希，
+/// 保留修改/访问时间、权限和扩展属性，最后改名为目标。
+/// 取消或出错时删除临时文件，目标路径不会出现不完整的文件
+fn copy_verified(
+    source: &Path,
+    dest: &Path,
+    verify_checksum: bool,
+    cancel: Option<&AtomicBool>,
+    on_bytes: &dyn Fn(u64),
+) -> io::Result<()> {
+    let mut reader = fs::File::open(source)?;
+    let source_meta = reader.metadata()?;
+    let temp = temp_path(dest);
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)?;
+
+    let result = (|| {
+        let mut hasher = verify_checksum.then(blake3::Hasher::new);
+        let mut buf = vec![0u8; COPY_CHUNK_SIZE];
+        let mut written = 0u64;
+        loop {
+            if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, tr!("copy-cancelled")));
+            }
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buf[..n])?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&buf[..n]);
+            }
+            written += n as u64;
+            on_bytes(written);
+        }
+        writer.sync_all()?;
+
+        let copied = fs::metadata(&temp)?.len();
+        if written != source_meta.len() || copied != written {
+            return Err(verify_error(tr!(
+                "copy-size-mismatch",
+                source = source_meta.len(),
+                copy = copied,
+            )));
+        }
+        if let Some(hasher) = hasher {
+            if file_hash(&temp)? != hasher.finalize() {
+                return Err(verify_error(tr!("copy-hash-mismatch")));
+            }
+        }
+
+        // 扩展属性要在设为只读之前写入；时间最后设置，避免被前面的写入改掉
+        copy_xattrs(source, &temp);
+        writer.set_permissions(source_meta.permissions())?;
+        let mut times = fs::FileTimes::new();
+        if let Ok(accessed) = source_meta.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        if let Ok(modified) = source_meta.modified() {
+            times = times.set_modified(modified);
+        }
+        writer.set_times(times)?;
+        writer.sync_all()?;
+
+        // 计划生成后目标可能已被占用，不覆盖
+        if dest.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                tr!("copy-destination-taken"),
+            ));
+        }
+        fs::rename(&temp, dest)
+    })();
+
+    if result.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn verify_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 复制扩展属性（如 macOS 的标签和来源信息）；目标文件系统不支持时只记录警告
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, dest: &Path) {
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(e) => {
+            log::warn!(
+                "{}",
+                tr!(
+                    "log-xattr-read-failed",
+                    path = source.display().to_string(),
+                    detail = e.to_string(),
+                )
+            );
+            return;
+        }
+    };
+    for name in names {
+        let copied = xattr::get(source, &name)
+            .and_then(|value| value.map_or(Ok(()), |value| xattr::set(dest, &name, &value)));
+        if let Err(e) = copied {
+            log::warn!(
+                "{}",
+                tr!(
+                    "log-xattr-copy-failed",
+                    name = name.to_string_lossy().to_string(),
+                    path = dest.display().to_string(),
+                    detail = e.to_string(),
+                )
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_xattrs(_source: &Path, _dest: &Path) {}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// 写时复制克隆（Linux FICLONE ioctl，需要 Btrfs/XFS 等文件系统支持）
+#[cfg(target_os = "linux")]
+fn reflink_file(source: &Path, dest: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src = fs::File::open(source)?;
+    let dst = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?;
+    // SAFETY: 两个文件描述符在调用期间都有效
+    let ret = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+    if ret != 0 {
+        let err = io::Error::last_os_error();
+        drop(dst);
+        let _ = fs::remove_file(dest);
+        // 文件系统不支持克隆、源和目标不在同一文件系统时统一报告为不支持，
+        // 提示改用其他传输方式
+        let unsupported = [libc::EOPNOTSUPP, libc::EXDEV, libc::EINVAL, libc::ENOTTY];
+        if err.raw_os_error().is_some_and(|code| unsupported.contains(&code)) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                tr!("reflink-unsupported-fs", detail = err.to_string()),
+            ));
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink_file(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        tr!("reflink-unsupported"),
+    ))
+}
+
+/// 读取目标路径本身（不跟随符号链接）的指纹
+pub(crate) fn link_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::symlink_metadata(path).ok()?;
+    let mtime_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Some(FileStamp {
+        size: meta.len(),
+        mtime_ms,
+    })
+}
+
+/// 撤销操作：移动的文件移回原处；复制、链接产生的文件删除。
+/// 每个文件先确认目标处仍是当初传输的文件（指纹和哈希一致）、原位置没有被占用，
+/// 不满足时跳过并在结果中注明原因；同组的图片和伴随文件只要有一个不能撤销，整组都不动。
+/// 按策略跳过的文件不做处理；覆盖时被替换掉的原文件无法恢复
+pub fn undo_move(operation_log: &OperationLog) -> Vec<UndoFileResult> {
+    let records: Vec<&MoveRecord> = operation_log.records.iter().filter(|r| r.needs_undo()).collect();
+    let mut results = Vec::with_capacity(records.len());
+
+    for group in records.chunk_by(|a, b| a.group.is_some() && a.group == b.group) {
+        let checks: Vec<Option<UndoOutcome>> = group.iter().map(|record| check_undo(record)).collect();
+        let blocked = checks
+            .iter()
+            .any(|check| check.is_some_and(|outcome| outcome != UndoOutcome::Missing));
+        for (record, check) in group.iter().zip(checks) {
+            let (outcome, error) = match check {
+                Some(outcome) => (outcome, None),
+                None if blocked => {
+                    log::warn!("{}", tr!("log-undo-group-skipped", path = record.new_path.as_str()));
+                    (UndoOutcome::SkippedGroup, None)
+                }
+                None => match restore_record(record) {
+                    Ok(()) => (UndoOutcome::Restored, None),
+                    Err(e) => {
+                        log::error!(
+                            "{}",
+                            tr!(
+                                "log-restore-failed",
+                                source = record.new_path.as_str(),
+                                destination = record.original_path.as_str(),
+                                detail = e.message.as_str(),
+                            )
+                        );
+                        (UndoOutcome::Failed, Some(e))
+                    }
+                },
+            };
+            results.push(UndoFileResult {
+                original_path: record.original_path.clone(),
+                new_path: record.new_path.clone(),
+                outcome,
+                error_key: error.as_ref().map(|e| e.key.to_string()),
+                error_kind: error.as_ref().map(|e| e.kind),
+                error: error.map(|e| e.message),
+            });
+        }
+    }
+
+    // 删除本次操作新建、现在已空的文件夹
+    remove_created_dirs(&operation_log.created_dirs);
+
+    results
+}
+
+/// 撤销单个文件
+fn undo_record(record: &MoveRecord) -> AppResult<UndoOutcome> {
+    match check_undo(record) {
+        Some(outcome) => Ok(outcome),
+        None => restore_record(record).map(|_| UndoOutcome::Restored),
+    }
+}
+
+/// 撤销前的检查：返回无法撤销的原因，可以撤销时返回 None
+fn check_undo(record: &MoveRecord) -> Option<UndoOutcome> {
+    let new_path = Path::new(&record.new_path);
+    let original_path = Path::new(&record.original_path);
+
+    // 符号链接的目标可能已不存在，用 symlink_metadata 判断链接本身
+    if fs::symlink_metadata(new_path).is_err() {
+        log::warn!("{}", tr!("log-undo-missing", path = record.new_path.as_str()));
+        return Some(UndoOutcome::Missing);
+    }
+    if !is_unchanged(record, new_path, original_path) {
+        log::warn!("{}", tr!("log-undo-modified", path = record.new_path.as_str()));
+        return Some(UndoOutcome::SkippedModified);
+    }
+    // 原位置已有文件时不移回，避免覆盖用户之后放入的文件
+    if restores_original(record) && fs::symlink_metadata(original_path).is_ok() {
+        log::warn!("{}", tr!("log-undo-occupied", path = record.original_path.as_str()));
+        return Some(UndoOutcome::SkippedOccupied);
+    }
+    None
+}
+
+/// 撤销时是否要把文件放回原处（否则只需删除副本或链接）
+fn restores_original(record: &MoveRecord) -> bool {
+    record.mode == TransferMode::Move || record.conflict == Some(ConflictOutcome::Deduplicated)
+}
+
+/// 恢复单个已通过检查的文件
+fn restore_record(record: &MoveRecord) -> AppResult<()> {
+    let new_path = Path::new(&record.new_path);
+    let original_path = Path::new(&record.original_path);
+
+    if !restores_original(record) {
+        // 副本和链接删除即可，原文件一直在原处
+        return fs::remove_file(new_path)
+            .map_err(|e| AppError::io("undo-remove-copy-failed", &e).with_path(new_path));
+    }
+    if let Some(parent) = original_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("undo-create-dir-failed", &e).with_path(parent))?;
+    }
+
+    // 去重时删除的源文件：目标处的文件本来就在，复制一份回原处即可
+    if record.conflict == Some(ConflictOutcome::Deduplicated) {
+        fs::copy(new_path, original_path).map_err(|e| restore_failed(&e, original_path))?;
+        return Ok(());
+    }
+
+    // 移回原处，跨卷时先完整复制回去再删除
+    match fs::rename(new_path, original_path) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            copy_verified(new_path, original_path, false, None, &|_| {})
+                .map_err(|e| restore_failed(&e, original_path))?;
+            fs::remove_file(new_path)
+                .map_err(|e| AppError::io("undo-remove-moved-failed", &e).with_path(new_path))
+        }
+        Err(e) => Err(restore_failed(&e, original_path)),
+    }
+}
+
+fn restore_failed(e: &io::Error, original_path: &Path) -> AppError {
+    AppError::io("undo-restore-failed", e).with_path(original_path)
+}
+
+/// 目标处的文件是否仍是当初传输的那个：
+/// 链接检查是否仍指向原文件，其他方式比较传输时记录的指纹和内容哈希。
+/// 旧版本日志没有记录指纹时不做检查
+fn is_unchanged(record: &MoveRecord, new_path: &Path, original_path: &Path) -> bool {
+    match record.mode {
+        TransferMode::Symlink => {
+            // 链接创建时指向源文件的规范路径，记录中的原路径可能经过符号链接或含有 ".."
+            return fs::read_link(new_path).is_ok_and(|target| {
+                target == original_path || fs::canonicalize(original_path).is_ok_and(|c| c == target)
+            });
+        }
+        TransferMode::Hardlink if record.conflict.is_none() => {
+            return is_same_file(new_path, original_path) || stamp_matches(record, new_path);
+        }
+        _ => {}
+    }
+    if !stamp_matches(record, new_path) {
+        return false;
+    }
+    match &record.dest_hash {
+        Some(expected) => file_hash(new_path).is_ok_and(|hash| hash.to_hex().as_str() == expected),
+        None => true,
+    }
+}
+
+fn stamp_matches(record: &MoveRecord, path: &Path) -> bool {
+    record.dest_stamp.is_none() || link_stamp(path) == record.dest_stamp
+}
+
+/// 解决文件名冲突：如果目标已存在同名文件或已被计划中的其他文件占用，
+/// 按改名模式生成带数字的新文件名；`is_free` 判断一个文件名（连同伴随文件）是否可用
+fn resolve_filename_conflict(
+    dir: &Path,
+    filename: &str,
+    pattern: &str,
+    is_free: &dyn Fn(&str) -> bool,
+) -> PathBuf {
+    if is_free(filename) {
+        return dir.join(filename);
     }
 
     let path = Path::new(filename);
@@ -157,28 +1660,554 @@ fn resolve_filename_conflict(dir: &Path, filename: &str) -> PathBuf {
 
     let mut counter = 1;
     loop {
-        let new_name = format!("{}_{}{}", stem, counter, ext);
-        let new_path = dir.join(&new_name);
-        if !new_path.exists() {
-            return new_path;
+        let new_name = pattern
+            .replace("{stem}", &stem)
+            .replace("{ext}", &ext)
+            .replace("{n}", &counter.to_string());
+        if is_free(&new_name) {
+            return dir.join(new_name);
         }
         counter += 1;
     }
 }
 
-/// 清理空的子文件夹
-fn cleanup_empty_dirs(target_dir: &str) {
-    let target = Path::new(target_dir);
-    if let Ok(entries) = fs::read_dir(target) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                if let Ok(mut dir) = fs::read_dir(&path) {
-                    if dir.next().is_none() {
-                        let _ = fs::remove_dir(&path);
-                    }
+/// 删除操作新建的文件夹，由深到浅；只删除空文件夹，用户之后放入文件的保留
+fn remove_created_dirs(dirs: &[String]) {
+    let mut dirs: Vec<&Path> = dirs.iter().map(Path::new).collect();
+    dirs.sort_by_key(|dir| (std::cmp::Reverse(dir.components().count()), *dir));
+    dirs.dedup();
+    for dir in dirs {
+        let empty = fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none());
+        if empty {
+            if let Err(e) = fs::remove_dir(dir) {
+                log::warn!(
+                    "{}",
+                    tr!(
+                        "log-remove-dir-failed",
+                        path = dir.display().to_string(),
+                        detail = e.to_string(),
+                    )
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read, TempDir};
+
+    fn request(path: &Path, person: &str) -> MoveImageRequest {
+        MoveImageRequest {
+            path: path.to_string_lossy().to_string(),
+            filename: path.file_name().unwrap().to_string_lossy().to_string(),
+            person: person.to_string(),
+            persons: Vec::new(),
+        }
+    }
+
+    /// 在 `case` 子目录中准备源文件和目标处已有的同名文件，按策略生成计划
+    fn conflict_plan(
+        dir: &TempDir,
+        case: &str,
+        policy: ConflictPolicy,
+        source: &[u8],
+        existing: &[u8],
+    ) -> (PathBuf, PathBuf, MovePlan) {
+        let source = dir.write(&format!("{}/in/a.jpg", case), source);
+        let existing = dir.write(&format!("{}/out/a.jpg", case), existing);
+        let options = MoveOptions {
+            conflict_policy: policy,
+            ..MoveOptions::default()
+        };
+        let target = dir.join(&format!("{}/out", case));
+        let plan = plan_move(&[request(&source, "")], &target.to_string_lossy(), &options).unwrap();
+        (source, existing, plan)
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let dir = TempDir::new();
+        let apply = |plan: &MovePlan| apply_plan(plan, None, ApplyControl::default()).unwrap();
+
+        let (source, existing, plan) = conflict_plan(&dir, "skip", ConflictPolicy::Skip, b"new", b"old");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Skipped));
+        assert!(apply(&plan).failures.is_empty());
+        assert_eq!((read(&source), read(&existing)), (Some(b"new".to_vec()), Some(b"old".to_vec())));
+
+        let (source, existing, plan) = conflict_plan(&dir, "rename", ConflictPolicy::Rename, b"new", b"old");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Renamed));
+        assert_eq!(Path::new(&plan.items[0].destination), existing.with_file_name("a_1.jpg"));
+        apply(&plan);
+        assert_eq!(read(&source), None);
+        assert_eq!(read(&existing), Some(b"old".to_vec()));
+        assert_eq!(read(&existing.with_file_name("a_1.jpg")), Some(b"new".to_vec()));
+
+        let (source, existing, plan) =
+            conflict_plan(&dir, "overwrite", ConflictPolicy::Overwrite, b"new", b"old");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Overwritten));
+        assert!(apply(&plan).failures.is_empty());
+        assert_eq!((read(&source), read(&existing)), (None, Some(b"new".to_vec())));
+        assert!(staged_files(&existing).is_empty());
+
+        let (source, existing, plan) = conflict_plan(&dir, "dedup", ConflictPolicy::Deduplicate, b"same", b"same");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Deduplicated));
+        apply(&plan);
+        assert_eq!((read(&source), read(&existing)), (None, Some(b"same".to_vec())));
+        let (_, _, plan) = conflict_plan(&dir, "dedup-diff", ConflictPolicy::Deduplicate, b"new", b"old");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Renamed));
+
+        let (source, existing, plan) =
+            conflict_plan(&dir, "larger", ConflictPolicy::KeepLarger, b"larger", b"old");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::Overwritten));
+        apply(&plan);
+        assert_eq!((read(&source), read(&existing)), (None, Some(b"larger".to_vec())));
+        let (source, existing, plan) =
+            conflict_plan(&dir, "smaller", ConflictPolicy::KeepLarger, b"new", b"existing");
+        assert_eq!(plan.items[0].conflict, Some(ConflictOutcome::KeptExisting));
+        apply(&plan);
+        assert_eq!(read(&source), Some(b"new".to_vec()));
+        assert_eq!(read(&existing), Some(b"existing".to_vec()));
+    }
+
+    #[test]
+    fn test_failed_overwrite_keeps_existing() {
+        let dir = TempDir::new();
+        let (source, existing, mut plan) =
+            conflict_plan(&dir, "cancel", ConflictPolicy::Overwrite, b"new", b"old");
+        plan.items[0].mode = TransferMode::Copy;
+
+        // 文件开始传输时请求取消，复制在写入第一块之前中断
+        let cancel = AtomicBool::new(false);
+        let on_progress = |_: &MoveProgressEvent| cancel.store(true, Ordering::Relaxed);
+        let control = ApplyControl {
+            cancel: Some(&cancel),
+            on_progress: Some(&on_progress),
+        };
+        let report = apply_plan(&plan, None, control).unwrap();
+        assert_eq!(report.failures[0].kind, MoveErrorKind::Cancelled);
+        assert_eq!(read(&existing), Some(b"old".to_vec()));
+        assert_eq!(read(&source), Some(b"new".to_vec()));
+        assert!(staged_files(&existing).is_empty());
+    }
+
+    #[test]
+    fn test_apply_plan_rejects_escaping_destination() {
+        let dir = TempDir::new();
+        let source = dir.write("in/a.jpg", b"a");
+        let target = dir.join("out");
+        let mut plan =
+            plan_move(&[request(&source, "Alice")], &target.to_string_lossy(), &MoveOptions::default())
+                .unwrap();
+        for escaped in ["../../etc/x".to_string(), format!("{}/../x.jpg", target.display())] {
+            plan.items[0].destination = escaped;
+            let err = apply_plan(&plan, None, ApplyControl::default()).unwrap_err();
+            assert_eq!((err.kind, err.key), (ErrorKind::InvalidInput, "destination-outside-target"));
+        }
+        // 拒绝时没有任何改动
+        assert_eq!(read(&source), Some(b"a".to_vec()));
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_plan_issues() {
+        let dir = TempDir::new();
+        let tom = dir.write("in/a.jpg", b"a");
+        dir.write("out/Tom/a.jpg", b"old");
+        let amy = [dir.write("in/x/b.jpg", b"b1"), dir.write("in/y/b.jpg", b"b2")];
+        let missing = dir.join("in/missing.jpg");
+        let requests = [
+            request(&tom, "Tom"),
+            request(&amy[0], "Amy"),
+            request(&amy[1], "Amy"),
+            request(&missing, "Amy"),
+        ];
+        let target = dir.join("out");
+        let plan = plan_move(&requests, &target.to_string_lossy(), &MoveOptions::default()).unwrap();
+        let issues: Vec<&[PlanIssue]> = plan.items.iter().map(|i| i.issues.as_slice()).collect();
+        assert_eq!(
+            issues,
+            [
+                &[PlanIssue::NameConflict][..],
+                &[],
+                &[PlanIssue::DuplicateDestination],
+                &[PlanIssue::SourceMissing],
+            ]
+        );
+        assert_eq!((plan.conflict_count, plan.missing_count), (2, 1));
+        assert_eq!((plan.cross_device_count, plan.permission_count), (0, 0));
+        assert_eq!(plan.estimated_bytes, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plan_permission_issues() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new();
+        let source = dir.write("in/a.jpg", b"a");
+        let locked = dir.join("out/Tom");
+        fs::create_dir_all(&locked).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        // 以 root 运行时不受权限位限制，此时目录确实可写，不应报告问题
+        let writable = fs::write(locked.join("probe"), b"").is_ok();
+
+        let target = dir.join("out");
+        let plan_for = |person: &str, mode: TransferMode| {
+            let options = MoveOptions {
+                mode,
+                ..MoveOptions::default()
+            };
+            plan_move(&[request(&source, person)], &target.to_string_lossy(), &options).unwrap()
+        };
+        let expected = usize::from(!writable);
+        assert_eq!(plan_for("Tom", TransferMode::Copy).permission_count, expected);
+        assert_eq!(plan_for("Amy", TransferMode::Copy).permission_count, 0);
+
+        // 移动时源文件夹也必须可写
+        fs::set_permissions(dir.join("in"), fs::Permissions::from_mode(0o555)).unwrap();
+        assert_eq!(plan_for("Amy", TransferMode::Move).permission_count, expected);
+        assert_eq!(plan_for("Amy", TransferMode::Copy).permission_count, 0);
+
+        for path in [dir.join("in"), locked] {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plan_cross_device() {
+        // 需要与临时目录不在同一设备上的可写目录（通常是 tmpfs 的 /dev/shm）
+        let other = Path::new("/dev/shm");
+        if !other.is_dir() || device_id(other) == device_id(&std::env::temp_dir()) {
+            return;
+        }
+        let dir = TempDir::new();
+        let target = TempDir::new_in(other);
+        let source = dir.write("in/a.jpg", b"abcd");
+        let plan_for = |mode: TransferMode| {
+            let options = MoveOptions {
+                mode,
+                ..MoveOptions::default()
+            };
+            plan_move(&[request(&source, "Tom")], &target.path().to_string_lossy(), &options).unwrap()
+        };
+        for mode in [TransferMode::Move, TransferMode::Hardlink, TransferMode::Reflink] {
+            assert_eq!(plan_for(mode).cross_device_count, 1, "{:?}", mode);
+        }
+        for mode in [TransferMode::Copy, TransferMode::Symlink] {
+            assert_eq!(plan_for(mode).cross_device_count, 0, "{:?}", mode);
+        }
+        // 跨设备移动需要复制，计入预计写入量
+        assert_eq!(plan_for(TransferMode::Move).estimated_bytes, 4);
+    }
+
+    /// 把 `source` 按 `mode` 传输到 `target/Tom` 下并执行
+    fn transfer(source: &Path, target: &Path, mode: TransferMode) -> MoveReport {
+        let options = MoveOptions {
+            mode,
+            ..MoveOptions::default()
+        };
+        let plan = plan_move(&[request(source, "Tom")], &target.to_string_lossy(), &options).unwrap();
+        apply_plan(&plan, None, ApplyControl::default()).unwrap()
+    }
+
+    fn outcomes(results: &[UndoFileResult]) -> Vec<UndoOutcome> {
+        results.iter().map(|r| r.outcome).collect()
+    }
+
+    #[test]
+    fn test_transfer_modes_and_undo() {
+        let dir = TempDir::new();
+        for mode in [
+            TransferMode::Move,
+            TransferMode::Copy,
+            TransferMode::Hardlink,
+            TransferMode::Symlink,
+        ] {
+            let case = format!("{:?}", mode);
+            let source = dir.write(&format!("{}/in/a.jpg", case), b"photo");
+            let target = dir.join(&format!("{}/out", case));
+            let dest = target.join("Tom/a.jpg");
+            let report = transfer(&source, &target, mode);
+            assert!(report.failures.is_empty(), "{:?}", mode);
+            assert_eq!(read(&dest), Some(b"photo".to_vec()), "{:?}", mode);
+            let kept = read(&source).is_some();
+            assert_eq!(kept, mode != TransferMode::Move, "{:?}", mode);
+            match mode {
+                TransferMode::Hardlink => assert!(is_same_file(&dest, &source)),
+                TransferMode::Symlink => {
+                    assert_eq!(fs::read_link(&dest).unwrap(), fs::canonicalize(&source).unwrap());
                 }
+                _ => assert!(!dest.symlink_metadata().unwrap().file_type().is_symlink()),
+            }
+
+            // 撤销后只剩原文件，新建的人物文件夹也被删除
+            let results = undo_move(&report.log);
+            assert_eq!(outcomes(&results), [UndoOutcome::Restored], "{:?}", mode);
+            assert_eq!(read(&source), Some(b"photo".to_vec()), "{:?}", mode);
+            assert!(dest.symlink_metadata().is_err(), "{:?}", mode);
+            assert!(!target.join("Tom").exists(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_undo_symlink_to_non_canonical_source() {
+        let dir = TempDir::new();
+        dir.write("in/a.jpg", b"photo");
+        dir.write("in/sub/b.jpg", b"photo");
+        create_symlink(&dir.join("in"), &dir.join("alias")).unwrap();
+        let target = dir.join("out");
+        // 经过符号链接的文件夹，以及含有 "." 和 ".." 的路径
+        for source in [dir.join("alias/a.jpg"), dir.join("in/./sub/../sub/b.jpg")] {
+            let report = transfer(&source, &target, TransferMode::Symlink);
+            assert!(report.failures.is_empty(), "{}", source.display());
+            assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::Restored], "{}", source.display());
+            assert_eq!(read(&source), Some(b"photo".to_vec()));
+        }
+        assert!(!target.join("Tom").exists());
+    }
+
+    #[test]
+    fn test_undo_skips_changed_files() {
+        let dir = TempDir::new();
+        let target = dir.join("out");
+
+        // 副本之后被修改，不能删除
+        let source = dir.write("in/a.jpg", b"photo");
+        let report = transfer(&source, &target, TransferMode::Copy);
+        let dest = target.join("Tom/a.jpg");
+        fs::write(&dest, b"edited photo").unwrap();
+        assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::SkippedModified]);
+        assert_eq!(read(&dest), Some(b"edited photo".to_vec()));
+
+        // 原位置又放入了文件，不移回
+        let source = dir.write("in/b.jpg", b"photo");
+        let report = transfer(&source, &target, TransferMode::Move);
+        dir.write("in/b.jpg", b"new");
+        assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::SkippedOccupied]);
+        assert_eq!(read(&target.join("Tom/b.jpg")), Some(b"photo".to_vec()));
+
+        // 链接被改为指向其他文件
+        let source = dir.write("in/c.jpg", b"photo");
+        let report = transfer(&source, &target, TransferMode::Symlink);
+        let link = target.join("Tom/c.jpg");
+        fs::remove_file(&link).unwrap();
+        create_symlink(&dir.join("in/b.jpg"), &link).unwrap();
+        assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::SkippedModified]);
+
+        // 已被删除的文件记为缺失
+        let source = dir.write("in/d.jpg", b"photo");
+        let report = transfer(&source, &target, TransferMode::Hardlink);
+        fs::remove_file(target.join("Tom/d.jpg")).unwrap();
+        assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::Missing]);
+    }
+
+    #[test]
+    fn test_copy_verified() {
+        let dir = TempDir::new();
+        let source = dir.write("in/a.jpg", b"photo");
+        let dest = dir.join("out/a.jpg");
+        fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        let copy = |cancel: Option<&AtomicBool>, on_bytes: &dyn Fn(u64)| {
+            copy_verified(&source, &dest, true, cancel, on_bytes)
+        };
+
+        // 上次中断残留的临时文件不影响之后的复制，也不会被误删
+        let stale = temp_path(&dest);
+        fs::write(&stale, b"ph").unwrap();
+        assert_eq!(temp_files(&dest), [stale.as_path()]);
+        copy(None, &|_| {}).unwrap();
+        assert_eq!(read(&dest), Some(b"photo".to_vec()));
+        assert_eq!(temp_files(&dest), [stale]);
+        fs::remove_file(&dest).unwrap();
+
+        // 复制过程中源文件变大：大小校验失败，目标和临时文件都不留下
+        let grown = AtomicBool::new(false);
+        let grow = |_: u64| {
+            if !grown.swap(true, Ordering::Relaxed) {
+                let mut file = fs::OpenOptions::new().append(true).open(&source).unwrap();
+                file.write_all(b" and more").unwrap();
+            }
+        };
+        let err = copy(None, &grow).unwrap_err();
+        assert_eq!(error_kind(&err), MoveErrorKind::VerifyFailed);
+        assert!(dest.symlink_metadata().is_err());
+        assert_eq!(temp_files(&dest).len(), 1);
+
+        // 写入第一块后取消
+        let cancel = AtomicBool::new(false);
+        let err = copy(Some(&cancel), &|_| cancel.store(true, Ordering::Relaxed)).unwrap_err();
+        assert_eq!(error_kind(&err), MoveErrorKind::Cancelled);
+        assert!(dest.symlink_metadata().is_err());
+        assert_eq!(temp_files(&dest).len(), 1);
+    }
+
+    #[test]
+    fn test_reflink_unsupported() {
+        let dir = TempDir::new();
+        let source = dir.write("in/a.jpg", b"photo");
+        let target = dir.join("out");
+        let report = transfer(&source, &target, TransferMode::Reflink);
+        let dest = target.join("Tom/a.jpg");
+        // 支持克隆的文件系统（Btrfs/XFS）上正常完成，其他文件系统报告不支持且不留下文件
+        match report.failures.first() {
+            None => {
+                assert_eq!(read(&dest), Some(b"photo".to_vec()));
+                assert_eq!(outcomes(&undo_move(&report.log)), [UndoOutcome::Restored]);
             }
+            Some(failure) => {
+                assert_eq!(failure.kind, MoveErrorKind::Unsupported);
+                assert!(dest.symlink_metadata().is_err());
+            }
+        }
+        assert_eq!(read(&source), Some(b"photo".to_vec()));
+
+        // 跨文件系统无法克隆
+        let other = Path::new("/dev/shm");
+        if !other.is_dir() || device_id(other) == device_id(&std::env::temp_dir()) {
+            return;
         }
+        let target = TempDir::new_in(other);
+        let report = transfer(&source, target.path(), TransferMode::Reflink);
+        let kinds: Vec<MoveErrorKind> = report.failures.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, [MoveErrorKind::Unsupported]);
+        assert!(target.join("Tom/a.jpg").symlink_metadata().is_err());
+    }
+
+    #[test]
+    fn test_resolve_filename_conflict() {
+        let taken = ["a.jpg", "a_1.jpg", "b.jpg"];
+        let is_free = |name: &str| !taken.contains(&name);
+        let dir = Path::new("/out");
+        assert_eq!(resolve_filename_conflict(dir, "c.jpg", DEFAULT_RENAME_PATTERN, &is_free), dir.join("c.jpg"));
+        assert_eq!(resolve_filename_conflict(dir, "a.jpg", DEFAULT_RENAME_PATTERN, &is_free), dir.join("a_2.jpg"));
+        assert_eq!(resolve_filename_conflict(dir, "b.jpg", "{stem} ({n}){ext}", &is_free), dir.join("b (1).jpg"));
+        assert_eq!(resolve_filename_conflict(dir, "b", DEFAULT_RENAME_PATTERN, &|n| n != "b"), dir.join("b_1"));
+    }
+
+    #[test]
+    fn test_validate_rename_pattern() {
+        assert!(validate_rename_pattern(DEFAULT_RENAME_PATTERN).is_ok());
+        assert!(validate_rename_pattern("{n}-{stem}{ext}").is_ok());
+        let key = |pattern| validate_rename_pattern(pattern).unwrap_err().key;
+        assert_eq!(key("{stem}{ext}"), "rename-pattern-missing-n");
+        assert_eq!(key("{n}/{stem}{ext}"), "rename-pattern-separator");
+        assert_eq!(key("{n}\\{stem}"), "rename-pattern-separator");
+        assert_eq!(key("{stem}_{n}{date}{ext}"), "rename-pattern-unknown-var");
+        assert_eq!(validate_rename_pattern("{stem}").unwrap_err().kind, ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_person_targets() {
+        let image = MoveImageRequest {
+            path: "/photos/a.jpg".to_string(),
+            filename: "a.jpg".to_string(),
+            person: "Bob".to_string(),
+            persons: vec!["Alice".to_string(), "Bob".to_string()],
+        };
+        let mut options = MoveOptions::default();
+        assert_eq!(person_targets(&image, &options), vec![("Bob".to_string(), TransferMode::Move)]);
+
+        options.multi_person = MultiPersonStrategy::CopyAll;
+        assert_eq!(
+            person_targets(&image, &options),
+            vec![
+                ("Alice".to_string(), TransferMode::Copy),
+                ("Bob".to_string(), TransferMode::Move),
+            ]
+        );
+
+        options.multi_person = MultiPersonStrategy::Combined;
+        assert_eq!(person_targets(&image, &options)[0].0, "Alice+Bob");
+
+        options.multi_person = MultiPersonStrategy::Group;
+        assert_eq!(person_targets(&image, &options)[0].0, GROUP_FOLDER_NAME);
+        options.group_threshold = 2;
+        assert_eq!(person_targets(&image, &options)[0].0, "Bob");
+    }
+
+    #[test]
+    fn test_failed_primary_reverts_person_copies() {
+        let dir = TempDir::new();
+        let source = dir.write("in/a.jpg", b"photo");
+        let target = dir.join("out");
+        let mut image = request(&source, "Alice");
+        image.persons = vec!["Alice".to_string(), "Bob".to_string()];
+        let options = MoveOptions {
+            multi_person: MultiPersonStrategy::CopyAll,
+            ..MoveOptions::default()
+        };
+        let plan = plan_move(&[image], &target.to_string_lossy(), &options).unwrap();
+        let modes: Vec<TransferMode> = plan.items.iter().map(|item| item.mode).collect();
+        assert_eq!(modes, [TransferMode::Copy, TransferMode::Move]);
+
+        // 主人物文件夹的位置被一个文件占用，最后的移动失败
+        dir.write("out/Alice", b"");
+        let report = apply_plan(&plan, None, ApplyControl::default()).unwrap();
+        assert!(report.log.records.is_empty());
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].kind, MoveErrorKind::CompanionFailed);
+        assert_eq!(report.failures[0].key, "move-person-reverted");
+        assert_eq!(report.failures[1].destination, plan.items[1].destination);
+        assert_eq!(report.failures[1].key, "move-transfer-failed");
+        assert_eq!(read(&target.join("Bob/a.jpg")), None);
+        assert_eq!(read(&source), Some(b"photo".to_vec()));
+
+        // 主文件夹可用时副本和移动都保留
+        fs::remove_file(target.join("Alice")).unwrap();
+        let report = apply_plan(&plan, None, ApplyControl::default()).unwrap();
+        assert!(report.failures.is_empty());
+        assert_eq!(report.log.records.len(), 2);
+        assert_eq!(read(&target.join("Bob/a.jpg")), Some(b"photo".to_vec()));
+        assert_eq!(read(&target.join("Alice/a.jpg")), Some(b"photo".to_vec()));
+        assert_eq!(read(&source), None);
+    }
+
+    #[test]
+    fn test_schedule_keeps_chains_together() {
+        let item = |source: &str, dest: &str, mode: TransferMode, group: Option<&str>| PlannedTransfer {
+            source: source.to_string(),
+            destination: dest.to_string(),
+            filename: String::new(),
+            mode,
+            size: 0,
+            conflict: None,
+            issues: Vec::new(),
+            group: group.map(str::to_string),
+        };
+        let plan = MovePlan {
+            target_dir: "/out".to_string(),
+            items: vec![
+                // a.jpg 带伴随文件，先复制给 Alice 再移动给 Bob
+                item("/in/a.jpg", "/out/Alice/a.jpg", TransferMode::Copy, Some("ga")),
+                item("/in/a.xmp", "/out/Alice/a.xmp", TransferMode::Copy, Some("ga")),
+                item("/in/a.jpg", "/out/Bob/a.jpg", TransferMode::Move, Some("gb")),
+                item("/in/a.xmp", "/out/Bob/a.xmp", TransferMode::Move, Some("gb")),
+                item("/in/b.jpg", "/out/Bob/b.jpg", TransferMode::Move, None),
+                item("/in/c.jpg", "/out/Bob/c.jpg", TransferMode::Copy, None),
+                item("/in/d.jpg", "/out/Bob/d.jpg", TransferMode::Move, None),
+            ],
+            estimated_bytes: 0,
+            conflict_count: 0,
+            missing_count: 0,
+            cross_device_count: 0,
+            permission_count: 0,
+            verify_checksum: false,
+            use_trash: false,
+            max_parallel: 4,
+        };
+
+        let tasks = schedule(&plan, &[0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            tasks,
+            vec![vec![vec![4..5], vec![6..7]], vec![vec![0..2, 2..4]], vec![vec![5..6]]]
+        );
+
+        // 继续中断的操作时，已处理的项不再调度，半完成的组只执行剩余部分
+        let tasks = schedule(&plan, &[1, 2, 3, 6]);
+        assert_eq!(tasks, vec![vec![vec![6..7]], vec![vec![1..2, 2..4]]]);
     }
 }
@@ -0,0 +1,525 @@
+// 操作历史模块
+// 将每次移动的操作日志持久化到数据目录，支持多级撤销和重做，
+// 应用重启后仍可撤销之前的操作。
+// 图形界面、命令行和监视任务可能同时写入，读改写期间持有历史目录中的锁文件
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::file_ops::{self, ApplyControl};
+use crate::i18n::{tr, FluentValue};
+use crate::journal;
+use crate::models::{MovePlan, MoveRecord, MoveReport, OperationLog, PlannedTransfer, UndoFileResult};
+
+/// 历史记录目录名（数据目录下，每次操作一个 JSON 文件）
+const HISTORY_DIR_NAME: &str = "history";
+/// 锁文件名（在历史记录目录中）
+const LOCK_FILE_NAME: &str = ".lock";
+
+/// 持久化的单次操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 操作日志
+    pub log: OperationLog,
+    /// 最近一次执行（或重做）的时间，Unix 毫秒，用于判断先后顺序
+    pub applied_at_ms: i64,
+    /// 是否已撤销
+    #[serde(default)]
+    pub undone: bool,
+    /// 撤销时间
+    #[serde(default)]
+    pub undone_at: Option<String>,
+}
+
+/// 历史列表中的一项（不含逐个文件的记录）
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub id: String,
+    pub timestamp: String,
+    pub target_dir: String,
+    /// 日志中的文件记录数
+    pub file_count: usize,
+    pub undone: bool,
+    pub undone_at: Option<String>,
+}
+
+/// 操作历史
+pub struct History {
+    data_dir: PathBuf,
+    dir: PathBuf,
+}
+
+impl History {
+    /// 打开数据目录下的操作历史（目录不存在时创建）
+    pub fn open(data_dir: &Path) -> AppResult<Self> {
+        let dir = data_dir.join(HISTORY_DIR_NAME);
+        fs::create_dir_all(&dir).map_err(|e| AppError::io("history-open", &e).with_path(&dir))?;
+        Ok(Self {
+            data_dir: data_dir.to_path_buf(),
+            dir,
+        })
+    }
+
+    /// 记录一次新执行的操作；写入后删除该操作的预写日志
+    pub fn record(&self, log: &OperationLog) -> AppResult<()> {
+        let _lock = self.lock()?;
+        self.save(&HistoryEntry {
+            log: log.clone(),
+            applied_at_ms: chrono::Local::now().timestamp_millis(),
+            undone: false,
+            undone_at: None,
+        })?;
+        journal::remove(&self.data_dir, &log.id);
+        Ok(())
+    }
+
+    /// 按执行时间从新到旧列出所有操作
+    pub fn list(&self) -> AppResult<Vec<HistorySummary>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .map(|entry| HistorySummary {
+                id: entry.log.id,
+                timestamp: entry.log.timestamp,
+                target_dir: entry.log.target_dir,
+                file_count: entry.log.records.len(),
+                undone: entry.undone,
+                undone_at: entry.undone_at,
+            })
+            .collect())
+    }
+
+    /// 读取单个操作
+    pub fn get(&self, id: &str) -> AppResult<HistoryEntry> {
+        let data = fs::read(self.entry_path(id)).map_err(|_| not_found(id))?;
+        serde_json::from_slice(&data).map_err(|e| AppError::internal("history-corrupt", e))
+    }
+
+    /// 是否还有未撤销的操作
+    pub fn has_undo(&self) -> bool {
+        self.entries()
+            .map(|entries| entries.iter().any(|e| !e.undone))
+            .unwrap_or(false)
+    }
+
+    /// 撤销指定操作；未指定时撤销最近一次未撤销的操作。
+    /// 较新的操作又移动过这次操作产生的文件时拒绝撤销，需要先撤销较新的操作。
+    /// 有文件未能恢复时操作不算撤销，只保留这些文件的记录，处理后可以再次撤销。
+    /// 返回操作日志和每个文件的撤销结果
+    pub fn undo(&self, id: Option<&str>) -> AppResult<(OperationLog, Vec<UndoFileResult>)> {
+        let _lock = self.lock()?;
+        let entries = self.entries()?;
+        let mut entry = match id {
+            Some(id) => entries
+                .iter()
+                .find(|e| e.log.id == id)
+                .cloned()
+                .ok_or_else(|| not_found(id))?,
+            None => entries
+                .iter()
+                .find(|e| !e.undone)
+                .cloned()
+                .ok_or_else(|| AppError::new(ErrorKind::NotFound, "undo-nothing"))?,
+        };
+        if entry.undone {
+            return Err(with_id(ErrorKind::InvalidInput, "undo-already-undone", &entry.log.id));
+        }
+
+        let produced: HashSet<&str> = entry
+            .log
+            .records
+            .iter()
+            .filter(|r| r.needs_undo())
+            .map(|r| r.new_path.as_str())
+            .collect();
+        let vacated: HashSet<&str> = entry
+            .log
+            .records
+            .iter()
+            .filter(|r| r.needs_undo())
+            .map(|r| r.original_path.as_str())
+            .collect();
+        let blocking = blocking_operations(&entries, &entry, |other| {
+            other.log.records.iter().filter(|r| r.needs_undo()).any(|r| {
+                produced.contains(r.original_path.as_str()) || vacated.contains(r.new_path.as_str())
+            })
+        });
+        if !blocking.is_empty() {
+            return Err(blocked("undo-blocked", &blocking));
+        }
+
+        let results = file_ops::undo_move(&entry.log);
+        if results.iter().all(|r| r.outcome.is_settled()) {
+            entry.undone = true;
+            entry.undone_at = Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+            self.save(&entry)?;
+            return Ok((entry.log, results));
+        }
+
+        // 部分撤销：已恢复的文件不再属于这次操作，其余的留待再次撤销
+        let log = entry.log.clone();
+        entry.log.retain_unsettled(&results);
+        self.save(&entry)?;
+        Ok((log, results))
+    }
+
+    /// 重做一次已撤销的操作：按原记录和原传输选项再次传输，成功后用新的记录替换旧记录。
+    /// 有文件失败时成功的部分记为新操作，原操作只留下失败文件的记录
+    pub fn redo(&self, id: &str) -> AppResult<MoveReport> {
+        let _lock = self.lock()?;
+        let entries = self.entries()?;
+        let mut entry = entries
+            .iter()
+            .find(|e| e.log.id == id)
+            .cloned()
+            .ok_or_else(|| not_found(id))?;
+        if !entry.undone {
+            return Err(with_id(ErrorKind::InvalidInput, "redo-not-undone", id));
+        }
+
+        // 撤销之后又有操作移走了源文件或占用了目标位置时拒绝重做
+        let sources: HashSet<&str> = entry.log.records.iter().map(|r| r.original_path.as_str()).collect();
+        let targets: HashSet<&str> = entry.log.records.iter().map(|r| r.new_path.as_str()).collect();
+        let blocking = blocking_operations(&entries, &entry, |other| {
+            other.log.records.iter().filter(|r| r.needs_undo()).any(|r| {
+                sources.contains(r.original_path.as_str()) || targets.contains(r.new_path.as_str())
+            })
+        });
+        if !blocking.is_empty() {
+            return Err(blocked("redo-blocked", &blocking));
+        }
+
+        let plan = MovePlan {
+            target_dir: entry.log.target_dir.clone(),
+            items: entry
+                .log
+                .records
+                .iter()
+                .filter(|r| r.needs_undo())
+                .map(|r| PlannedTransfer {
+                    source: r.original_path.clone(),
+                    destination: r.new_path.clone(),
+                    filename: r.filename.clone(),
+                    mode: r.mode,
+                    size: fs::metadata(&r.original_path).map(|m| m.len()).unwrap_or(0),
+                    conflict: r.conflict,
+                    issues: Vec::new(),
+                    group: r.group.clone(),
+                })
+                .collect(),
+            estimated_bytes: 0,
+            conflict_count: 0,
+            missing_count: 0,
+            cross_device_count: 0,
+            permission_count: 0,
+            verify_checksum: entry.log.verify_checksum,
+            use_trash: entry.log.use_trash,
+            max_parallel: entry.log.max_parallel,
+        };
+        let mut redone = file_ops::apply_plan(&plan, Some(&self.data_dir), ApplyControl::default())?;
+        journal::remove(&self.data_dir, &redone.log.id);
+        if redone.log.records.is_empty() && !plan.items.is_empty() {
+            return Err(AppError::new(ErrorKind::NotFound, "redo-nothing"));
+        }
+
+        // 部分文件重做失败：重做成功的作为一次新操作记录，原操作只保留失败的记录，仍为已撤销，
+        // 处理后可以再次重做
+        let done: HashSet<(&str, &str)> =
+            redone.log.records.iter().map(|r| (r.original_path.as_str(), r.new_path.as_str())).collect();
+        let failed: Vec<MoveRecord> = entry
+            .log
+            .records
+            .iter()
+            .filter(|r| r.needs_undo() && !done.contains(&(r.original_path.as_str(), r.new_path.as_str())))
+            .cloned()
+            .collect();
+        if !failed.is_empty() {
+            self.save(&HistoryEntry {
+                log: redone.log.clone(),
+                applied_at_ms: chrono::Local::now().timestamp_millis(),
+                undone: false,
+                undone_at: None,
+            })?;
+            entry.log.records = failed;
+            self.save(&entry)?;
+            return Ok(redone);
+        }
+
+        // 保留原操作 ID 和时间，历史中仍是同一条操作
+        entry.log.records = std::mem::take(&mut redone.log.records);
+        // 撤销时没删掉的文件夹不会被重新创建，仍算作这次操作创建的
+        for dir in std::mem::take(&mut redone.log.created_dirs) {
+            if !entry.log.created_dirs.contains(&dir) {
+                entry.log.created_dirs.push(dir);
+            }
+        }
+        entry.applied_at_ms = chrono::Local::now().timestamp_millis();
+        entry.undone = false;
+        entry.undone_at = None;
+        self.save(&entry)?;
+        redone.log = entry.log;
+        Ok(redone)
+    }
+
+    /// 读取所有操作，按执行时间从新到旧排序；损坏的文件跳过
+    fn entries(&self) -> AppResult<Vec<HistoryEntry>> {
+        let mut entries: Vec<HistoryEntry> = fs::read_dir(&self.dir)
+            .map_err(|e| AppError::io("history-read", &e).with_path(&self.dir))?
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let data = fs::read(entry.path()).ok()?;
+                match serde_json::from_slice(&data) {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        log::warn!(
+                            "{}",
+                            tr!(
+                                "log-history-corrupt",
+                                path = entry.path().display().to_string(),
+                                detail = e.to_string(),
+                            )
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.applied_at_ms));
+        Ok(entries)
+    }
+
+    /// 等待并取得历史目录的锁，返回值离开作用域时释放
+    fn lock(&self) -> AppResult<File> {
+        let path = self.dir.join(LOCK_FILE_NAME);
+        lock_file(&path).map_err(|e| AppError::io("history-lock", &e).with_path(&path))
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn save(&self, entry: &HistoryEntry) -> AppResult<()> {
+        let path = self.entry_path(&entry.log.id);
+        let data =
+            serde_json::to_vec_pretty(entry).map_err(|e| AppError::internal("history-write", e))?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, data)
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| AppError::io("history-write", &e).with_path(&path))
+    }
+}
+
+/// 以独占方式锁住文件，其他进程持有时等待；文件关闭时自动释放，进程退出不会留下死锁
+#[cfg(unix)]
+fn lock_file(path: &Path) -> io::Result<File> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(path)?;
+    loop {
+        // SAFETY: 文件描述符在调用期间有效
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            return Ok(file);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn lock_file(path: &Path) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    /// 文件已被其他进程独占打开
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    loop {
+        let opened = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .share_mode(0)
+            .open(path);
+        match opened {
+            Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            result => return result,
+        }
+    }
+}
+
+fn with_id(kind: ErrorKind, key: &'static str, id: &str) -> AppError {
+    AppError::with_args(kind, key, &[("id", FluentValue::from(id))])
+}
+
+fn not_found(id: &str) -> AppError {
+    with_id(ErrorKind::NotFound, "history-not-found", id)
+}
+
+/// 被其他操作阻止，`ids` 为需要先撤销的操作
+fn blocked(key: &'static str, ids: &[String]) -> AppError {
+    AppError::with_args(ErrorKind::Blocked, key, &[("ids", FluentValue::from(ids.join(", ")))])
+}
+
+/// 找出比 `entry` 更晚执行、尚未撤销且与之冲突的操作 ID
+fn blocking_operations(
+    entries: &[HistoryEntry],
+    entry: &HistoryEntry,
+    conflicts: impl Fn(&HistoryEntry) -> bool,
+) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|other| {
+            other.log.id != entry.log.id
+                && !other.undone
+                && other.applied_at_ms >= entry.applied_at_ms
+                && conflicts(other)
+        })
+        .map(|other| other.log.id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MoveImageRequest, MoveOptions, UndoOutcome};
+    use crate::test_util::{read, TempDir};
+
+    /// 把 `names` 从 `from` 移到 `to` 下的 `person` 文件夹并写入历史，返回操作 ID
+    fn move_and_record(history: &History, dir: &TempDir, from: &str, names: &[&str], to: &str) -> String {
+        let images: Vec<MoveImageRequest> = names
+            .iter()
+            .map(|name| MoveImageRequest {
+                path: dir.join(&format!("{}/{}", from, name)).to_string_lossy().to_string(),
+                filename: name.to_string(),
+                person: String::new(),
+                persons: Vec::new(),
+            })
+            .collect();
+        let target = dir.join(to);
+        let report =
+            file_ops::move_images(&images, &target.to_string_lossy(), &MoveOptions::default(), None)
+                .unwrap();
+        assert!(report.failures.is_empty());
+        // 同一毫秒内记录的操作无法区分先后，会互相阻止
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        history.record(&report.log).unwrap();
+        report.log.id
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let dir = TempDir::new();
+        let history = History::open(&dir.join("data")).unwrap();
+        assert_eq!(history.undo(None).unwrap_err().key, "undo-nothing");
+
+        dir.write("in/a.jpg", b"a");
+        let id = move_and_record(&history, &dir, "in", &["a.jpg"], "out");
+        assert!(history.has_undo());
+        assert_eq!(history.redo(&id).unwrap_err().key, "redo-not-undone");
+
+        let (log, files) = history.undo(None).unwrap();
+        assert_eq!((log.id.as_str(), files[0].outcome), (id.as_str(), UndoOutcome::Restored));
+        assert_eq!(read(&dir.join("in/a.jpg")), Some(b"a".to_vec()));
+        assert!(!history.has_undo());
+        assert!(history.list().unwrap()[0].undone);
+        assert_eq!(history.undo(Some(&id)).unwrap_err().key, "undo-already-undone");
+
+        let report = history.redo(&id).unwrap();
+        assert_eq!(report.log.id, id);
+        assert_eq!(read(&dir.join("out/a.jpg")), Some(b"a".to_vec()));
+        assert!(!history.get(&id).unwrap().undone);
+
+        let err = history.undo(Some("missing")).unwrap_err();
+        assert_eq!((err.kind, err.key), (ErrorKind::NotFound, "history-not-found"));
+    }
+
+    #[test]
+    fn test_partial_undo_can_be_retried() {
+        let dir = TempDir::new();
+        let history = History::open(&dir.join("data")).unwrap();
+        dir.write("in/a.jpg", b"a");
+        dir.write("in/b.jpg", b"b");
+        let id = move_and_record(&history, &dir, "in", &["a.jpg", "b.jpg"], "out");
+
+        // 原位置被新文件占用，b 不能移回
+        dir.write("in/b.jpg", b"new");
+        let (_, files) = history.undo(Some(&id)).unwrap();
+        let outcomes: Vec<UndoOutcome> = files.iter().map(|f| f.outcome).collect();
+        assert_eq!(outcomes, [UndoOutcome::Restored, UndoOutcome::SkippedOccupied]);
+        let entry = history.get(&id).unwrap();
+        assert!(!entry.undone);
+        assert_eq!(entry.log.records.len(), 1);
+        assert!(entry.log.records[0].new_path.ends_with("b.jpg"));
+
+        // 处理掉占用的文件后再次撤销剩下的部分
+        fs::remove_file(dir.join("in/b.jpg")).unwrap();
+        let (_, files) = history.undo(Some(&id)).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(history.get(&id).unwrap().undone);
+        assert_eq!(read(&dir.join("in/b.jpg")), Some(b"b".to_vec()));
+    }
+
+    #[test]
+    fn test_partial_redo_keeps_failed_records() {
+        let dir = TempDir::new();
+        let history = History::open(&dir.join("data")).unwrap();
+        dir.write("in/a.jpg", b"a");
+        dir.write("in/b.jpg", b"b");
+        let id = move_and_record(&history, &dir, "in", &["a.jpg", "b.jpg"], "out");
+        history.undo(Some(&id)).unwrap();
+
+        // b 在撤销后被删除，重做时失败
+        fs::remove_file(dir.join("in/b.jpg")).unwrap();
+        let report = history.redo(&id).unwrap();
+        assert_eq!(report.failures.len(), 1);
+        assert_ne!(report.log.id, id);
+        assert_eq!(report.log.max_parallel, MoveOptions::default().max_parallel);
+        assert_eq!(read(&dir.join("out/a.jpg")), Some(b"a".to_vec()));
+        assert!(!history.get(&report.log.id).unwrap().undone);
+
+        let entry = history.get(&id).unwrap();
+        assert!(entry.undone);
+        assert_eq!(entry.log.records.len(), 1);
+        assert!(entry.log.records[0].original_path.ends_with("b.jpg"));
+
+        // 文件回来后可以重做剩下的部分
+        dir.write("in/b.jpg", b"b");
+        assert!(history.redo(&id).unwrap().failures.is_empty());
+        assert_eq!(read(&dir.join("out/b.jpg")), Some(b"b".to_vec()));
+    }
+
+    #[test]
+    fn test_blocking_operations() {
+        let dir = TempDir::new();
+        let history = History::open(&dir.join("data")).unwrap();
+        dir.write("in/a.jpg", b"a");
+        let first = move_and_record(&history, &dir, "in", &["a.jpg"], "out");
+        // 较新的操作又把同一个文件移走了
+        let second = move_and_record(&history, &dir, "out", &["a.jpg"], "later");
+
+        let err = history.undo(Some(&first)).unwrap_err();
+        assert_eq!((err.kind, err.key), (ErrorKind::Blocked, "undo-blocked"));
+        history.undo(Some(&second)).unwrap();
+        history.undo(Some(&first)).unwrap();
+        assert_eq!(read(&dir.join("in/a.jpg")), Some(b"a".to_vec()));
+
+        // 撤销后源文件又被别的操作移走，不能重做
+        let third = move_and_record(&history, &dir, "in", &["a.jpg"], "other");
+        let err = history.redo(&first).unwrap_err();
+        assert_eq!((err.kind, err.key), (ErrorKind::Blocked, "redo-blocked"));
+
+        let entries = history.entries().unwrap();
+        let entry = entries.iter().find(|e| e.log.id == first).unwrap();
+        let ids = blocking_operations(&entries, entry, |_| true);
+        // 已撤销的 second 不算
+        assert_eq!(ids, [third]);
+    }
+}
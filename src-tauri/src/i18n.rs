@@ -0,0 +1,245 @@
+// 本地化模块
+// 后端的错误信息和日志按 Fluent 消息目录渲染，目前提供 zh-CN 和 en-US。
+// 语言优先取用户设置，没有设置时跟随系统语言；
+// 当前语言缺少某条消息时退回中文，仍然没有时直接显示消息键
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+
+pub use fluent_bundle::FluentValue;
+
+/// 支持的语言，与 `SOURCES` 一一对应；第一项为消息缺失时的后备语言
+pub const LOCALES: &[&str] = &["zh-CN", "en-US"];
+
+const SOURCES: &[&str] = &[
+    include_str!("../locales/zh-CN.ftl"),
+    include_str!("../locales/en-US.ftl"),
+];
+
+/// 语言设置保存的文件名（在应用配置目录中）
+const SETTINGS_FILE_NAME: &str = "locale.json";
+
+/// 消息渲染出错时记录的日志
+const FORMAT_FAILED_KEY: &str = "log-message-format-failed";
+
+/// 尚未选择语言
+const UNSET: usize = usize::MAX;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(UNSET);
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// 语言设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocaleSetting {
+    /// 用户选择的语言；为空时跟随系统
+    pub locale: Option<String>,
+}
+
+/// 当前语言信息（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleInfo {
+    /// 正在使用的语言
+    pub current: &'static str,
+    /// 系统语言对应的支持语言
+    pub system: &'static str,
+    /// 用户设置的语言
+    pub setting: Option<String>,
+    pub available: Vec<&'static str>,
+}
+
+/// 把语言标签匹配到支持的语言：先完全匹配，再按语言部分匹配（如 en-GB -> en-US）
+pub fn negotiate(tag: &str) -> Option<&'static str> {
+    let tag = tag.trim().replace('_', "-");
+    // 去掉 POSIX 形式的编码后缀，如 zh_CN.UTF-8
+    let tag = tag.split('.').next().unwrap_or_default();
+    let language = tag.split('-').next().unwrap_or_default();
+    LOCALES
+        .iter()
+        .find(|locale| locale.eq_ignore_ascii_case(tag))
+        .or_else(|| {
+            LOCALES.iter().find(|locale| {
+                locale
+                    .split('-')
+                    .next()
+                    .is_some_and(|l| !language.is_empty() && l.eq_ignore_ascii_case(language))
+            })
+        })
+        .copied()
+}
+
+/// 系统语言对应的支持语言；中文以外的系统语言都使用英文
+pub fn system_locale() -> &'static str {
+    sys_locale::get_locales()
+        .find_map(|tag| negotiate(&tag))
+        .unwrap_or("en-US")
+}
+
+/// 切换语言；`None` 或不支持的语言时跟随系统语言。返回实际使用的语言
+pub fn set_locale(tag: Option<&str>) -> &'static str {
+    let locale = tag.and_then(negotiate).unwrap_or_else(system_locale);
+    let index = LOCALES.iter().position(|l| *l == locale).unwrap_or(0);
+    CURRENT.store(index, Ordering::Relaxed);
+    locale
+}
+
+/// 当前语言；尚未设置时按系统语言选择
+pub fn current() -> &'static str {
+    match CURRENT.load(Ordering::Relaxed) {
+        UNSET => set_locale(None),
+        index => LOCALES[index],
+    }
+}
+
+/// 读取保存的语言设置
+pub fn load_setting(config_dir: &Path) -> LocaleSetting {
+    fs::read(config_dir.join(SETTINGS_FILE_NAME))
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// 保存语言设置
+pub fn save_setting(config_dir: &Path, setting: &LocaleSetting) -> AppResult<()> {
+    let path = config_dir.join(SETTINGS_FILE_NAME);
+    let data = serde_json::to_vec_pretty(setting).map_err(|e| AppError::internal("locale-save", e))?;
+    fs::create_dir_all(config_dir)
+        .and_then(|_| fs::write(&path, data))
+        .map_err(|e| AppError::io("locale-save", &e).with_path(&path))
+}
+
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    // 消息目录本身的问题要等目录建好后才能用目录里的消息记录
+    let mut problems = Vec::new();
+    let bundles = BUNDLES.get_or_init(|| {
+        LOCALES
+            .iter()
+            .zip(SOURCES)
+            .map(|(locale, source)| {
+                let resource = FluentResource::try_new(source.to_string())
+                    .unwrap_or_else(|(resource, errors)| {
+                        problems.push(("log-messages-parse-failed", *locale, format!("{:?}", errors)));
+                        resource
+                    });
+                let langid = locale.parse().expect("LOCALES 中的语言标签有效");
+                let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+                // 日志和错误信息是纯文本，不插入双向文本隔离符
+                bundle.set_use_isolating(false);
+                if let Err(errors) = bundle.add_resource(resource) {
+                    problems.push(("log-messages-duplicates", *locale, format!("{:?}", errors)));
+                }
+                bundle
+            })
+            .collect()
+    });
+    for (key, locale, detail) in problems {
+        log::error!("{}", tr!(key, locale = locale, detail = detail));
+    }
+    bundles
+}
+
+/// 按当前语言渲染消息
+pub fn message(key: &str, args: &[(&str, FluentValue)]) -> String {
+    let bundles = bundles();
+    let index = LOCALES.iter().position(|l| *l == current()).unwrap_or(0);
+    [index, 0]
+        .iter()
+        .find_map(|&i| format(&bundles[i], key, args))
+        .unwrap_or_else(|| key.to_string())
+}
+
+fn format(
+    bundle: &FluentBundle<FluentResource>,
+    key: &str,
+    args: &[(&str, FluentValue)],
+) -> Option<String> {
+    let pattern = bundle.get_message(key)?.value()?;
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(Cow::Owned(name.to_string()), value.clone());
+    }
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+    // 渲染出错的消息本身出错时不再记录，避免递归
+    if !errors.is_empty() && key != FORMAT_FAILED_KEY {
+        let detail = format!("{:?}", errors);
+        log::warn!("{}", tr!(FORMAT_FAILED_KEY, key = key, detail = detail));
+    }
+    Some(text.into_owned())
+}
+
+/// 按当前语言渲染消息：`tr!("log-scan-found", count = total)`；
+/// 参数可以是字符串或数字（数字参与复数选择）；命令行工具通过 `app_lib::tr!` 使用
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $key,
+            &[$((stringify!($name), $crate::i18n::FluentValue::from($value))),+],
+        )
+    };
+}
+pub use crate::tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("zh_CN.UTF-8"), Some("zh-CN"));
+        assert_eq!(negotiate("zh-TW"), Some("zh-CN"));
+        assert_eq!(negotiate("en-GB"), Some("en-US"));
+        assert_eq!(negotiate("de-DE"), None);
+        assert_eq!(negotiate(""), None);
+    }
+
+    #[test]
+    fn test_save_setting() {
+        let dir = TempDir::new();
+        let setting = LocaleSetting {
+            locale: Some("en-US".to_string()),
+        };
+        save_setting(&dir.join("config"), &setting).unwrap();
+        assert_eq!(load_setting(&dir.join("config")).locale.as_deref(), Some("en-US"));
+
+        // 配置目录被同名文件占用
+        let file = dir.write("file", b"");
+        let err = save_setting(&file, &setting).unwrap_err();
+        assert_eq!(err.key, "locale-save");
+        assert!(err.path.is_some());
+    }
+
+    #[test]
+    fn test_catalogs_match() {
+        // 两种语言的消息键必须一致
+        let keys = |source: &str| {
+            let mut keys: Vec<String> = source
+                .lines()
+                .filter_map(|line| line.split_once(" ="))
+                .map(|(key, _)| key.to_string())
+                .filter(|key| !key.starts_with([' ', '#']))
+                .collect();
+            keys.sort();
+            keys
+        };
+        let zh = keys(SOURCES[0]);
+        assert_eq!(zh, keys(SOURCES[1]));
+        // 语法错误的消息会被丢弃，这里确认每条消息都能找到
+        for (bundle, key) in bundles().iter().flat_map(|b| zh.iter().map(move |k| (b, k))) {
+            assert!(bundle.get_message(key).and_then(|m| m.value()).is_some(), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+// 后台任务模块
+// 为扫描、移动等耗时操作分配任务 ID，记录状态、进度、取消标志和结果。
+// 任务句柄在离开作用域时一定会把任务标记为结束（包括出错、panic 和后台线程启动失败），
+// 不会出现任务一直处于"运行中"、之后的任务全部被拒绝的情况
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::FluentValue;
+
+/// 最多保留多少个已结束的任务，更早的任务从列表中移除
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// 任务类型；同一类型同时只能运行一个任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// 扫描图片
+    Scan,
+    /// 移动图片（包括继续中断的移动）
+    Move,
+}
+
+impl JobKind {
+    fn busy_key(self) -> &'static str {
+        match self {
+            JobKind::Scan => "scan-busy",
+            JobKind::Move => "move-busy",
+        }
+    }
+}
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 任务信息（返回给前端的快照）
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// 已处理数量
+    pub done: usize,
+    /// 总数量（尚未确定时为 0）
+    pub total: usize,
+    /// 是否已请求取消
+    pub cancel_requested: bool,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// 任务结果，与对应命令的返回值相同
+    pub result: Option<serde_json::Value>,
+    /// 失败原因
+    pub error: Option<AppError>,
+}
+
+struct Job {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+/// 任务登记表
+#[derive(Default)]
+pub struct JobRegistry {
+    /// 按开始时间排列
+    jobs: Mutex<Vec<Job>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记新任务；同类型任务正在运行时返回错误
+    pub fn start(self: &Arc<Self>, kind: JobKind) -> AppResult<JobHandle> {
+        let mut jobs = self.jobs.lock();
+        if jobs
+            .iter()
+            .any(|job| job.info.kind == kind && job.info.status == JobStatus::Running)
+        {
+            return Err(AppError::new(ErrorKind::Busy, kind.busy_key()));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let cancel = Arc::new(AtomicBool::new(false));
+        jobs.push(Job {
+            info: JobInfo {
+                id: id.clone(),
+                kind,
+                status: JobStatus::Running,
+                done: 0,
+                total: 0,
+                cancel_requested: false,
+                started_at: now(),
+                finished_at: None,
+                result: None,
+                error: None,
+            },
+            cancel: cancel.clone(),
+        });
+        prune_finished(&mut jobs);
+        Ok(JobHandle {
+            registry: self.clone(),
+            id,
+            cancel,
+            finished: AtomicBool::new(false),
+        })
+    }
+
+    /// 所有任务（按开始时间排列）
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs.lock().iter().map(|job| job.info.clone()).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobInfo> {
+        self.jobs
+            .lock()
+            .iter()
+            .find(|job| job.info.id == id)
+            .map(|job| job.info.clone())
+    }
+
+    /// 请求取消任务；任务已结束时不做任何事
+    pub fn cancel(&self, id: &str) -> AppResult<()> {
+        let mut jobs = self.jobs.lock();
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.info.id == id)
+            .ok_or_else(|| job_not_found(id))?;
+        request_cancel(job);
+        Ok(())
+    }
+
+    /// 取消某一类型正在运行的任务，返回是否有任务被取消
+    pub fn cancel_kind(&self, kind: JobKind) -> bool {
+        let mut jobs = self.jobs.lock();
+        let mut found = false;
+        for job in jobs
+            .iter_mut()
+            .filter(|job| job.info.kind == kind && job.info.status == JobStatus::Running)
+        {
+            request_cancel(job);
+            found = true;
+        }
+        found
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobInfo)) {
+        if let Some(job) = self.jobs.lock().iter_mut().find(|job| job.info.id == id) {
+            f(&mut job.info);
+        }
+    }
+}
+
+pub fn job_not_found(id: &str) -> AppError {
+    AppError::with_args(ErrorKind::NotFound, "job-not-found", &[("id", FluentValue::from(id))])
+}
+
+fn request_cancel(job: &mut Job) {
+    if job.info.status == JobStatus::Running {
+        job.cancel.store(true, Ordering::Relaxed);
+        job.info.cancel_requested = true;
+    }
+}
+
+/// 已结束的任务超过上限时移除最早结束的
+fn prune_finished(jobs: &mut Vec<Job>) {
+    let finished = jobs
+        .iter()
+        .filter(|job| job.info.status != JobStatus::Running)
+        .count();
+    let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+    jobs.retain(|job| {
+        if excess > 0 && job.info.status != JobStatus::Running {
+            excess -= 1;
+            return false;
+        }
+        true
+    });
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// 正在运行的任务的句柄，可以在线程间共享。
+/// 没有调用 `finish` 就被丢弃时（提前返回、panic、后台线程启动失败）任务记为失败
+pub struct JobHandle {
+    registry: Arc<JobRegistry>,
+    id: String,
+    cancel: Arc<AtomicBool>,
+    finished: AtomicBool,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// 取消标志，传给扫描或移动的执行代码
+    pub fn cancel_flag(&self) -> &Arc<AtomicBool> {
+        &self.cancel
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// 更新进度
+    pub fn progress(&self, done: usize, total: usize) {
+        self.registry.update(&self.id, |info| {
+            info.done = done;
+            info.total = total;
+        });
+    }
+
+    /// 记录任务结果。`cancelled` 表示任务因取消而提前结束（结果中是已完成的部分）
+    pub fn finish<T: Serialize>(&self, result: &AppResult<T>, cancelled: bool) {
+        let (status, result, error) = match result {
+            Ok(value) => {
+                let status = if cancelled {
+                    JobStatus::Cancelled
+                } else {
+                    JobStatus::Completed
+                };
+                (status, serde_json::to_value(value).ok(), None)
+            }
+            Err(e) => (JobStatus::Failed, None, Some(e.clone())),
+        };
+        self.end(status, result, error);
+    }
+
+    fn end(&self, status: JobStatus, result: Option<serde_json::Value>, error: Option<AppError>) {
+        if self.finished.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.registry.update(&self.id, |info| {
+            info.status = status;
+            info.finished_at = Some(now());
+            info.result = result;
+            info.error = error;
+        });
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        let error = AppError::new(ErrorKind::Internal, "job-aborted");
+        self.end(JobStatus::Failed, None, Some(error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let registry = Arc::new(JobRegistry::new());
+        let scan = registry.start(JobKind::Scan).unwrap();
+        assert_eq!(registry.start(JobKind::Scan).err().map(|e| e.kind), Some(ErrorKind::Busy));
+        let moving = registry.start(JobKind::Move).unwrap();
+
+        scan.progress(3, 10);
+        assert!(registry.cancel_kind(JobKind::Scan));
+        assert!(scan.is_cancelled());
+        scan.finish(&Ok(3), true);
+        let info = registry.get(scan.id()).unwrap();
+        assert_eq!((info.status, info.done, info.total), (JobStatus::Cancelled, 3, 10));
+        assert_eq!(info.result, Some(serde_json::json!(3)));
+
+        // 未调用 finish 就丢弃的任务记为失败，并释放同类型任务的占用
+        let id = moving.id().to_string();
+        drop(moving);
+        assert_eq!(registry.get(&id).unwrap().status, JobStatus::Failed);
+        assert!(registry.start(JobKind::Move).is_ok());
+        assert!(registry.cancel("missing").is_err());
+    }
+}
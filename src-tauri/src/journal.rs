@@ -0,0 +1,569 @@
+// 预写日志模块
+// 执行移动计划时，每个文件传输前写入"意图"、完成后写入结果，并立即落盘。
+// 应用崩溃或磁盘断开后，启动时根据日志继续完成或回滚未完成的操作。
+// 操作写入操作历史后，对应的日志文件即被删除
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::file_ops::{self, ApplyControl};
+use crate::history::History;
+use crate::i18n::tr;
+use crate::models::{MovePlan, MoveRecord, MoveReport, OperationLog, TransferMode, UndoFileResult};
+
+/// 日志目录名（数据目录下，每次操作一个 JSON Lines 文件）
+const JOURNAL_DIR_NAME: &str = "journal";
+
+/// 日志中的一行
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JournalEntry {
+    /// 操作开始，记录完整计划
+    Begin {
+        id: String,
+        timestamp: String,
+        plan: MovePlan,
+    },
+    /// 即将传输第 index 项
+    Intent { index: usize },
+    /// 第 index 项已完成
+    Done { index: usize, record: MoveRecord },
+    /// 第 index 项被跳过或失败
+    Failed { index: usize },
+    /// 新建了文件夹
+    Dirs { paths: Vec<String> },
+    /// 所有项都已处理
+    End,
+}
+
+/// 未完成的操作（供启动时提示用户）
+#[derive(Debug, Clone, Serialize)]
+pub struct InterruptedOperation {
+    pub id: String,
+    pub timestamp: String,
+    pub target_dir: String,
+    /// 计划中的传输总数
+    pub total: usize,
+    /// 已完成的传输数
+    pub completed: usize,
+    /// 所有项都已处理，只是未写入操作历史
+    pub finished: bool,
+}
+
+/// 正在执行的操作的日志写入器
+pub struct JournalWriter {
+    file: File,
+    path: PathBuf,
+}
+
+impl JournalWriter {
+    /// 创建日志并写入计划
+    pub fn begin(data_dir: &Path, id: &str, timestamp: &str, plan: &MovePlan) -> AppResult<Self> {
+        let dir = data_dir.join(JOURNAL_DIR_NAME);
+        let path = journal_path(data_dir, id);
+        let file = fs::create_dir_all(&dir)
+            .and_then(|_| File::create(&path))
+            .map_err(|e| AppError::io("journal-create", &e).with_path(&path))?;
+        let mut writer = Self { file, path };
+        writer.write(&JournalEntry::Begin {
+            id: id.to_string(),
+            timestamp: timestamp.to_string(),
+            plan: plan.clone(),
+        })?;
+        Ok(writer)
+    }
+
+    /// 打开已有日志继续追加
+    fn append(path: &Path) -> AppResult<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| AppError::io("journal-open", &e).with_path(path))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn intent(&mut self, index: usize) -> AppResult<()> {
+        self.write(&JournalEntry::Intent { index })
+    }
+
+    pub fn done(&mut self, index: usize, record: &MoveRecord) -> AppResult<()> {
+        self.write(&JournalEntry::Done {
+            index,
+            record: record.clone(),
+        })
+    }
+
+    pub fn failed(&mut self, index: usize) -> AppResult<()> {
+        self.write(&JournalEntry::Failed { index })
+    }
+
+    /// 记录新建的文件夹，没有新建时不写入
+    pub fn dirs(&mut self, paths: &[String]) -> AppResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        self.write(&JournalEntry::Dirs {
+            paths: paths.to_vec(),
+        })
+    }
+
+    pub fn end(&mut self) -> AppResult<()> {
+        self.write(&JournalEntry::End)
+    }
+
+    /// 写入一行并落盘，保证崩溃后日志与磁盘状态一致
+    fn write(&mut self, entry: &JournalEntry) -> AppResult<()> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| AppError::internal("journal-write", e))?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|e| AppError::io("journal-write", &e).with_path(&self.path))
+    }
+}
+
+/// 从日志恢复出的操作状态
+struct JournalState {
+    path: PathBuf,
+    id: String,
+    timestamp: String,
+    plan: MovePlan,
+    /// 已完成的传输及其在计划中的序号
+    records: Vec<(usize, MoveRecord)>,
+    /// 已新建的文件夹
+    created_dirs: Vec<String>,
+    /// 已有结果（完成或失败）的项
+    processed: HashSet<usize>,
+    /// 写了意图但没有结果的项（崩溃时正在传输，并行传输时可能有多个）
+    in_flight: Vec<usize>,
+    ended: bool,
+}
+
+impl JournalState {
+    fn load(path: &Path) -> AppResult<Self> {
+        let file = File::open(path).map_err(|e| AppError::io("journal-read", &e).with_path(path))?;
+        let mut lines = BufReader::new(file).lines();
+
+        let corrupt = || AppError::new(ErrorKind::Internal, "journal-corrupt").with_path(path);
+        let first = lines.next().and_then(|line| line.ok()).ok_or_else(corrupt)?;
+        let (id, timestamp, plan) = match serde_json::from_str(&first) {
+            Ok(JournalEntry::Begin { id, timestamp, plan }) => (id, timestamp, plan),
+            _ => return Err(corrupt()),
+        };
+
+        let mut state = Self {
+            path: path.to_path_buf(),
+            id,
+            timestamp,
+            plan,
+            records: Vec::new(),
+            created_dirs: Vec::new(),
+            processed: HashSet::new(),
+            in_flight: Vec::new(),
+            ended: false,
+        };
+        for line in lines.map_while(Result::ok) {
+            // 崩溃时最后一行可能只写了一半，忽略即可
+            let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) else {
+                break;
+            };
+            match entry {
+                JournalEntry::Begin { .. } => {}
+                JournalEntry::Intent { index } => state.in_flight.push(index),
+                JournalEntry::Done { index, record } => {
+                    state.records.push((index, record));
+                    state.processed.insert(index);
+                    state.in_flight.retain(|i| *i != index);
+                }
+                JournalEntry::Failed { index } => {
+                    // 同组文件失败时已完成的项会被回退，之后补写失败
+                    state.records.retain(|(done, _)| *done != index);
+                    state.processed.insert(index);
+                    state.in_flight.retain(|i| *i != index);
+                }
+                JournalEntry::Dirs { paths } => state.created_dirs.extend(paths),
+                JournalEntry::End => state.ended = true,
+            }
+        }
+        Ok(state)
+    }
+
+    fn summary(&self) -> InterruptedOperation {
+        InterruptedOperation {
+            id: self.id.clone(),
+            timestamp: self.timestamp.clone(),
+            target_dir: self.plan.target_dir.clone(),
+            total: self.plan.items.len(),
+            completed: self.records.len(),
+            finished: self.ended,
+        }
+    }
+
+    /// 根据磁盘上的实际情况判断崩溃时正在传输的文件是否已完成，
+    /// 并把结果补写到日志中；半途的副本会被删除
+    fn reconcile_in_flight(&mut self) -> AppResult<()> {
+        for index in std::mem::take(&mut self.in_flight) {
+            self.reconcile(index)?;
+        }
+        Ok(())
+    }
+
+    fn reconcile(&mut self, index: usize) -> AppResult<()> {
+        let Some(item) = self.plan.items.get(index) else {
+            return Ok(());
+        };
+        let source = Path::new(&item.source);
+        let dest = Path::new(&item.destination);
+        // 覆盖时新文件先暂存在目标旁；移动模式下暂存的就是源文件本身，放回原处后重新执行
+        for staged in file_ops::staged_files(dest) {
+            let restored = if item.mode == TransferMode::Move && !source.exists() {
+                log::warn!("{}", tr!("log-staged-restored", path = item.source.as_str()));
+                file_ops::unstage(source, &staged, item.mode)
+            } else {
+                log::warn!("{}", tr!("log-staged-removed", path = staged.display().to_string()));
+                fs::remove_file(&staged)
+            };
+            if let Err(e) = restored {
+                log::error!(
+                    "{}",
+                    tr!(
+                        "log-unstage-failed",
+                        path = staged.display().to_string(),
+                        detail = e.to_string(),
+                    )
+                );
+            }
+        }
+        let source_exists = source.exists();
+        let dest_exists = dest.symlink_metadata().is_ok();
+        // 复制先写入临时文件，校验后才改名为目标；崩溃时留下的临时文件直接删除
+        for temp in file_ops::temp_files(dest) {
+            log::warn!("{}", tr!("log-temp-removed", path = temp.display().to_string()));
+            let _ = fs::remove_file(&temp);
+        }
+
+        let completed = match item.mode {
+            // 链接是原子操作，存在即完成
+            TransferMode::Hardlink | TransferMode::Symlink => dest_exists,
+            TransferMode::Move if !source_exists => dest_exists,
+            // 源和目标都在：跨设备移动已复制完但未删除源文件，或目标来自别处，内容一致才算完成
+            _ if dest_exists && file_ops::files_identical(source, dest) => {
+                if item.mode == TransferMode::Move {
+                    file_ops::discard_file(source, self.plan.use_trash).map_err(|e| {
+                        AppError::io("journal-discard-source", &e).with_path(source)
+                    })?;
+                }
+                true
+            }
+            _ => {
+                // 复制不会在目标处留下半个文件，只有克隆可能中途断开
+                if dest_exists && source_exists && item.mode == TransferMode::Reflink {
+                    log::warn!("{}", tr!("log-clone-removed", path = item.destination.as_str()));
+                    let _ = fs::remove_file(dest);
+                }
+                false
+            }
+        };
+
+        let mut writer = JournalWriter::append(&self.path)?;
+        if completed {
+            let record = MoveRecord {
+                original_path: item.source.clone(),
+                new_path: item.destination.clone(),
+                filename: item.filename.clone(),
+                mode: item.mode,
+                dest_stamp: file_ops::link_stamp(dest),
+                conflict: item.conflict,
+                dest_hash: file_ops::content_hash(dest, item.mode),
+                group: item.group.clone(),
+            };
+            writer.done(index, &record)?;
+            self.records.push((index, record));
+            self.processed.insert(index);
+        } else if !source_exists {
+            // 源和目标都不存在，无法继续
+            writer.failed(index)?;
+            self.processed.insert(index);
+        }
+        Ok(())
+    }
+
+    fn operation_log(&self) -> OperationLog {
+        OperationLog {
+            id: self.id.clone(),
+            timestamp: self.timestamp.clone(),
+            target_dir: self.plan.target_dir.clone(),
+            records: self.records.iter().map(|(_, record)| record.clone()).collect(),
+            created_dirs: self.created_dirs.clone(),
+            verify_checksum: self.plan.verify_checksum,
+            use_trash: self.plan.use_trash,
+            max_parallel: self.plan.max_parallel,
+        }
+    }
+}
+
+fn journal_path(data_dir: &Path, id: &str) -> PathBuf {
+    data_dir.join(JOURNAL_DIR_NAME).join(format!("{}.jsonl", id))
+}
+
+/// 删除操作的日志（操作已写入操作历史）
+pub fn remove(data_dir: &Path, id: &str) {
+    let path = journal_path(data_dir, id);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!(
+                "{}",
+                tr!(
+                    "log-journal-remove-failed",
+                    path = path.display().to_string(),
+                    detail = e.to_string(),
+                )
+            );
+        }
+    }
+}
+
+/// 列出所有未完成的操作
+pub fn interrupted(data_dir: &Path) -> Vec<InterruptedOperation> {
+    let Ok(entries) = fs::read_dir(data_dir.join(JOURNAL_DIR_NAME)) else {
+        return Vec::new();
+    };
+    let mut list: Vec<InterruptedOperation> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|entry| match JournalState::load(&entry.path()) {
+            Ok(state) => Some(state.summary()),
+            Err(e) => {
+                log::warn!(
+                    "{}",
+                    tr!(
+                        "log-journal-skipped",
+                        path = entry.path().display().to_string(),
+                        detail = e.to_string(),
+                    )
+                );
+                None
+            }
+        })
+        .collect();
+    list.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    list
+}
+
+/// 继续完成中断的操作，返回完整的操作日志和本次失败的文件
+/// （调用方写入操作历史后日志即被删除）
+pub fn resume(data_dir: &Path, id: &str, control: ApplyControl) -> AppResult<MoveReport> {
+    let mut state = JournalState::load(&journal_path(data_dir, id))?;
+    state.reconcile_in_flight()?;
+    if state.ended {
+        return Ok(MoveReport {
+            log: state.operation_log(),
+            failures: Vec::new(),
+            cancelled: false,
+        });
+    }
+    let mut writer = JournalWriter::append(&state.path)?;
+    Ok(file_ops::apply_items(
+        &state.plan,
+        &state.processed,
+        Some(&mut writer),
+        control,
+        state.operation_log(),
+    ))
+}
+
+/// 回滚中断的操作：撤销已完成的传输并删除日志。
+/// 有文件未能撤销时（目标被修改、原位置被占用等），把这些记录写入操作历史，之后仍可再次撤销。
+/// 返回已完成部分的操作日志和每个文件的撤销结果
+pub fn rollback(data_dir: &Path, id: &str) -> AppResult<(OperationLog, Vec<UndoFileResult>)> {
+    let mut state = JournalState::load(&journal_path(data_dir, id))?;
+    state.reconcile_in_flight()?;
+    let log = state.operation_log();
+    let results = file_ops::undo_move(&log);
+    if results.iter().all(|r| r.outcome.is_settled()) {
+        remove(data_dir, id);
+    } else {
+        let mut remaining = log.clone();
+        remaining.retain_unsettled(&results);
+        // 写入历史后日志随之删除
+        History::open(data_dir)?.record(&remaining)?;
+    }
+    Ok((log, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MoveImageRequest, MoveOptions, UndoOutcome};
+    use crate::test_util::{read, TempDir};
+
+    const ID: &str = "op";
+
+    /// 在 `case` 子目录中准备 a.jpg、b.jpg 两个源文件，按传输方式生成计划并写入日志开头
+    fn begin(dir: &TempDir, case: &str, mode: TransferMode) -> (PathBuf, MovePlan, JournalWriter) {
+        let images: Vec<MoveImageRequest> = ["a.jpg", "b.jpg"]
+            .iter()
+            .map(|name| MoveImageRequest {
+                path: dir
+                    .write(&format!("{}/in/{}", case, name), name.as_bytes())
+                    .to_string_lossy()
+                    .to_string(),
+                filename: name.to_string(),
+                person: String::new(),
+                persons: Vec::new(),
+            })
+            .collect();
+        let options = MoveOptions {
+            mode,
+            ..MoveOptions::default()
+        };
+        let target = dir.join(&format!("{}/out", case));
+        let plan = file_ops::plan_move(&images, &target.to_string_lossy(), &options).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        let data_dir = dir.join(&format!("{}/data", case));
+        let writer = JournalWriter::begin(&data_dir, ID, "2026-01-01 00:00:00", &plan).unwrap();
+        (data_dir, plan, writer)
+    }
+
+    fn outcomes(files: &[UndoFileResult]) -> Vec<UndoOutcome> {
+        files.iter().map(|f| f.outcome).collect()
+    }
+
+    #[test]
+    fn test_interrupted_before_intent() {
+        let dir = TempDir::new();
+        for case in ["resume", "rollback"] {
+            let (data_dir, plan, writer) = begin(&dir, case, TransferMode::Move);
+            drop(writer);
+            let list = interrupted(&data_dir);
+            assert_eq!(list.len(), 1);
+            assert_eq!((list[0].total, list[0].completed, list[0].finished), (2, 0, false));
+
+            if case == "resume" {
+                let report = resume(&data_dir, ID, ApplyControl::default()).unwrap();
+                assert_eq!(report.log.records.len(), 2);
+                assert!(plan.items.iter().all(|i| read(Path::new(&i.destination)).is_some()));
+            } else {
+                let (log, files) = rollback(&data_dir, ID).unwrap();
+                assert!(log.records.is_empty() && files.is_empty());
+                assert!(plan.items.iter().all(|i| read(Path::new(&i.source)).is_some()));
+                assert!(interrupted(&data_dir).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_interrupted_between_intent_and_done() {
+        let dir = TempDir::new();
+        for case in ["resume", "rollback"] {
+            // 第一个文件已经移走，还没来得及写完成；最后一行只写了一半
+            let (data_dir, plan, mut writer) = begin(&dir, case, TransferMode::Move);
+            writer.intent(0).unwrap();
+            let first = &plan.items[0];
+            fs::rename(&first.source, &first.destination).unwrap();
+            drop(writer);
+            let mut file = OpenOptions::new().append(true).open(journal_path(&data_dir, ID)).unwrap();
+            file.write_all(br#"{"type":"done","ind"#).unwrap();
+
+            let state = JournalState::load(&journal_path(&data_dir, ID)).unwrap();
+            assert_eq!((state.records.len(), state.in_flight.clone()), (0, vec![0]));
+
+            if case == "resume" {
+                let report = resume(&data_dir, ID, ApplyControl::default()).unwrap();
+                assert_eq!(report.log.records.len(), 2);
+                assert!(report.failures.is_empty());
+                assert_eq!(read(Path::new(&first.destination)), Some(b"a.jpg".to_vec()));
+            } else {
+                let (log, files) = rollback(&data_dir, ID).unwrap();
+                assert_eq!(log.records.len(), 1);
+                assert_eq!(outcomes(&files), [UndoOutcome::Restored]);
+                assert_eq!(read(Path::new(&first.source)), Some(b"a.jpg".to_vec()));
+                assert!(!Path::new(&first.destination).exists());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rollback_keeps_unrestored_files_in_history() {
+        let dir = TempDir::new();
+        // 两个文件都已移完，还没写入历史；回滚前用户又在 a.jpg 的原位置放了新文件
+        let (data_dir, plan, mut writer) = begin(&dir, "occupied", TransferMode::Move);
+        let log = JournalState::load(&journal_path(&data_dir, ID)).unwrap().operation_log();
+        let control = ApplyControl::default();
+        let report = file_ops::apply_items(&plan, &HashSet::new(), Some(&mut writer), control, log);
+        assert!(report.failures.is_empty());
+        drop(writer);
+        let (first, second) = (&plan.items[0], &plan.items[1]);
+        fs::write(&first.source, b"new").unwrap();
+
+        let (log, files) = rollback(&data_dir, ID).unwrap();
+        assert_eq!(log.records.len(), 2);
+        assert_eq!(outcomes(&files), [UndoOutcome::SkippedOccupied, UndoOutcome::Restored]);
+        assert_eq!(read(Path::new(&second.source)), Some(b"b.jpg".to_vec()));
+        assert_eq!(read(Path::new(&first.destination)), Some(b"a.jpg".to_vec()));
+
+        // 日志已删除，未能移回的文件留在操作历史中，可以再次撤销
+        assert!(interrupted(&data_dir).is_empty());
+        let history = History::open(&data_dir).unwrap();
+        assert!(history.has_undo());
+        fs::remove_file(&first.source).unwrap();
+        let (remaining, files) = history.undo(Some(ID)).unwrap();
+        assert_eq!(remaining.records.len(), 1);
+        assert_eq!(outcomes(&files), [UndoOutcome::Restored]);
+        assert_eq!(read(Path::new(&first.source)), Some(b"a.jpg".to_vec()));
+    }
+
+    #[test]
+    fn test_interrupted_with_temp_file() {
+        let dir = TempDir::new();
+        for case in ["resume", "rollback"] {
+            // 复制写到一半：只有临时文件，目标还不存在；旁边还有更早一次中断留下的临时文件
+            let (data_dir, plan, mut writer) = begin(&dir, case, TransferMode::Copy);
+            writer.intent(0).unwrap();
+            let first = &plan.items[0];
+            let temps = [
+                file_ops::temp_path(Path::new(&first.destination)),
+                file_ops::temp_path(Path::new(&first.destination)),
+            ];
+            for temp in &temps {
+                fs::write(temp, b"a.").unwrap();
+            }
+            drop(writer);
+
+            if case == "resume" {
+                let report = resume(&data_dir, ID, ApplyControl::default()).unwrap();
+                assert_eq!(report.log.records.len(), 2);
+                assert_eq!(read(Path::new(&first.destination)), Some(b"a.jpg".to_vec()));
+            } else {
+                let (log, files) = rollback(&data_dir, ID).unwrap();
+                assert!(log.records.is_empty() && files.is_empty());
+                assert!(!Path::new(&first.destination).exists());
+            }
+            assert!(temps.iter().all(|temp| !temp.exists()));
+            assert_eq!(read(Path::new(&first.source)), Some(b"a.jpg".to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_unreadable_journal() {
+        let dir = TempDir::new();
+        let path = journal_path(dir.path(), ID);
+        let error_key = |path: &Path| JournalState::load(path).err().map(|e| e.key);
+        assert_eq!(error_key(&path), Some("journal-read"));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        for content in ["", "{\"type\":\"end\"}\n"] {
+            fs::write(&path, content).unwrap();
+            assert_eq!(error_key(&path), Some("journal-corrupt"));
+        }
+        // 无法读取的日志不会出现在列表中
+        assert!(interrupted(dir.path()).is_empty());
+    }
+}
@@ -1,14 +1,35 @@
 // tag2dir - 图片人物分类工具
 // 主入口模块，注册所有 Tauri 命令和插件
+// 扫描、元数据、文件操作等核心模块不依赖 Tauri，命令行工具直接复用
 
+pub mod catalog;
+#[cfg(feature = "gui")]
 mod commands;
-mod file_ops;
-mod metadata;
-mod models;
-mod scanner;
+pub mod companions;
+pub mod error;
+pub mod file_ops;
+pub mod history;
+pub mod i18n;
+pub mod jobs;
+pub mod journal;
+pub mod metadata;
+pub mod models;
+pub mod path_template;
+pub mod progress;
+pub mod sanitize;
+pub mod scan_index;
+pub mod scanner;
+#[cfg(test)]
+mod test_util;
+pub mod trash;
+pub mod watcher;
 
+#[cfg(feature = "gui")]
 use commands::AppState;
+#[cfg(feature = "gui")]
+use tauri::Manager;
 
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -16,6 +37,13 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .manage(AppState::new())
         .setup(|app| {
+            // 后端消息的语言：优先使用保存的设置，否则跟随系统
+            let setting = app
+                .path()
+                .app_config_dir()
+                .map(|dir| i18n::load_setting(&dir))
+                .unwrap_or_default();
+            i18n::set_locale(setting.locale.as_deref());
             if cfg!(debug_assertions) {
                 app.handle().plugin(
                     tauri_plugin_log::Builder::default()
@@ -29,7 +57,30 @@ pub fn run() {
             commands::scan_images,
             commands::cancel_scan,
             commands::move_images,
+            commands::plan_move,
+            commands::apply_plan,
+            commands::cancel_move,
+            commands::list_jobs,
+            commands::get_job,
+            commands::cancel_job,
+            commands::get_locale,
+            commands::set_locale,
             commands::undo_move,
+            commands::redo_move,
+            commands::list_history,
+            commands::list_interrupted,
+            commands::resume_interrupted,
+            commands::rollback_interrupted,
+            commands::preview_path_template,
+            commands::catalog_query,
+            commands::catalog_get_file,
+            commands::catalog_persons,
+            commands::get_watch_config,
+            commands::start_watch,
+            commands::stop_watch,
+            commands::pause_watch,
+            commands::resume_watch,
+            commands::watch_status,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
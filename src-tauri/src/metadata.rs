@@ -5,21 +5,54 @@ use std::fs;
 use std::io::BufReader;
 use std::path::Path;
 
-/// 从图片文件中提取所有人物/关键字标签
+use chrono::NaiveDateTime;
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::models::FaceRegion;
+
+/// 从单个文件中提取的元数据
+#[derive(Debug, Clone, Default)]
+pub struct ImageMetadata {
+    /// 人物标签
+    pub persons: Vec<String>,
+    /// 所有关键字
+    pub keywords: Vec<String>,
+    /// 带坐标的人脸区域
+    pub regions: Vec<FaceRegion>,
+    /// 拍摄时间（EXIF DateTimeOriginal）
+    pub taken_at: Option<NaiveDateTime>,
+    /// 相机型号
+    pub camera: Option<String>,
+}
+
+/// EXIF 解析结果
+struct ExifData {
+    keywords: Vec<String>,
+    taken_at: Option<NaiveDateTime>,
+    camera: Option<String>,
+}
+
+/// 从图片文件中提取所有人物/关键字标签及人脸区域
 /// 按优先级检查: XMP人物区域 > XMP dc:subject > IPTC关键字 > EXIF XPKeywords
-pub fn extract_person_tags(path: &Path) -> (Vec<String>, Vec<String>) {
+pub fn extract_metadata(path: &Path) -> ImageMetadata {
     let mut all_keywords: Vec<String> = Vec::new();
     let mut persons: Vec<String> = Vec::new();
+    let mut regions: Vec<FaceRegion> = Vec::new();
+    let mut taken_at = None;
+    let mut camera = None;
 
     // 尝试读取 EXIF 数据
-    if let Ok(exif_keywords) = read_exif_keywords(path) {
-        all_keywords.extend(exif_keywords);
+    if let Ok(exif) = read_exif_data(path) {
+        all_keywords.extend(exif.keywords);
+        taken_at = exif.taken_at;
+        camera = exif.camera;
     }
 
     // 尝试读取 XMP 数据（嵌入在 JPEG/TIFF 等格式中）
-    if let Ok((xmp_persons, xmp_keywords)) = read_xmp_data(path) {
+    if let Ok((xmp_persons, xmp_keywords, xmp_regions)) = read_xmp_data(path) {
         persons.extend(xmp_persons);
         all_keywords.extend(xmp_keywords);
+        regions.extend(xmp_regions);
     }
 
     // 尝试读取 IPTC 关键字
@@ -34,19 +67,32 @@ pub fn extract_person_tags(path: &Path) -> (Vec<String>, Vec<String>) {
     persons.dedup();
 
     // 如果没有从 XMP 人物区域中找到人物，将所有关键字都视为潜在人物标签
+    // （层级关键字如 "Event|Wedding" 不是人名，排除在外）
     if persons.is_empty() && !all_keywords.is_empty() {
-        persons = all_keywords.clone();
+        persons = all_keywords
+            .iter()
+            .filter(|kw| !kw.contains('|'))
+            .cloned()
+            .collect();
     }
 
-    (persons, all_keywords)
+    ImageMetadata {
+        persons,
+        keywords: all_keywords,
+        regions,
+        taken_at,
+        camera,
+    }
 }
 
-/// 读取 EXIF 中的 XPKeywords（Windows 风格的关键字标签）
-fn read_exif_keywords(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let file = fs::File::open(path)?;
+/// 读取 EXIF 中的 XPKeywords（Windows 风格的关键字标签）、拍摄时间和相机型号
+fn read_exif_data(path: &Path) -> AppResult<ExifData> {
+    let file = fs::File::open(path).map_err(|e| read_error(path, &e))?;
     let mut reader = BufReader::new(file);
     let exif_reader = exif::Reader::new();
-    let exif = exif_reader.read_from_container(&mut reader)?;
+    let exif = exif_reader
+        .read_from_container(&mut reader)
+        .map_err(|e| AppError::caused(ErrorKind::Decode, "metadata-exif", e).with_path(path))?;
 
     let mut keywords = Vec::new();
 
@@ -80,23 +126,63 @@ fn read_exif_keywords(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Er
         }
     }
 
-    Ok(keywords)
+    // 拍摄时间，格式为 "YYYY:MM:DD HH:MM:SS"
+    let taken_at = [exif::Tag::DateTimeOriginal, exif::Tag::DateTime]
+        .iter()
+        .find_map(|tag| exif_ascii(&exif, *tag))
+        .and_then(|text| NaiveDateTime::parse_from_str(&text, "%Y:%m:%d %H:%M:%S").ok());
+
+    // 相机型号：Model 通常已包含厂商名，没有时再补上 Make
+    let make = exif_ascii(&exif, exif::Tag::Make);
+    let camera = match (make, exif_ascii(&exif, exif::Tag::Model)) {
+        (Some(make), Some(model)) if !model.starts_with(make.split(' ').next().unwrap_or("")) => {
+            Some(format!("{} {}", make, model))
+        }
+        (_, Some(model)) => Some(model),
+        (make, None) => make,
+    };
+
+    Ok(ExifData {
+        keywords,
+        taken_at,
+        camera,
+    })
+}
+
+fn read_error(path: &Path, err: &std::io::Error) -> AppError {
+    AppError::io("metadata-read", err).with_path(path)
 }
 
-/// 从文件中提取 XMP 数据段并解析人物和关键字
-fn read_xmp_data(path: &Path) -> Result<(Vec<String>, Vec<String>), Box<dyn std::error::Error>> {
-    let data = fs::read(path)?;
+/// 读取 EXIF ASCII 字段的第一个值
+fn exif_ascii(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    match field.value {
+        exif::Value::Ascii(ref values) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim_end_matches('\0').trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    }
+}
+
+/// XMP 解析结果：(人物, 关键字, 人脸区域)
+type XmpData = (Vec<String>, Vec<String>, Vec<FaceRegion>);
+
+/// 从文件中提取 XMP 数据段并解析人物、关键字和人脸区域
+fn read_xmp_data(path: &Path) -> AppResult<XmpData> {
+    let data = fs::read(path).map_err(|e| read_error(path, &e))?;
     let mut persons = Vec::new();
     let mut keywords = Vec::new();
+    let mut regions = Vec::new();
 
     // 查找 XMP 数据段 - 标记为 "<?xpacket" 或 "<x:xmpmeta"
     let xmp_xml = extract_xmp_from_bytes(&data);
 
     if let Some(xml) = xmp_xml {
-        parse_xmp_xml(&xml, &mut persons, &mut keywords);
+        parse_xmp_xml(&xml, &mut persons, &mut keywords, &mut regions);
     }
 
-    Ok((persons, keywords))
+    Ok((persons, keywords, regions))
 }
 
 /// 从原始字节中提取 XMP XML 数据
@@ -127,8 +213,13 @@ fn extract_xmp_from_bytes(data: &[u8]) -> Option<String> {
     None
 }
 
-/// 解析 XMP XML 并提取人物和关键字
-fn parse_xmp_xml(xml: &str, persons: &mut Vec<String>, keywords: &mut Vec<String>) {
+/// 解析 XMP XML 并提取人物、关键字和人脸区域
+fn parse_xmp_xml(
+    xml: &str,
+    persons: &mut Vec<String>,
+    keywords: &mut Vec<String>,
+    regions: &mut Vec<FaceRegion>,
+) {
     let doc = match roxmltree::Document::parse(xml) {
         Ok(doc) => doc,
         Err(_) => return,
@@ -159,12 +250,24 @@ fn parse_xmp_xml(xml: &str, persons: &mut Vec<String>, keywords: &mut Vec<String
             extract_region_persons(node, persons);
         }
 
+        // 带坐标的人脸区域：MWG 的 RegionList，以及 Microsoft 的 MPRI:Regions
+        // （MWG 的 Regions 只是 RegionList 的外层容器，跳过避免重复）
+        if tag_name == "RegionList"
+            || (tag_name == "Regions" && node.tag_name().namespace() != Some(MWG_RS_NS))
+        {
+            extract_face_regions(node, regions);
+        }
+
         // Lightroom/Bridge 人物标签: lr:hierarchicalSubject
         if tag_name == "hierarchicalSubject" {
             for child in node.descendants() {
                 if child.tag_name().name() == "li" {
                     if let Some(text) = child.text() {
                         let text = text.trim();
+                        // 完整的层级关键字也保留（供路径模板 {keyword:Event|*} 使用）
+                        if !text.is_empty() {
+                            keywords.push(text.to_string());
+                        }
                         // 层级标签格式: "People|人物名" 或 "人物|名字"
                         if text.contains('|') {
                             let parts: Vec<&str> = text.split('|').collect();
@@ -248,9 +351,85 @@ fn extract_region_persons(node: roxmltree::Node, persons: &mut Vec<String>) {
     }
 }
 
+/// MWG 人物区域命名空间
+const MWG_RS_NS: &str = "http://www.metadataworkinggroup.com/schemas/regions/";
+
+/// 从区域列表节点中提取带坐标的人脸区域
+/// 统一转换为左上角坐标 + 宽高的归一化值（0~1）
+fn extract_face_regions(list: roxmltree::Node, regions: &mut Vec<FaceRegion>) {
+    // 每个区域是列表容器（rdf:Bag/rdf:Seq）下的一个 rdf:li
+    let items = list
+        .children()
+        .filter(|n| n.is_element())
+        .flat_map(|container| container.children())
+        .filter(|n| n.tag_name().name() == "li");
+
+    for item in items {
+        let name = ["Name", "PersonDisplayName"]
+            .iter()
+            .find_map(|key| region_value(item, key));
+        let name = match name {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+
+        // MWG: <mwg-rs:Area stArea:x/y/w/h>，x/y 为区域中心点
+        if let Some(area) = item.descendants().find(|n| n.tag_name().name() == "Area") {
+            let coords: Option<Vec<f64>> = ["x", "y", "w", "h"]
+                .iter()
+                .map(|key| region_value(area, key).and_then(|v| v.parse().ok()))
+                .collect();
+            if let Some(c) = coords {
+                regions.push(FaceRegion {
+                    name,
+                    x: c[0] - c[2] / 2.0,
+                    y: c[1] - c[3] / 2.0,
+                    w: c[2],
+                    h: c[3],
+                });
+            }
+            continue;
+        }
+
+        // Microsoft: MPReg:Rectangle="x, y, w, h"，x/y 为左上角
+        if let Some(rect) = region_value(item, "Rectangle") {
+            let c: Vec<f64> = rect
+                .split(',')
+                .filter_map(|v| v.trim().parse().ok())
+                .collect();
+            if c.len() == 4 {
+                regions.push(FaceRegion {
+                    name,
+                    x: c[0],
+                    y: c[1],
+                    w: c[2],
+                    h: c[3],
+                });
+            }
+        }
+    }
+}
+
+/// 读取区域字段：XMP 允许写成属性，也允许写成子元素（可能包在 rdf:Description 中）
+fn region_value(node: roxmltree::Node, key: &str) -> Option<String> {
+    node.descendants()
+        .find_map(|n| {
+            n.attributes()
+                .find(|attr| attr.name() == key)
+                .map(|attr| attr.value().to_string())
+                .or_else(|| {
+                    (n.tag_name().name() == key)
+                        .then(|| n.text())
+                        .flatten()
+                        .map(|t| t.to_string())
+                })
+        })
+        .map(|v| v.trim().to_string())
+}
+
 /// 读取 IPTC 关键字（解析 JPEG 中的 IPTC-IIM 数据段）
-fn read_iptc_keywords(path: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let data = fs::read(path)?;
+fn read_iptc_keywords(path: &Path) -> AppResult<Vec<String>> {
+    let data = fs::read(path).map_err(|e| read_error(path, &e))?;
     let mut keywords = Vec::new();
 
     // JPEG IPTC 数据位于 APP13 段 (0xFFED) 中
@@ -395,4 +574,36 @@ mod tests {
         assert!(xmp.contains("Alice"));
         assert!(xmp.contains("Bob"));
     }
+
+    #[test]
+    fn test_parse_face_regions() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                <rdf:Description
+                    xmlns:mwg-rs="http://www.metadataworkinggroup.com/schemas/regions/"
+                    xmlns:stArea="http://ns.adobe.com/xmp/sType/Area#">
+                    <mwg-rs:Regions rdf:parseType="Resource">
+                        <mwg-rs:RegionList>
+                            <rdf:Bag>
+                                <rdf:li>
+                                    <rdf:Description mwg-rs:Name="Alice" mwg-rs:Type="Face">
+                                        <mwg-rs:Area stArea:x="0.5" stArea:y="0.4"
+                                            stArea:w="0.2" stArea:h="0.2" stArea:unit="normalized"/>
+                                    </rdf:Description>
+                                </rdf:li>
+                            </rdf:Bag>
+                        </mwg-rs:RegionList>
+                    </mwg-rs:Regions>
+                </rdf:Description>
+            </rdf:RDF>
+        </x:xmpmeta>"#;
+
+        let (mut persons, mut keywords, mut regions) = (Vec::new(), Vec::new(), Vec::new());
+        parse_xmp_xml(xml, &mut persons, &mut keywords, &mut regions);
+        assert!(persons.contains(&"Alice".to_string()));
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].name, "Alice");
+        assert!((regions[0].x - 0.4).abs() < 1e-9);
+        assert!((regions[0].y - 0.3).abs() < 1e-9);
+    }
 }
@@ -3,6 +3,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorKind;
+
 /// 图片信息结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageInfo {
@@ -16,12 +18,36 @@ pub struct ImageInfo {
     pub persons: Vec<String>,
     /// 所有元数据关键字
     pub keywords: Vec<String>,
+    /// 带坐标的人脸区域
+    pub regions: Vec<FaceRegion>,
     /// 缩略图 base64 编码
     pub thumbnail: String,
     /// 用户选择的目标人物（用于移动分类）
     pub selected_person: Option<String>,
     /// 处理状态
     pub status: ImageStatus,
+    /// 按文件头识别出的真实格式（无法识别时为空）
+    pub detected_format: Option<String>,
+    /// 扩展名与真实格式不符（包括缺少扩展名）
+    pub extension_mismatch: bool,
+    /// 随图片一起移动的伴随文件（RAW、XMP、Live Photo 视频等）的完整路径
+    #[serde(default)]
+    pub companions: Vec<String>,
+}
+
+/// 人脸区域（归一化坐标，0~1，以左上角为原点）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FaceRegion {
+    /// 人物名称
+    pub name: String,
+    /// 区域左上角 x
+    pub x: f64,
+    /// 区域左上角 y
+    pub y: f64,
+    /// 区域宽度
+    pub w: f64,
+    /// 区域高度
+    pub h: f64,
 }
 
 /// 图片处理状态
@@ -39,36 +65,453 @@ pub enum ImageStatus {
     Error(String),
 }
 
-/// 扫描进度事件 - 通过 Tauri event 推送到前端
+/// 扫描进度事件 - 通过 Tauri event 推送到前端。
+/// 只包含计数，按固定间隔发送；扫描结果通过 `ScanBatchEvent` 分批发送
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanProgressEvent {
+    /// 所属扫描任务的 ID
+    #[serde(default)]
+    pub job_id: String,
     /// 当前已扫描数量
     pub scanned: usize,
-    /// 扫描到的图片信息（增量）
-    pub image: Option<ImageInfo>,
+    /// 需要扫描的总数
+    #[serde(default)]
+    pub total: usize,
+    /// 是否仍在查找图片（边查找边处理时 total 还会增加）
+    #[serde(default)]
+    pub discovering: bool,
+    /// 已用时间（毫秒）
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    /// 预计剩余时间（毫秒），尚无法估算时为空
+    #[serde(default)]
+    pub eta_ms: Option<u64>,
     /// 是否扫描完成（正常完成或取消都会为 true）
     pub done: bool,
     /// 是否被用户取消
     pub cancelled: bool,
-    /// 错误信息（如果有）
-    pub error: Option<String>,
+}
+
+/// 查找图片阶段的进度事件：遍历文件夹时按间隔发送，查找结束时发送一次 done
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanDiscoveryEvent {
+    /// 所属扫描任务的 ID
+    #[serde(default)]
+    pub job_id: String,
+    /// 已查找完的文件夹数量
+    pub dirs_scanned: usize,
+    /// 已找到的图片数量（伴随文件不计入）
+    pub found: usize,
+    /// 最近查找完的文件夹
+    pub current_dir: String,
+    /// 查找是否结束（正常结束或取消）
+    pub done: bool,
+}
+
+/// 一批扫描结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanBatchEvent {
+    /// 所属扫描任务的 ID
+    #[serde(default)]
+    pub job_id: String,
+    /// 扫描到的图片信息（增量）
+    pub images: Vec<ImageInfo>,
+    /// 读取失败的文件
+    #[serde(default)]
+    pub failures: Vec<ScanFailure>,
+}
+
+/// 读取失败的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// 扫描事件的发送频率和时机
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanEventOptions {
+    /// 每批最多包含多少条扫描结果
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// 两批结果之间的最长间隔（毫秒）
+    #[serde(default = "default_batch_interval_ms")]
+    pub batch_interval_ms: u64,
+    /// 进度事件的最短间隔（毫秒）
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
+    /// 是否边查找边处理：为 false 时查找完所有图片后再处理，进度从一开始就有准确的总数；
+    /// 为 true 时查找完一个文件夹就开始处理其中的图片，结果更早出现
+    #[serde(default)]
+    pub process_during_discovery: bool,
+}
+
+fn default_batch_size() -> usize {
+    50
+}
+
+fn default_batch_interval_ms() -> u64 {
+    250
+}
+
+fn default_progress_interval_ms() -> u64 {
+    100
+}
+
+impl Default for ScanEventOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: default_batch_size(),
+            batch_interval_ms: default_batch_interval_ms(),
+            progress_interval_ms: default_progress_interval_ms(),
+            process_during_discovery: false,
+        }
+    }
+}
+
+/// 增量扫描时发现已删除文件的事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRemovedEvent {
+    /// 所属扫描任务的 ID
+    #[serde(default)]
+    pub job_id: String,
+    /// 索引中存在、但本次扫描已找不到的文件路径
+    pub paths: Vec<String>,
 }
 
 /// 移动进度事件
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveProgressEvent {
-    /// 当前已移动数量
+    /// 所属移动任务的 ID（由命令层填写）
+    #[serde(default)]
+    pub job_id: String,
+    /// 已处理的文件数（含失败）
     pub moved_count: usize,
     /// 总数
     pub total: usize,
     /// 当前正在处理的文件名
     pub current_file: String,
+    /// 当前文件已写入的字节数（跨设备复制时逐块更新）
+    #[serde(default)]
+    pub bytes_done: u64,
+    /// 当前文件的大小
+    #[serde(default)]
+    pub bytes_total: u64,
     /// 是否完成
     pub done: bool,
+    /// 是否被取消
+    #[serde(default)]
+    pub cancelled: bool,
     /// 错误信息
     pub error: Option<String>,
 }
 
+/// 文件指纹：大小 + 修改时间，用于判断文件是否变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 毫秒）
+    pub mtime_ms: i64,
+}
+
+/// 文件传输方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferMode {
+    /// 移动（默认）
+    #[default]
+    Move,
+    /// 复制，原文件保留
+    Copy,
+    /// 硬链接（要求源和目标在同一文件系统）
+    Hardlink,
+    /// 符号链接，指向原文件
+    Symlink,
+    /// 写时复制克隆（仅支持 Btrfs/XFS 等文件系统）
+    Reflink,
+}
+
+/// 移动请求：一张图片及其要移动到的人物文件夹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveImageRequest {
+    pub path: String,
+    pub filename: String,
+    /// 主人物（为空时取 `persons` 的第一个）
+    pub person: String,
+    /// 图片中的全部人物，用于多人物策略
+    #[serde(default)]
+    pub persons: Vec<String>,
+}
+
+/// 多人物图片的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiPersonStrategy {
+    /// 只放入主人物文件夹（默认）
+    #[default]
+    Primary,
+    /// 放入主人物文件夹，并复制到其他人物文件夹
+    CopyAll,
+    /// 放入主人物文件夹，并在其他人物文件夹创建硬链接
+    HardlinkAll,
+    /// 放入合并文件夹，如 "Alice+Bob"
+    Combined,
+    /// 人物数超过阈值时放入 "Group" 文件夹
+    Group,
+}
+
+/// 目标位置已有同名文件时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 按改名模式生成新文件名（默认）
+    #[default]
+    Rename,
+    /// 跳过，源文件保留原处
+    Skip,
+    /// 覆盖目标处的文件
+    Overwrite,
+    /// 保留较大的文件：源文件更大时覆盖，否则跳过
+    KeepLarger,
+    /// 保留较新的文件：源文件更新时覆盖，否则跳过
+    KeepNewer,
+    /// 内容相同（大小 + 哈希）时视为重复：移动时删除源文件，其他方式不做处理；
+    /// 内容不同时按改名处理
+    Deduplicate,
+}
+
+/// 单个文件的冲突处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictOutcome {
+    /// 改名后传输
+    Renamed,
+    /// 覆盖了目标处原有的文件
+    Overwritten,
+    /// 按策略跳过
+    Skipped,
+    /// 目标处的文件更大或更新，保留目标、跳过源文件
+    KeptExisting,
+    /// 目标处已有相同内容的文件
+    Deduplicated,
+}
+
+/// 移动选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveOptions {
+    /// 传输方式
+    #[serde(default)]
+    pub mode: TransferMode,
+    /// 目标路径模板（相对目标文件夹），为空时按 "{person}/{filename}" 放置
+    #[serde(default)]
+    pub path_template: Option<String>,
+    /// 多人物图片的处理策略
+    #[serde(default)]
+    pub multi_person: MultiPersonStrategy,
+    /// Group 策略下，人物数超过该值时放入合影文件夹
+    #[serde(default = "default_group_threshold")]
+    pub group_threshold: usize,
+    /// 同名冲突处理策略
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// 改名模式，可用 {stem}、{n}、{ext}（含点），默认 "{stem}_{n}{ext}"
+    #[serde(default)]
+    pub rename_pattern: Option<String>,
+    /// 人物名称等转换为文件夹名时的清理规则
+    #[serde(default)]
+    pub name_rules: NameRules,
+    /// 伴随文件规则
+    #[serde(default)]
+    pub companions: CompanionRules,
+    /// 复制（包括跨设备移动）后重新读取目标文件，与源文件的哈希比较
+    #[serde(default)]
+    pub verify_checksum: bool,
+    /// 需要删除文件时（跨设备移动后的源文件、覆盖、去重）移到回收站而不是直接删除
+    #[serde(default)]
+    pub use_trash: bool,
+    /// 同时复制的文件数上限（改名和链接不受影响），1 表示逐个传输
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+}
+
+fn default_group_threshold() -> usize {
+    1
+}
+
+fn default_max_parallel() -> usize {
+    4
+}
+
+impl Default for MoveOptions {
+    fn default() -> Self {
+        Self {
+            mode: TransferMode::default(),
+            path_template: None,
+            multi_person: MultiPersonStrategy::default(),
+            group_threshold: default_group_threshold(),
+            conflict_policy: ConflictPolicy::default(),
+            rename_pattern: None,
+            name_rules: NameRules::default(),
+            companions: CompanionRules::default(),
+            verify_checksum: false,
+            use_trash: false,
+            max_parallel: default_max_parallel(),
+        }
+    }
+}
+
+/// 文件夹名清理规则：人物名称和模板变量值在作为路径的一层之前按此清理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameRules {
+    /// 替换非法字符（路径分隔符、Windows 保留字符、控制字符）的字符串
+    #[serde(default = "default_name_replacement")]
+    pub replacement: String,
+    /// 额外的替换规则，在清理非法字符之前按顺序应用（如 "&" → "and"）
+    #[serde(default)]
+    pub replacements: Vec<NameReplacement>,
+    /// 单层文件夹名的最大字节数（UTF-8），超出部分截断
+    #[serde(default = "default_max_name_bytes")]
+    pub max_bytes: usize,
+}
+
+/// 一条自定义替换规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameReplacement {
+    pub from: String,
+    pub to: String,
+}
+
+fn default_name_replacement() -> String {
+    "_".to_string()
+}
+
+fn default_max_name_bytes() -> usize {
+    255
+}
+
+impl Default for NameRules {
+    fn default() -> Self {
+        Self {
+            replacement: default_name_replacement(),
+            replacements: Vec::new(),
+            max_bytes: default_max_name_bytes(),
+        }
+    }
+}
+
+/// 伴随文件规则：与图片同目录、同名（或以图片完整文件名为前缀）且扩展名在列表中的文件
+/// 与图片组成一组，一起移动、改名和撤销
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionRules {
+    /// 是否启用伴随文件分组
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 伴随文件的扩展名（不含点，不区分大小写）
+    #[serde(default = "default_companion_extensions")]
+    pub extensions: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_companion_extensions() -> Vec<String> {
+    [
+        "xmp", "json", "aae", "cr2", "cr3", "nef", "arw", "raf", "orf", "rw2", "dng", "pef", "srw",
+        "mov", "mp4",
+    ]
+    .iter()
+    .map(|ext| ext.to_string())
+    .collect()
+}
+
+impl Default for CompanionRules {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            extensions: default_companion_extensions(),
+        }
+    }
+}
+
+/// 移动计划中发现的问题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanIssue {
+    /// 源文件不存在，执行时跳过
+    SourceMissing,
+    /// 目标位置已有同名文件，已自动改名
+    NameConflict,
+    /// 与计划中的其他文件目标同名，已自动改名
+    DuplicateDestination,
+    /// 源和目标不在同一设备：移动会退化为复制 + 删除，硬链接/克隆会失败
+    CrossDevice,
+    /// 目标文件夹（或移动时的源文件夹）不可写
+    PermissionDenied,
+}
+
+/// 计划中的单个传输
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedTransfer {
+    /// 源文件路径
+    pub source: String,
+    /// 目标路径（已处理同名冲突）
+    pub destination: String,
+    /// 原文件名
+    pub filename: String,
+    /// 传输方式
+    pub mode: TransferMode,
+    /// 文件大小（字节），源文件不存在时为 0
+    pub size: u64,
+    /// 同名冲突的处理结果，无冲突时为空
+    #[serde(default)]
+    pub conflict: Option<ConflictOutcome>,
+    /// 发现的问题，为空表示可以正常执行
+    #[serde(default)]
+    pub issues: Vec<PlanIssue>,
+    /// 所属文件组（图片与其伴随文件共用，取图片的目标路径），单个文件时为空
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// 移动计划：只计算不改动磁盘，确认后按原样执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovePlan {
+    /// 目标文件夹
+    pub target_dir: String,
+    /// 所有计划中的传输，按执行顺序排列
+    pub items: Vec<PlannedTransfer>,
+    /// 需要写入的总字节数（移动到同一设备和链接不计入）
+    pub estimated_bytes: u64,
+    /// 同名冲突数（含计划内重名）
+    pub conflict_count: usize,
+    /// 源文件缺失数
+    pub missing_count: usize,
+    /// 跨设备传输数
+    pub cross_device_count: usize,
+    /// 权限问题数
+    pub permission_count: usize,
+    /// 复制后是否校验内容哈希（大小总是校验）
+    #[serde(default)]
+    pub verify_checksum: bool,
+    /// 需要删除的文件是否移到回收站
+    #[serde(default)]
+    pub use_trash: bool,
+    /// 同时复制的文件数上限
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+}
+
+/// 路径模板预览：源文件与渲染后的目标路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathPreview {
+    /// 源文件路径
+    pub source: String,
+    /// 目标路径（尚未处理同名冲突）
+    pub destination: String,
+}
+
 /// 移动操作记录（用于撤销）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveRecord {
@@ -78,6 +521,127 @@ pub struct MoveRecord {
     pub new_path: String,
     /// 文件名
     pub filename: String,
+    /// 传输方式
+    #[serde(default)]
+    pub mode: TransferMode,
+    /// 传输完成时目标文件的指纹（撤销复制时用来确认副本未被修改）
+    #[serde(default)]
+    pub dest_stamp: Option<FileStamp>,
+    /// 同名冲突的处理结果，无冲突时为空
+    #[serde(default)]
+    pub conflict: Option<ConflictOutcome>,
+    /// 传输完成时目标文件内容的 BLAKE3 哈希（十六进制），链接为空。
+    /// 撤销时与指纹一起确认文件仍是当初移动的那个
+    #[serde(default)]
+    pub dest_hash: Option<String>,
+    /// 所属文件组，整组一起撤销
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl MoveRecord {
+    /// 撤销时是否需要处理：跳过的文件没有改动，
+    /// 去重时只有移动方式删除了源文件
+    pub fn needs_undo(&self) -> bool {
+        match self.conflict {
+            Some(ConflictOutcome::Skipped | ConflictOutcome::KeptExisting) => false,
+            Some(ConflictOutcome::Deduplicated) => self.mode == TransferMode::Move,
+            _ => true,
+        }
+    }
+}
+
+/// 传输失败的原因分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveErrorKind {
+    /// 源文件不存在
+    SourceMissing,
+    /// 目标位置已被占用
+    DestinationExists,
+    /// 去重时目标文件内容已变化
+    ContentChanged,
+    /// 没有读写权限
+    PermissionDenied,
+    /// 磁盘空间不足
+    NoSpace,
+    /// 传输方式不受支持（如文件系统不支持 reflink）
+    Unsupported,
+    /// 复制过程中被取消
+    Cancelled,
+    /// 预写日志写入失败，传输已停止
+    Journal,
+    /// 同组的其他文件（或同一图片的主文件夹）传输失败，本文件已回退
+    CompanionFailed,
+    /// 复制后的文件大小或哈希与源文件不一致，源文件未删除
+    VerifyFailed,
+    /// 其他 I/O 错误
+    Io,
+}
+
+/// 单个文件的传输失败
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveFailure {
+    pub source: String,
+    pub destination: String,
+    pub kind: MoveErrorKind,
+    /// 消息键（如 "move-source-missing"），前端用它查找本地化文本
+    pub key: String,
+    /// 按当前语言渲染好的错误信息
+    pub message: String,
+}
+
+/// 执行移动计划的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveReport {
+    /// 已完成部分的操作日志（取消或部分失败时也可撤销）
+    pub log: OperationLog,
+    /// 失败的文件
+    pub failures: Vec<MoveFailure>,
+    /// 是否被取消（剩余文件未处理）
+    pub cancelled: bool,
+}
+
+/// 撤销时单个文件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoOutcome {
+    /// 已恢复（移回原处或删除了副本、链接）
+    Restored,
+    /// 目标文件在操作后被修改或替换，未处理
+    SkippedModified,
+    /// 原位置已有其他文件，未移回
+    SkippedOccupied,
+    /// 同组的其他文件无法撤销，整组保持不动
+    SkippedGroup,
+    /// 目标文件已不存在
+    Missing,
+    /// 恢复时出错
+    Failed,
+}
+
+impl UndoOutcome {
+    /// 是否已了结：已恢复，或目标文件已不存在（没有可撤销的了）；
+    /// 其余结果的文件仍在目标处，可以再次撤销
+    pub fn is_settled(self) -> bool {
+        matches!(self, Self::Restored | Self::Missing)
+    }
+}
+
+/// 撤销时单个文件的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoFileResult {
+    /// 原始路径
+    pub original_path: String,
+    /// 移动后的路径
+    pub new_path: String,
+    pub outcome: UndoOutcome,
+    /// 出错时的错误信息
+    pub error: Option<String>,
+    /// 出错时的消息键
+    pub error_key: Option<String>,
+    /// 出错时的错误类型
+    pub error_kind: Option<ErrorKind>,
 }
 
 /// 操作日志（用于撤销整次操作）
@@ -91,6 +655,26 @@ pub struct OperationLog {
     pub target_dir: String,
     /// 所有移动记录
     pub records: Vec<MoveRecord>,
+    /// 本次操作新建的文件夹（由浅到深），撤销时只删除这些
+    #[serde(default)]
+    pub created_dirs: Vec<String>,
+    /// 执行时的传输选项，重做时沿用
+    #[serde(default)]
+    pub verify_checksum: bool,
+    #[serde(default)]
+    pub use_trash: bool,
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+}
+
+impl OperationLog {
+    /// 部分撤销后去掉已了结的记录，留下仍可再次撤销的；
+    /// `files` 是 `file_ops::undo_move` 按顺序返回的结果
+    pub fn retain_unsettled(&mut self, files: &[UndoFileResult]) {
+        let mut outcomes = files.iter().map(|f| f.outcome);
+        self.records
+            .retain(|r| !r.needs_undo() || !outcomes.next().is_some_and(UndoOutcome::is_settled));
+    }
 }
 
 /// 扫描统计信息
@@ -102,4 +686,7 @@ pub struct ScanStats {
     pub person_count: usize,
     /// 人物名称列表
     pub person_names: Vec<String>,
+    /// 读取失败的文件数（失败详情随 `ScanBatchEvent` 发送）
+    #[serde(default)]
+    pub failed_count: usize,
 }
@@ -0,0 +1,347 @@
+// 路径模板模块
+// 解析和渲染目标路径模板，例如 "{person}/{year}/{month}" 或
+// "{keyword:Event|*}/{year}-{month}-{day}_{filename}"
+
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, NaiveDateTime};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::FluentValue;
+use crate::metadata;
+use crate::models::NameRules;
+use crate::sanitize;
+
+/// 缺少拍摄时间、相机或关键字时使用的占位名称
+const UNKNOWN_VALUE: &str = "Unknown";
+
+/// 模板中的变量
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 人物名称（为空时该层目录省略）
+    Person,
+    /// 拍摄年份（四位）
+    Year,
+    /// 拍摄月份（两位）
+    Month,
+    /// 拍摄日期（两位）
+    Day,
+    /// 相机型号
+    Camera,
+    /// 小写扩展名（不含点）
+    Ext,
+    /// 不含扩展名的文件名
+    Stem,
+    /// 完整文件名
+    Filename,
+    /// 原文件所在文件夹的名称
+    OriginalDir,
+    /// 匹配模式的关键字；模式中的 * 为捕获部分
+    Keyword(String),
+}
+
+/// 模板片段
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Token(Token),
+}
+
+/// 解析后的路径模板
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    /// 按 "/" 分隔的各层路径
+    segments: Vec<Vec<Part>>,
+    /// 模板最后一层不含文件名变量时，自动在末尾追加原文件名
+    appends_filename: bool,
+}
+
+/// 渲染模板所需的单个文件信息
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    pub person: String,
+    pub filename: String,
+    pub original_dir: String,
+    pub taken_at: Option<NaiveDateTime>,
+    pub camera: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+impl PathTemplate {
+    /// 解析并校验模板
+    pub fn parse(template: &str) -> AppResult<Self> {
+        let template = template.trim();
+        if template.is_empty() {
+            return Err(invalid("template-empty"));
+        }
+        // 拒绝 "/xxx"、"\\xxx" 和 "C:\\xxx" 这类绝对路径
+        if template.starts_with(['/', '\\']) || template.chars().nth(1) == Some(':') {
+            return Err(invalid_with("template-absolute", "template", template));
+        }
+
+        let mut segments = Vec::new();
+        for raw in split_segments(template)? {
+            let parts = parse_segment(&raw)?;
+            if parts.is_empty() {
+                continue;
+            }
+            if let [Part::Literal(text)] = parts.as_slice() {
+                if text == "." || text == ".." {
+                    return Err(invalid_with("template-dot-segment", "text", text));
+                }
+            }
+            segments.push(parts);
+        }
+        if segments.is_empty() {
+            return Err(invalid("template-empty"));
+        }
+
+        // 文件名变量只能出现在最后一层
+        let is_name_token = |p: &Part| matches!(p, Part::Token(Token::Filename | Token::Stem));
+        let last = segments.len() - 1;
+        if segments[..last].iter().flatten().any(is_name_token) {
+            return Err(invalid("template-filename-not-last"));
+        }
+        let appends_filename = !segments[last].iter().any(is_name_token);
+
+        Ok(Self {
+            segments,
+            appends_filename,
+        })
+    }
+
+    /// 模板是否用到需要读取图片元数据的变量
+    pub fn needs_metadata(&self) -> bool {
+        self.segments.iter().flatten().any(|p| {
+            matches!(
+                p,
+                Part::Token(Token::Year | Token::Month | Token::Day | Token::Camera | Token::Keyword(_))
+            )
+        })
+    }
+
+    /// 渲染为相对于目标文件夹的路径（包含文件名），每层按 `rules` 清理为安全的名称
+    pub fn render(&self, ctx: &TemplateContext, rules: &NameRules) -> PathBuf {
+        let mut path = PathBuf::new();
+        for segment in &self.segments {
+            let text: String = segment.iter().map(|part| render_part(part, ctx)).collect();
+            // 变量为空（如无人物标签）时整层省略
+            if text.trim().is_empty() {
+                continue;
+            }
+            path.push(sanitize::sanitize_component(&text, rules));
+        }
+        if self.appends_filename {
+            path.push(&ctx.filename);
+        }
+        path
+    }
+}
+
+impl TemplateContext {
+    /// 根据源文件构造渲染上下文；`with_metadata` 为 true 时读取拍摄时间、相机和关键字
+    pub fn from_source(source: &Path, person: &str, with_metadata: bool) -> Self {
+        let filename = source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let original_dir = source
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut ctx = Self {
+            person: person.to_string(),
+            filename,
+            original_dir,
+            ..Default::default()
+        };
+
+        if with_metadata {
+            let meta = metadata::extract_metadata(source);
+            // 没有 EXIF 拍摄时间时退回文件修改时间
+            ctx.taken_at = meta.taken_at.or_else(|| {
+                let modified = std::fs::metadata(source).and_then(|m| m.modified()).ok()?;
+                Some(chrono::DateTime::<chrono::Local>::from(modified).naive_local())
+            });
+            ctx.camera = meta.camera;
+            ctx.keywords = meta.keywords;
+        }
+        ctx
+    }
+}
+
+/// 按 "/" 或 "\" 拆分模板，变量内部的分隔符（如 {keyword:Event/*}）不拆分
+fn split_segments(template: &str) -> AppResult<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in template.chars() {
+        match c {
+            '{' => {
+                if depth > 0 {
+                    return Err(invalid("template-nested-braces"));
+                }
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                if depth == 0 {
+                    return Err(invalid("template-extra-brace"));
+                }
+                depth -= 1;
+                current.push(c);
+            }
+            '/' | '\\' if depth == 0 => segments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if depth > 0 {
+        return Err(invalid("template-unclosed-brace"));
+    }
+    segments.push(current);
+    Ok(segments)
+}
+
+/// 解析单层路径中的文字和变量
+fn parse_segment(segment: &str) -> AppResult<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut rest = segment;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Literal(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find('}')
+            .map(|i| start + i)
+            .ok_or_else(|| invalid("template-unclosed-brace"))?;
+        parts.push(Part::Token(parse_token(&rest[start + 1..end])?));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+fn parse_token(name: &str) -> AppResult<Token> {
+    if let Some(pattern) = name.strip_prefix("keyword:") {
+        if pattern.is_empty() {
+            return Err(invalid("template-keyword-pattern"));
+        }
+        if pattern.matches('*').count() > 1 {
+            return Err(invalid_with("template-keyword-stars", "pattern", pattern));
+        }
+        return Ok(Token::Keyword(pattern.to_string()));
+    }
+    match name {
+        "person" => Ok(Token::Person),
+        "year" => Ok(Token::Year),
+        "month" => Ok(Token::Month),
+        "day" => Ok(Token::Day),
+        "camera" => Ok(Token::Camera),
+        "ext" => Ok(Token::Ext),
+        "stem" => Ok(Token::Stem),
+        "filename" => Ok(Token::Filename),
+        "original_dir" => Ok(Token::OriginalDir),
+        _ => Err(invalid_with("template-unknown-var", "name", name)),
+    }
+}
+
+fn invalid(key: &'static str) -> AppError {
+    AppError::new(ErrorKind::InvalidInput, key)
+}
+
+fn invalid_with(key: &'static str, name: &str, value: &str) -> AppError {
+    AppError::with_args(ErrorKind::InvalidInput, key, &[(name, FluentValue::from(value))])
+}
+
+fn render_part(part: &Part, ctx: &TemplateContext) -> String {
+    match part {
+        Part::Literal(text) => text.clone(),
+        Part::Token(token) => match token {
+            Token::Person => ctx.person.clone(),
+            Token::Year => date_value(ctx, |d| format!("{:04}", d.year())),
+            Token::Month => date_value(ctx, |d| format!("{:02}", d.month())),
+            Token::Day => date_value(ctx, |d| format!("{:02}", d.day())),
+            Token::Camera => ctx.camera.clone().unwrap_or_else(|| UNKNOWN_VALUE.to_string()),
+            Token::Ext => Path::new(&ctx.filename)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            Token::Stem => Path::new(&ctx.filename)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            Token::Filename => ctx.filename.clone(),
+            Token::OriginalDir => ctx.original_dir.clone(),
+            Token::Keyword(pattern) => ctx
+                .keywords
+                .iter()
+                .find_map(|kw| match_keyword(pattern, kw))
+                .unwrap_or_else(|| UNKNOWN_VALUE.to_string()),
+        },
+    }
+}
+
+fn date_value(ctx: &TemplateContext, f: impl Fn(&NaiveDateTime) -> String) -> String {
+    ctx.taken_at
+        .as_ref()
+        .map(f)
+        .unwrap_or_else(|| UNKNOWN_VALUE.to_string())
+}
+
+/// 按模式匹配关键字：含 * 时返回 * 匹配到的部分，否则完全相等时返回关键字本身
+fn match_keyword(pattern: &str, keyword: &str) -> Option<String> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            let captured = keyword.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            (!captured.is_empty()).then(|| captured.to_string())
+        }
+        None => (pattern == keyword).then(|| keyword.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TemplateContext {
+        TemplateContext {
+            person: "Alice".to_string(),
+            filename: "IMG_1.JPG".to_string(),
+            original_dir: "DCIM".to_string(),
+            taken_at: NaiveDateTime::parse_from_str("2024-03-05 10:00:00", "%Y-%m-%d %H:%M:%S").ok(),
+            camera: None,
+            keywords: vec!["Event|Wedding".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let t = PathTemplate::parse("{person}/{year}/{month}").unwrap();
+        assert_eq!(t.render(&sample(), &NameRules::default()), PathBuf::from("Alice/2024/03/IMG_1.JPG"));
+
+        let t = PathTemplate::parse("{keyword:Event|*}/{year}{month}{day}_{stem}.{ext}").unwrap();
+        assert_eq!(t.render(&sample(), &NameRules::default()), PathBuf::from("Wedding/20240305_IMG_1.jpg"));
+
+        let t = PathTemplate::parse("{person}/{camera}").unwrap();
+        let ctx = TemplateContext {
+            person: String::new(),
+            ..sample()
+        };
+        assert_eq!(t.render(&ctx, &NameRules::default()), PathBuf::from("Unknown/IMG_1.JPG"));
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(PathTemplate::parse("").is_err());
+        assert!(PathTemplate::parse("{nope}").is_err());
+        assert!(PathTemplate::parse("{person").is_err());
+        assert!(PathTemplate::parse("../{person}").is_err());
+        assert!(PathTemplate::parse("{filename}/{person}").is_err());
+        assert!(PathTemplate::parse("/abs/{person}").is_err());
+    }
+}
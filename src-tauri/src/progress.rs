@@ -0,0 +1,106 @@
+// 进度事件模块
+// 扫描结果按批次推送（每 N 条或每隔 M 毫秒一批），进度计数按固定间隔推送，
+// 避免多个工作线程每处理一张图片就发送一个事件、让前端忙于处理事件而卡住
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// 批次收集器：可以在多个线程间共享
+pub struct Batcher<T> {
+    size: usize,
+    interval: Duration,
+    /// 尚未发送的条目和上一批的发送时间
+    state: Mutex<(Vec<T>, Instant)>,
+}
+
+impl<T> Batcher<T> {
+    /// `size` 为每批最多条数，`interval` 为两批之间的最长间隔
+    pub fn new(size: usize, interval: Duration) -> Self {
+        Self {
+            size: size.max(1),
+            interval,
+            state: Mutex::new((Vec::new(), Instant::now())),
+        }
+    }
+
+    /// 加入一条结果；达到批次大小或距上一批超过间隔时返回要发送的一批
+    pub fn push(&self, item: T) -> Option<Vec<T>> {
+        let mut state = self.state.lock();
+        state.0.push(item);
+        if state.0.len() < self.size && state.1.elapsed() < self.interval {
+            return None;
+        }
+        state.1 = Instant::now();
+        Some(std::mem::take(&mut state.0))
+    }
+
+    /// 取出剩余的结果（扫描结束时调用）
+    pub fn take(&self) -> Vec<T> {
+        let mut state = self.state.lock();
+        state.1 = Instant::now();
+        std::mem::take(&mut state.0)
+    }
+}
+
+/// 限流器：两次放行之间至少间隔 `interval`
+pub struct Throttle {
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// 是否可以发送；第一次调用总是放行
+    pub fn ready(&self) -> bool {
+        let mut last = self.last.lock();
+        if last.is_some_and(|at| at.elapsed() < self.interval) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        true
+    }
+}
+
+/// 按已用时间和完成比例估算剩余时间；尚未完成任何一项时无法估算
+pub fn eta(elapsed: Duration, done: usize, total: usize) -> Option<Duration> {
+    if done == 0 || total < done {
+        return None;
+    }
+    let per_item = elapsed.as_secs_f64() / done as f64;
+    Some(Duration::from_secs_f64(per_item * (total - done) as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batcher_and_eta() {
+        let batcher = Batcher::new(3, Duration::from_secs(3600));
+        assert_eq!(batcher.push(1), None);
+        assert_eq!(batcher.push(2), None);
+        assert_eq!(batcher.push(3), Some(vec![1, 2, 3]));
+        assert_eq!(batcher.push(4), None);
+        assert_eq!(batcher.take(), vec![4]);
+        assert!(batcher.take().is_empty());
+
+        // 间隔为 0 时每条结果单独成批
+        let batcher = Batcher::new(100, Duration::ZERO);
+        assert_eq!(batcher.push(1), Some(vec![1]));
+
+        let throttle = Throttle::new(Duration::from_secs(3600));
+        assert!(throttle.ready());
+        assert!(!throttle.ready());
+
+        assert_eq!(eta(Duration::from_secs(10), 0, 100), None);
+        assert_eq!(eta(Duration::from_secs(10), 10, 30), Some(Duration::from_secs(20)));
+        assert_eq!(eta(Duration::from_secs(10), 30, 30), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,200 @@
+// 文件夹名清理模块
+// 把人物名称、模板变量值等任意文本转换为安全的单层文件夹名：
+// 不含路径分隔符和 Windows/SMB 不允许的字符，不是保留设备名，不超过长度限制，
+// 并保证最终目标路径不会跳出目标文件夹
+
+use std::path::{Component, Path};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::FluentValue;
+use crate::models::NameRules;
+
+/// Windows 不允许出现在文件名中的字符（同时包含两种路径分隔符）
+const FORBIDDEN_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Windows 保留的设备名，不区分大小写，带扩展名（如 "CON.txt"）同样不可用
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 文件系统允许的单层名称最大字节数
+const MAX_COMPONENT_BYTES: usize = 255;
+
+/// 校验清理规则本身：替换字符串不能再引入非法字符，长度限制要在合理范围内
+pub fn validate_rules(rules: &NameRules) -> AppResult<()> {
+    if rules.replacement.is_empty() {
+        return Err(AppError::new(ErrorKind::InvalidInput, "name-rules-empty-replacement"));
+    }
+    if rules.replacement.chars().any(is_forbidden) || rules.replacement.chars().all(|c| c == '.') {
+        let args = [("replacement", FluentValue::from(rules.replacement.as_str()))];
+        return Err(AppError::with_args(ErrorKind::InvalidInput, "name-rules-bad-replacement", &args));
+    }
+    if rules.max_bytes == 0 || rules.max_bytes > MAX_COMPONENT_BYTES {
+        let args = [
+            ("max", FluentValue::from(MAX_COMPONENT_BYTES)),
+            ("value", FluentValue::from(rules.max_bytes)),
+        ];
+        return Err(AppError::with_args(ErrorKind::InvalidInput, "name-rules-max-bytes", &args));
+    }
+    if rules.replacements.iter().any(|r| r.from.is_empty()) {
+        return Err(AppError::new(ErrorKind::InvalidInput, "name-rules-empty-from"));
+    }
+    Ok(())
+}
+
+/// 把任意文本清理为安全的单层文件夹名；输入为空时返回空字符串（表示省略该层）
+pub fn sanitize_component(name: &str, rules: &NameRules) -> String {
+    let mut text = name.to_string();
+    for r in &rules.replacements {
+        text = text.replace(&r.from, &r.to);
+    }
+
+    let mut cleaned = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_forbidden(c) {
+            cleaned.push_str(&rules.replacement);
+        } else {
+            cleaned.push(c);
+        }
+    }
+
+    // Windows 会去掉末尾的点和空格，"." 和 ".." 也因此变为空
+    let mut cleaned = trim_name(&cleaned).to_string();
+    if cleaned.is_empty() {
+        return if name.trim().is_empty() {
+            String::new()
+        } else {
+            rules.replacement.clone()
+        };
+    }
+
+    if is_reserved(&cleaned) {
+        // Windows 按第一个点之前的部分判断保留名，替换字符要加在这部分之后
+        let stem_end = cleaned.find('.').unwrap_or(cleaned.len());
+        cleaned.insert_str(stem_end, &rules.replacement);
+    }
+
+    if cleaned.len() > rules.max_bytes {
+        let mut end = rules.max_bytes;
+        while !cleaned.is_char_boundary(end) {
+            end -= 1;
+        }
+        cleaned.truncate(end);
+        cleaned = trim_name(&cleaned).to_string();
+        if cleaned.is_empty() || is_reserved(&cleaned) {
+            cleaned = rules.replacement.clone();
+        }
+    }
+    cleaned
+}
+
+/// 确认 `path` 位于 `target_dir` 之内（按路径字面判断，不访问磁盘）
+pub fn ensure_within(target_dir: &Path, path: &Path) -> AppResult<()> {
+    let escaped = || {
+        AppError::with_args(
+            ErrorKind::InvalidInput,
+            "destination-outside-target",
+            &[("path", FluentValue::from(path.display().to_string()))],
+        )
+        .with_path(path)
+    };
+    let relative = path.strip_prefix(target_dir).map_err(|_| escaped())?;
+    if relative.as_os_str().is_empty() {
+        return Err(escaped());
+    }
+    if relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        Ok(())
+    } else {
+        Err(escaped())
+    }
+}
+
+fn is_forbidden(c: char) -> bool {
+    c.is_control() || FORBIDDEN_CHARS.contains(&c)
+}
+
+fn trim_name(name: &str) -> &str {
+    name.trim().trim_end_matches(['.', ' '])
+}
+
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_ops::{destination_path, parse_template};
+    use crate::models::NameReplacement;
+
+    #[test]
+    fn test_sanitize_component() {
+        let rules = NameRules::default();
+        assert_eq!(sanitize_component("Alice", &rules), "Alice");
+        assert_eq!(sanitize_component("", &rules), "");
+        assert_eq!(sanitize_component("AC/DC", &rules), "AC_DC");
+        assert_eq!(sanitize_component("../../etc", &rules), ".._.._etc");
+        assert_eq!(sanitize_component("..", &rules), "_");
+        assert_eq!(sanitize_component("C:\\Users", &rules), "C__Users");
+        assert_eq!(sanitize_component("con", &rules), "con_");
+        assert_eq!(sanitize_component("LPT1.txt", &rules), "LPT1_.txt");
+        assert_eq!(sanitize_component("nul.tar.gz", &rules), "nul_.tar.gz");
+        assert_eq!(sanitize_component("aux .jpg", &rules), "aux _.jpg");
+        assert_eq!(sanitize_component("Dr. Who. ", &rules), "Dr. Who");
+        assert_eq!(sanitize_component("a\tb\u{0}", &rules), "a_b_");
+
+        let rules = NameRules {
+            replacement: "-".to_string(),
+            replacements: vec![NameReplacement {
+                from: "&".to_string(),
+                to: "and".to_string(),
+            }],
+            max_bytes: 10,
+        };
+        assert_eq!(sanitize_component("Tom & Jerry", &rules), "Tom and Je");
+        assert_eq!(sanitize_component("张三李四王五", &rules), "张三李");
+        assert!(validate_rules(&NameRules {
+            replacement: "/".to_string(),
+            ..NameRules::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_destination_never_escapes_target() {
+        let target = Path::new("/photos/sorted");
+        let source = Path::new("/inbox/IMG_1.jpg");
+        let rules = NameRules::default();
+        let names = [
+            "..", "../..", "../../etc", "/etc", "\\\\server\\share", "C:\\Users", "C:", "a/../../b",
+            ".", "...", " .. ", "CON", "nul.txt", "x/", "/", "\\", "a\u{0}b",
+        ];
+        let templates = [None, Some("{person}/{year}"), Some("{original_dir}/{person}/{stem}.{ext}")];
+        for template in templates {
+            let template = parse_template(template).unwrap();
+            for name in names {
+                let dest = destination_path(template.as_ref(), target, source, name, &rules);
+                assert!(
+                    ensure_within(target, &dest).is_ok(),
+                    "{:?} escaped: {}",
+                    name,
+                    dest.display()
+                );
+                assert_eq!(dest.file_name().unwrap(), "IMG_1.jpg", "{:?}", name);
+                let relative = dest.strip_prefix(target).unwrap();
+                assert!(
+                    relative.iter().all(|c| !is_reserved(&c.to_string_lossy())),
+                    "{:?} is reserved: {}",
+                    name,
+                    dest.display()
+                );
+            }
+        }
+
+        assert!(ensure_within(target, Path::new("/photos/sorted/../x.jpg")).is_err());
+        assert!(ensure_within(target, Path::new("/photos/other/x.jpg")).is_err());
+        assert!(ensure_within(target, target).is_err());
+    }
+}
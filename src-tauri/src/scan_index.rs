@@ -0,0 +1,349 @@
+// 扫描索引模块
+// 将每个文件的扫描结果（大小、修改时间、人物、关键字、缩略图）持久化到本地，
+// 重新扫描时只处理新增或修改过的文件
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::i18n::tr;
+use crate::models::{FaceRegion, FileStamp, ImageInfo, ImageStatus};
+use crate::scanner;
+
+/// 索引文件名
+const INDEX_FILE_NAME: &str = "scan_index.json";
+/// 缩略图缓存目录名
+const THUMBNAIL_DIR_NAME: &str = "thumbnails";
+/// 缩略图 data URL 前缀（与 scanner 生成的格式一致）
+const THUMBNAIL_DATA_URL_PREFIX: &str = "data:image/jpeg;base64,";
+
+/// 单个文件的索引记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// 文件完整路径
+    pub path: String,
+    /// 扫描时的文件指纹
+    pub stamp: FileStamp,
+    /// 检测到的人物标签列表
+    pub persons: Vec<String>,
+    /// 所有元数据关键字
+    pub keywords: Vec<String>,
+    /// 带坐标的人脸区域
+    #[serde(default)]
+    pub regions: Vec<FaceRegion>,
+    /// 缩略图缓存文件的键（无缩略图时为空）
+    pub thumbnail_key: Option<String>,
+    /// 按文件头识别出的真实格式
+    pub detected_format: Option<String>,
+    /// 扩展名与真实格式不符
+    pub extension_mismatch: bool,
+    /// 建立记录时是否按文件内容识别图片（判断文件是否仍算图片时使用同样的方式）
+    #[serde(default)]
+    pub detect_by_content: bool,
+}
+
+/// 持久化的扫描索引
+pub struct ScanIndex {
+    /// 索引文件路径
+    index_path: PathBuf,
+    /// 缩略图缓存目录
+    thumbnail_dir: PathBuf,
+    /// 以文件路径为键的索引记录
+    entries: HashMap<String, IndexEntry>,
+}
+
+/// 读取文件指纹，文件不存在或无法读取时返回 None
+pub fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Some(FileStamp {
+        size: meta.len(),
+        mtime_ms,
+    })
+}
+
+impl ScanIndex {
+    /// 从数据目录加载索引；文件不存在或损坏时返回空索引
+    pub fn load(data_dir: &Path) -> Self {
+        let index_path = data_dir.join(INDEX_FILE_NAME);
+        let entries = fs::read(&index_path)
+            .ok()
+            .and_then(|data| match serde_json::from_slice::<Vec<IndexEntry>>(&data) {
+                Ok(list) => Some(list),
+                Err(e) => {
+                    log::warn!("{}", tr!("log-scan-index-corrupt", detail = e.to_string()));
+                    None
+                }
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+
+        Self {
+            index_path,
+            thumbnail_dir: data_dir.join(THUMBNAIL_DIR_NAME),
+            entries,
+        }
+    }
+
+    /// 保存索引（先写临时文件再重命名，避免写到一半时损坏）
+    pub fn save(&self) -> AppResult<()> {
+        let list: Vec<&IndexEntry> = self.entries.values().collect();
+        let data = serde_json::to_vec(&list).map_err(|e| AppError::internal("scan-index-save", e))?;
+        let tmp_path = self.index_path.with_extension("json.tmp");
+        self.index_path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp_path, data))
+            .and_then(|_| fs::rename(&tmp_path, &self.index_path))
+            .map_err(|e| AppError::io("scan-index-save", &e).with_path(&self.index_path))
+    }
+
+    /// 如果文件自上次扫描后未变化，直接从索引构造 ImageInfo
+    pub fn cached_image(&self, path: &Path, stamp: FileStamp) -> Option<ImageInfo> {
+        let path_str = path.to_string_lossy();
+        let entry = self.entries.get(path_str.as_ref())?;
+        if entry.stamp != stamp {
+            return None;
+        }
+
+        // 缩略图缓存丢失时视为未命中，重新处理
+        let thumbnail = match &entry.thumbnail_key {
+            Some(key) => {
+                let data = fs::read(self.thumbnail_path(key)).ok()?;
+                format!(
+                    "{}{}",
+                    THUMBNAIL_DATA_URL_PREFIX,
+                    base64::engine::general_purpose::STANDARD.encode(data)
+                )
+            }
+            None => String::new(),
+        };
+
+        Some(ImageInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            path: entry.path.clone(),
+            filename: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            persons: entry.persons.clone(),
+            keywords: entry.keywords.clone(),
+            regions: entry.regions.clone(),
+            thumbnail,
+            selected_person: entry.persons.first().cloned(),
+            status: ImageStatus::Scanned,
+            detected_format: entry.detected_format.clone(),
+            extension_mismatch: entry.extension_mismatch,
+            companions: Vec::new(),
+        })
+    }
+
+    /// 根据新处理的图片生成索引记录，并把缩略图写入缓存目录
+    pub fn make_entry(&self, info: &ImageInfo, stamp: FileStamp, detect_by_content: bool) -> IndexEntry {
+        let thumbnail_key = info
+            .thumbnail
+            .strip_prefix(THUMBNAIL_DATA_URL_PREFIX)
+            .and_then(|b64| base64::engine::general_purpose::STANDARD.decode(b64).ok())
+            .and_then(|data| {
+                let key = uuid::Uuid::new_v4().to_string();
+                fs::create_dir_all(&self.thumbnail_dir).ok()?;
+                match fs::write(self.thumbnail_path(&key), data) {
+                    Ok(()) => Some(key),
+                    Err(e) => {
+                        log::warn!("{}", tr!("log-thumbnail-cache-failed", detail = e.to_string()));
+                        None
+                    }
+                }
+            });
+
+        IndexEntry {
+            path: info.path.clone(),
+            stamp,
+            persons: info.persons.clone(),
+            keywords: info.keywords.clone(),
+            regions: info.regions.clone(),
+            thumbnail_key,
+            detected_format: info.detected_format.clone(),
+            extension_mismatch: info.extension_mismatch,
+            detect_by_content,
+        }
+    }
+
+    /// 写入一批新记录，替换旧记录时删除其过期的缩略图
+    pub fn apply_updates(&mut self, updates: Vec<IndexEntry>) {
+        for entry in updates {
+            if let Some(old) = self.entries.insert(entry.path.clone(), entry) {
+                self.remove_thumbnail(&old);
+            }
+        }
+    }
+
+    /// 移除本次扫描范围内已不存在的文件记录，返回被移除的路径列表。
+    /// 本次没找到的文件不一定已被删除（换了识别方式时找到的文件不同），
+    /// 只移除确实不在了、或按建立记录时的识别方式已不再是图片的文件
+    pub fn prune_missing(
+        &mut self,
+        source_dir: &str,
+        include_subdirs: bool,
+        seen: &HashSet<String>,
+    ) -> Vec<String> {
+        let source = Path::new(source_dir);
+        let removed: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(key, entry)| {
+                let path = Path::new(key.as_str());
+                let in_scope = if include_subdirs {
+                    path.starts_with(source)
+                } else {
+                    path.parent() == Some(source)
+                };
+                let gone = || {
+                    fs::symlink_metadata(path).is_err()
+                        || !scanner::is_image_file(path, entry.detect_by_content)
+                };
+                in_scope && !seen.contains(key.as_str()) && gone()
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for path in &removed {
+            if let Some(old) = self.entries.remove(path) {
+                self.remove_thumbnail(&old);
+            }
+        }
+        removed
+    }
+
+    fn thumbnail_path(&self, key: &str) -> PathBuf {
+        self.thumbnail_dir.join(format!("{}.jpg", key))
+    }
+
+    fn remove_thumbnail(&self, entry: &IndexEntry) {
+        if let Some(key) = &entry.thumbnail_key {
+            let _ = fs::remove_file(self.thumbnail_path(key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0];
+
+    fn info(path: &Path, thumbnail: &[u8]) -> ImageInfo {
+        ImageInfo {
+            id: String::new(),
+            path: path.to_string_lossy().to_string(),
+            filename: path.file_name().unwrap().to_string_lossy().to_string(),
+            persons: vec!["Alice".to_string()],
+            keywords: Vec::new(),
+            regions: Vec::new(),
+            thumbnail: format!(
+                "{}{}",
+                THUMBNAIL_DATA_URL_PREFIX,
+                base64::engine::general_purpose::STANDARD.encode(thumbnail)
+            ),
+            selected_person: None,
+            status: ImageStatus::Scanned,
+            detected_format: Some("jpeg".to_string()),
+            extension_mismatch: false,
+            companions: Vec::new(),
+        }
+    }
+
+    /// 为文件建立索引记录，返回文件的指纹
+    fn index_file(index: &mut ScanIndex, path: &Path, detect_by_content: bool) -> FileStamp {
+        let stamp = file_stamp(path).unwrap();
+        let entry = index.make_entry(&info(path, b"thumb"), stamp, detect_by_content);
+        index.apply_updates(vec![entry]);
+        stamp
+    }
+
+    #[test]
+    fn test_incremental_reuse() {
+        let dir = TempDir::new();
+        let data_dir = dir.join("data");
+        let photo = dir.write("photos/a.jpg", JPEG);
+        let mut index = ScanIndex::load(&data_dir);
+        let stamp = index_file(&mut index, &photo, false);
+        index.save().unwrap();
+
+        // 重新加载后未变化的文件直接复用，缩略图从缓存读回
+        let index = ScanIndex::load(&data_dir);
+        let cached = index.cached_image(&photo, stamp).unwrap();
+        assert_eq!(cached.persons, ["Alice"]);
+        assert_eq!(cached.selected_person.as_deref(), Some("Alice"));
+        assert_eq!(cached.thumbnail, info(&photo, b"thumb").thumbnail);
+
+        let changed = FileStamp {
+            size: stamp.size + 1,
+            ..stamp
+        };
+        assert!(index.cached_image(&photo, changed).is_none());
+        // 缩略图缓存丢失时重新处理
+        fs::remove_dir_all(data_dir.join(THUMBNAIL_DIR_NAME)).unwrap();
+        assert!(index.cached_image(&photo, stamp).is_none());
+
+        // 索引文件损坏时从空索引开始
+        fs::write(data_dir.join(INDEX_FILE_NAME), b"{").unwrap();
+        assert!(ScanIndex::load(&data_dir).entries.is_empty());
+    }
+
+    #[test]
+    fn test_prune_missing() {
+        let dir = TempDir::new();
+        let mut index = ScanIndex::load(&dir.join("data"));
+        let kept = dir.write("photos/kept.jpg", JPEG);
+        let deleted = dir.write("photos/deleted.jpg", JPEG);
+        // 按内容识别时找到的无扩展名图片，按扩展名扫描时找不到但仍然存在
+        let no_ext = dir.write("photos/no_ext", JPEG);
+        // 按内容识别时是图片，之后内容被替换
+        let replaced = dir.write("photos/replaced", JPEG);
+        let nested = dir.write("photos/sub/nested.jpg", JPEG);
+        let outside = dir.write("other/outside.jpg", JPEG);
+        for path in [&kept, &deleted, &nested, &outside] {
+            index_file(&mut index, path, false);
+        }
+        for path in [&no_ext, &replaced] {
+            index_file(&mut index, path, true);
+        }
+        for path in [&deleted, &outside] {
+            fs::remove_file(path).unwrap();
+        }
+        fs::write(&replaced, b"text").unwrap();
+
+        let source = dir.join("photos");
+        let seen: HashSet<String> = HashSet::from([kept.to_string_lossy().to_string()]);
+        let mut removed = index.prune_missing(&source.to_string_lossy(), false, &seen);
+        removed.sort();
+        let expected: Vec<String> =
+            [&deleted, &replaced].iter().map(|p| p.to_string_lossy().to_string()).collect();
+        assert_eq!(removed, expected);
+
+        // 不含子文件夹的扫描不处理子文件夹；范围之外的记录不动
+        let key = |p: &Path| p.to_string_lossy().to_string();
+        assert!(index.entries.contains_key(&key(&no_ext)));
+        assert!(index.entries.contains_key(&key(&nested)));
+        assert!(index.entries.contains_key(&key(&outside)));
+
+        fs::remove_file(&nested).unwrap();
+        let removed = index.prune_missing(&source.to_string_lossy(), true, &seen);
+        assert_eq!(removed, [key(&nested)]);
+    }
+}
@@ -1,12 +1,17 @@
 // 图片扫描模块
 // 遍历文件夹、过滤图片文件、读取元数据、生成缩略图
 
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use base64::Engine;
 use image::GenericImageView;
 use walkdir::WalkDir;
 
+use crate::error::{AppError, AppResult, ErrorKind};
 use crate::metadata;
 use crate::models::{ImageInfo, ImageStatus};
 
@@ -15,12 +20,21 @@ const IMAGE_EXTENSIONS: &[&str] = &[
     "jpg", "jpeg", "png", "webp", "tiff", "tif", "bmp", "gif", "heic", "heif", "avif",
 ];
 
+/// 内容嗅探时读取的文件头字节数（足够覆盖 ftyp 盒中的兼容品牌列表）
+const SNIFF_HEADER_LEN: usize = 64;
+
 /// 缩略图最大尺寸（像素，长边）
 const THUMBNAIL_MAX_SIZE: u32 = 300;
 
 /// 扫描指定文件夹中的图片文件
+/// `detect_by_content` 为 true 时按文件头魔数识别图片，忽略扩展名；
+/// 否则仅按扩展名过滤。
 /// 返回所有图片文件路径列表
-pub fn scan_image_files(source_dir: &str, include_subdirs: bool) -> Vec<PathBuf> {
+pub fn scan_image_files(
+    source_dir: &str,
+    include_subdirs: bool,
+    detect_by_content: bool,
+) -> Vec<PathBuf> {
     let walker = WalkDir::new(source_dir);
     let walker = if include_subdirs {
         walker
@@ -31,24 +45,89 @@ pub fn scan_image_files(source_dir: &str, include_subdirs: bool) -> Vec<PathBuf>
     walker
         .into_iter()
         .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            if !entry.file_type().is_file() {
-                return false;
-            }
-            if let Some(ext) = entry.path().extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                IMAGE_EXTENSIONS.contains(&ext_lower.as_str())
-            } else {
-                false
-            }
-        })
+        .filter(|entry| entry.file_type().is_file() && is_image_file(entry.path(), detect_by_content))
         .map(|entry| entry.into_path())
         .collect()
 }
 
+/// 逐个目录查找图片文件：一个目录（不含子目录）查找完后，
+/// 用该目录和其中的图片文件调用 `on_dir`，调用方可以边查找边处理。
+/// 子目录先于父目录报告；`cancel` 被设置后停止查找
+pub fn discover_image_files(
+    source_dir: &str,
+    include_subdirs: bool,
+    detect_by_content: bool,
+    cancel: &AtomicBool,
+    mut on_dir: impl FnMut(&Path, Vec<PathBuf>),
+) {
+    let walker = WalkDir::new(source_dir).contents_first(true);
+    let walker = if include_subdirs {
+        walker
+    } else {
+        walker.max_depth(1)
+    };
+
+    // 尚未查找完的目录中已找到的图片
+    let mut pending: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for entry in walker.into_iter().filter_map(|entry| entry.ok()) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        if entry.file_type().is_dir() {
+            // 目录在其内容之后出现，此时目录已查找完；不递归时不报告子目录
+            if include_subdirs || entry.depth() == 0 {
+                let files = pending.remove(entry.path()).unwrap_or_default();
+                on_dir(entry.path(), files);
+            }
+        } else if entry.file_type().is_file() && is_image_file(entry.path(), detect_by_content) {
+            let dir = entry.path().parent().unwrap_or(Path::new("")).to_path_buf();
+            pending.entry(dir).or_default().push(entry.into_path());
+        }
+    }
+    // 出错的目录或直接传入文件时剩下的图片
+    for (dir, files) in pending {
+        on_dir(&dir, files);
+    }
+}
+
+/// 判断文件是否为支持的图片
+/// `detect_by_content` 为 true 时按文件头魔数判断，否则按扩展名判断
+pub fn is_image_file(path: &Path, detect_by_content: bool) -> bool {
+    if detect_by_content {
+        return sniff_image_format(path).is_some();
+    }
+    if let Some(ext) = path.extension() {
+        let ext_lower = ext.to_string_lossy().to_lowercase();
+        IMAGE_EXTENSIONS.contains(&ext_lower.as_str())
+    } else {
+        false
+    }
+}
+
+/// 创建扫描用的线程池
+/// 限制并行线程数，避免 100% 占用导致电脑卡顿：
+/// 设置为逻辑核心数的一半，但至少 1 个线程，最多 6 个线程
+pub fn build_worker_pool() -> AppResult<rayon::ThreadPool> {
+    let num_cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    let worker_threads = (num_cpus / 2).clamp(1, 6);
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(worker_threads)
+        .build()
+        .map_err(|e| AppError::internal("scan-thread-pool", e))
+}
+
 /// 处理单张图片：读取元数据 + 生成缩略图
-/// 返回 ImageInfo 或错误信息
-pub fn process_single_image(path: &Path) -> Result<ImageInfo, String> {
+/// 返回 ImageInfo 或错误信息（文件无法读取时）。
+/// 缩略图只用于预览：HEIC 等无法解码的图片仍可按标签分类，解码失败时不显示缩略图
+pub fn process_single_image(path: &Path) -> AppResult<ImageInfo> {
+    let mut info = read_image_info(path)?;
+    info.thumbnail = generate_thumbnail(path).unwrap_or_default();
+    Ok(info)
+}
+
+/// 读取单张图片的元数据，不生成缩略图（供命令行等无界面场景使用）
+pub fn read_image_info(path: &Path) -> AppResult<ImageInfo> {
     let filename = path
         .file_name()
         .unwrap_or_default()
@@ -57,11 +136,23 @@ pub fn process_single_image(path: &Path) -> Result<ImageInfo, String> {
 
     let path_str = path.to_string_lossy().to_string();
 
+    // 元数据读取出错时只会得到空结果，先确认文件可以读取：
+    // 查找之后被删除、没有读取权限或不是普通文件时报告失败
+    check_readable(path)?;
+
     // 读取人物标签和关键字
-    let (persons, keywords) = metadata::extract_person_tags(path);
+    let metadata::ImageMetadata {
+        persons,
+        keywords,
+        regions,
+        ..
+    } = metadata::extract_metadata(path);
 
-    // 生成缩略图
-    let thumbnail = generate_thumbnail(path).unwrap_or_default();
+    // 按文件头识别真实格式，并检查扩展名是否与之相符
+    let detected_format = sniff_image_format(path);
+    let extension_mismatch = detected_format
+        .map(|format| !extension_matches_format(path, format))
+        .unwrap_or(false);
 
     // 只要有人物标签，就默认选择第一个（多人物时也选第一个，用户可在前端修改）
     let selected_person = if !persons.is_empty() {
@@ -76,15 +167,112 @@ pub fn process_single_image(path: &Path) -> Result<ImageInfo, String> {
         filename,
         persons,
         keywords,
-        thumbnail,
+        regions,
+        thumbnail: String::new(),
         selected_person,
         status: ImageStatus::Scanned,
+        detected_format: detected_format.map(|f| f.to_string()),
+        extension_mismatch,
+        companions: Vec::new(),
     })
 }
 
+/// 打开文件并读取开头的字节，确认可以读取
+fn check_readable(path: &Path) -> AppResult<()> {
+    let mut buf = [0u8; 1];
+    fs::File::open(path)
+        .and_then(|mut file| file.read(&mut buf))
+        .map(|_| ())
+        .map_err(|e| AppError::io("image-open", &e).with_path(path))
+}
+
+/// 根据文件头魔数识别图片格式
+/// 返回规范化的格式名（jpeg/png/gif/webp/tiff/bmp/heic/heif/avif），无法识别时返回 None
+pub fn sniff_image_format(path: &Path) -> Option<&'static str> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut header = [0u8; SNIFF_HEADER_LEN];
+    let mut len = 0;
+    // read 可能返回不足长度，循环读满或读到文件末尾
+    while len < header.len() {
+        match file.read(&mut header[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(_) => return None,
+        }
+    }
+    detect_format_from_header(&header[..len])
+}
+
+/// 从文件头字节识别图片格式
+fn detect_format_from_header(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some("jpeg");
+    }
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("png");
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some("gif");
+    }
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+    if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+        return Some("tiff");
+    }
+    if header.starts_with(b"BM") && header.len() >= 14 {
+        return Some("bmp");
+    }
+    // ISO BMFF 容器（HEIC/HEIF/AVIF）：第 4~8 字节为 "ftyp"，随后是主品牌和兼容品牌列表
+    if header.len() >= 12 && &header[4..8] == b"ftyp" {
+        let box_size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let box_end = box_size.clamp(12, header.len());
+        let mut brands = vec![&header[8..12]];
+        // 跳过 minor_version（4 字节），其后均为兼容品牌
+        let mut pos = 16;
+        while pos + 4 <= box_end {
+            brands.push(&header[pos..pos + 4]);
+            pos += 4;
+        }
+        if brands.iter().any(|b| matches!(*b, b"avif" | b"avis")) {
+            return Some("avif");
+        }
+        if brands
+            .iter()
+            .any(|b| matches!(*b, b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis"))
+        {
+            return Some("heic");
+        }
+        if brands.iter().any(|b| matches!(*b, b"mif1" | b"msf1")) {
+            return Some("heif");
+        }
+    }
+    None
+}
+
+/// 判断文件扩展名是否与识别出的格式相符（无扩展名视为不符）
+fn extension_matches_format(path: &Path, format: &str) -> bool {
+    let ext = match path.extension() {
+        Some(ext) => ext.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+    match format {
+        "jpeg" => matches!(ext.as_str(), "jpg" | "jpeg" | "jpe" | "jfif"),
+        "tiff" => matches!(ext.as_str(), "tif" | "tiff"),
+        // HEIC 是 HEIF 的一种编码，两种扩展名互相通用
+        "heic" | "heif" => matches!(ext.as_str(), "heic" | "heif"),
+        other => ext == other,
+    }
+}
+
 /// 生成图片缩略图，返回 base64 编码的 JPEG 数据
-fn generate_thumbnail(path: &Path) -> Result<String, String> {
-    let img = image::open(path).map_err(|e| format!("无法打开图片: {}", e))?;
+fn generate_thumbnail(path: &Path) -> AppResult<String> {
+    // 按文件内容猜测格式，扩展名错误或缺失时也能解码
+    let img = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| AppError::io("image-open", &e).with_path(path))?
+        .decode()
+        .map_err(|e| AppError::caused(ErrorKind::Decode, "image-decode", e).with_path(path))?;
 
     let (w, h) = img.dimensions();
 
@@ -110,9 +298,75 @@ fn generate_thumbnail(path: &Path) -> Result<String, String> {
     let mut cursor = std::io::Cursor::new(&mut buf);
     thumbnail
         .write_to(&mut cursor, image::ImageFormat::Jpeg)
-        .map_err(|e| format!("缩略图编码失败: {}", e))?;
+        .map_err(|e| AppError::caused(ErrorKind::Decode, "thumbnail-encode", e).with_path(path))?;
 
     // 转为 base64
     let b64 = base64::engine::general_purpose::STANDARD.encode(&buf);
     Ok(format!("data:image/jpeg;base64,{}", b64))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_unreadable_files_fail() {
+        let dir = TempDir::new();
+        let key = |path: &Path| process_single_image(path).err().map(|e| e.key);
+        // 查找之后被删除的文件，以及名字像图片的文件夹
+        assert_eq!(key(&dir.join("gone.jpg")), Some("image-open"));
+        fs::create_dir(dir.join("folder.jpg")).unwrap();
+        assert_eq!(key(&dir.join("folder.jpg")), Some("image-open"));
+
+        // 能读取但无法解码的图片仍然返回，只是没有缩略图
+        let broken = dir.write("broken.jpg", &[0xFF, 0xD8, 0xFF, 0x00]);
+        let info = process_single_image(&broken).unwrap();
+        assert_eq!((info.thumbnail.as_str(), info.detected_format.as_deref()), ("", Some("jpeg")));
+    }
+
+    #[test]
+    fn test_detect_format_from_header() {
+        assert_eq!(detect_format_from_header(&[0xFF, 0xD8, 0xFF, 0xE1]), Some("jpeg"));
+        assert_eq!(detect_format_from_header(b"RIFF\0\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(
+            detect_format_from_header(b"\0\0\0\x18ftypheic\0\0\0\0mif1heic"),
+            Some("heic")
+        );
+        assert_eq!(
+            detect_format_from_header(b"\0\0\0\x1cftypavif\0\0\0\0avifmif1miaf"),
+            Some("avif")
+        );
+        assert_eq!(detect_format_from_header(b"hello world"), None);
+    }
+
+    #[test]
+    fn test_extension_matches_format() {
+        assert!(extension_matches_format(Path::new("a.JPG"), "jpeg"));
+        assert!(extension_matches_format(Path::new("a.heif"), "heic"));
+        assert!(!extension_matches_format(Path::new("a.jpg"), "heic"));
+        assert!(!extension_matches_format(Path::new("IMG_001"), "jpeg"));
+    }
+
+    #[test]
+    fn test_discover_by_directory() {
+        let dir = TempDir::new();
+        for name in ["a.jpg", "notes.txt", "sub/b.png", "sub/c.JPG"] {
+            dir.write(name, b"");
+        }
+        let root = dir.path().to_path_buf();
+
+        let mut dirs = Vec::new();
+        let source = root.to_string_lossy();
+        discover_image_files(&source, true, false, &AtomicBool::new(false), |dir, files| {
+            dirs.push((dir.to_path_buf(), files.len()));
+        });
+        assert_eq!(dirs, vec![(root.join("sub"), 2), (root.clone(), 1)]);
+
+        dirs.clear();
+        discover_image_files(&source, false, false, &AtomicBool::new(false), |dir, files| {
+            dirs.push((dir.to_path_buf(), files.len()));
+        });
+        assert_eq!(dirs, vec![(root.clone(), 1)]);
+    }
+}
@@ -0,0 +1,53 @@
+// 测试辅助模块
+// 在系统临时目录下为每个测试创建独立的目录，测试结束时删除
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 测试用的临时目录，离开作用域时删除
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        Self::new_in(&std::env::temp_dir())
+    }
+
+    /// 在指定目录下创建（用于需要另一个文件系统的测试）
+    pub fn new_in(parent: &Path) -> Self {
+        let path = parent.join(format!("tag2dir-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).expect("创建测试目录");
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 测试目录下的路径
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+
+    /// 写入文件（自动创建上级目录），返回完整路径
+    pub fn write(&self, name: &str, content: &[u8]) -> PathBuf {
+        let path = self.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("创建测试目录");
+        }
+        fs::write(&path, content).expect("写入测试文件");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// 读取文件内容；文件不存在时返回 None
+pub(crate) fn read(path: &Path) -> Option<Vec<u8>> {
+    fs::read(path).ok()
+}
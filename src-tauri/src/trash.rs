@@ -0,0 +1,182 @@
+// 回收站模块
+// 按 freedesktop.org 回收站规范把文件移到用户的回收站（写入 .trashinfo 记录），
+// 可以在文件管理器中查看和还原。
+// 与主目录同一设备的文件放入 $XDG_DATA_HOME/Trash，
+// 其他设备上的文件放入该设备挂载点下的 .Trash/$uid 或 .Trash-$uid
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::tr;
+
+/// 当前平台是否支持移到回收站
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+/// 把文件移到回收站，返回它在回收站中的路径
+#[cfg(target_os = "linux")]
+pub fn move_to_trash(path: &Path) -> io::Result<PathBuf> {
+    use std::fs;
+    use std::io::Write;
+    use std::os::unix::fs::MetadataExt;
+
+    let path = absolute(path)?;
+    let meta = fs::symlink_metadata(&path)?;
+    let (trash_dir, topdir) = trash_dir_for(&path, meta.dev())?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    create_private_dir(&files_dir)?;
+    create_private_dir(&info_dir)?;
+
+    // 回收站中的 Path 对挂载点下的回收站使用相对路径
+    let recorded = match &topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path),
+        None => &path,
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(recorded),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut counter = 0;
+    loop {
+        let candidate = trash_name(&name, counter);
+        counter += 1;
+        let trashed = files_dir.join(&candidate);
+        if trashed.symlink_metadata().is_ok() {
+            continue;
+        }
+        // 以独占方式创建 .trashinfo 占住名称，避免与其他程序同时写入冲突
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+        let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let moved = file
+            .write_all(info.as_bytes())
+            .and_then(|_| file.sync_all())
+            .and_then(|_| fs::rename(&path, &trashed));
+        if let Err(e) = moved {
+            drop(file);
+            let _ = fs::remove_file(&info_path);
+            return Err(e);
+        }
+        return Ok(trashed);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn move_to_trash(_path: &Path) -> io::Result<PathBuf> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, tr!("trash-unsupported")))
+}
+
+/// 选择回收站：与主目录回收站同一设备时用主目录回收站，
+/// 否则用挂载点下的回收站，同时返回挂载点
+#[cfg(target_os = "linux")]
+fn trash_dir_for(path: &Path, dev: u64) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    use std::fs;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, tr!("trash-no-data-dir")))?;
+    let home_trash = data_dir.join("Trash");
+    let home_dev = home_trash
+        .ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .map(|meta| meta.dev());
+    if home_dev == Some(dev) {
+        return Ok((home_trash, None));
+    }
+
+    let topdir = mount_point(path, dev);
+    // SAFETY: getuid 没有任何前置条件，总是成功
+    let uid = unsafe { libc::getuid() };
+    // 管理员创建的 .Trash 必须是设置了粘滞位的真实目录，否则不能使用
+    let admin = topdir.join(".Trash");
+    let admin_ok = fs::symlink_metadata(&admin)
+        .is_ok_and(|meta| meta.is_dir() && meta.permissions().mode() & 0o1000 != 0);
+    if admin_ok {
+        let dir = admin.join(uid.to_string());
+        if create_private_dir(&dir).is_ok() {
+            return Ok((dir, Some(topdir)));
+        }
+    }
+    Ok((topdir.join(format!(".Trash-{}", uid)), Some(topdir)))
+}
+
+/// 文件所在设备的挂载点：沿上级目录向上，直到设备号改变
+#[cfg(target_os = "linux")]
+fn mount_point(path: &Path, dev: u64) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut top = path.parent().unwrap_or(path);
+    for dir in path.ancestors().skip(1) {
+        match std::fs::metadata(dir) {
+            Ok(meta) if meta.dev() == dev => top = dir,
+            _ => break,
+        }
+    }
+    top.to_path_buf()
+}
+
+#[cfg(target_os = "linux")]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+}
+
+/// 补全为绝对路径（不解析符号链接，链接本身进回收站）
+#[cfg(target_os = "linux")]
+fn absolute(path: &Path) -> io::Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(path))
+    }
+}
+
+/// 回收站内的文件名：重名时在扩展名前加序号，如 a.jpg、a.2.jpg、a.3.jpg
+fn trash_name(name: &str, counter: usize) -> String {
+    if counter == 0 {
+        return name.to_string();
+    }
+    let n = counter + 1;
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}.{}.{}", stem, n, ext),
+        _ => format!("{}.{}", name, n),
+    }
+}
+
+/// 按 URL 规则转义路径（保留 "/" 和不需要转义的字符），规范要求 Path 以此格式记录
+fn encode_path(path: &Path) -> String {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trashinfo_names() {
+        assert_eq!(
+            encode_path(Path::new("/home/a/照片 1/IMG#1.jpg")),
+            "/home/a/%E7%85%A7%E7%89%87%201/IMG%231.jpg"
+        );
+        assert_eq!(trash_name("a.jpg", 0), "a.jpg");
+        assert_eq!(trash_name("a.jpg", 1), "a.2.jpg");
+        assert_eq!(trash_name(".hidden", 2), ".hidden.3");
+    }
+}
@@ -0,0 +1,695 @@
+// 监视文件夹模块
+// 监视源文件夹（如手机同步的 Inbox），等待新文件写入稳定后读取人物标签，
+// 按保存的规则自动移动到目标文件夹
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::file_ops;
+use crate::i18n::{tr, FluentValue};
+use crate::metadata;
+use crate::models::{
+    ConflictOutcome, ConflictPolicy, FileStamp, MoveImageRequest, MoveOptions, MoveReport,
+    NameRules, OperationLog, TransferMode,
+};
+use crate::scan_index;
+use crate::scanner;
+
+/// 监视配置文件名
+const CONFIG_FILE_NAME: &str = "watch_config.json";
+
+/// 事件循环的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 分类规则：照片包含该人物时移动到对应文件夹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SortRule {
+    /// 人物名称（与元数据中的人物标签完全匹配）
+    pub person: String,
+    /// 目标文件夹中的子文件夹名，为空时使用人物名称
+    #[serde(default)]
+    pub folder: String,
+}
+
+/// 监视模式配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchConfig {
+    /// 被监视的源文件夹
+    pub source_dir: String,
+    /// 分类目标文件夹
+    pub target_dir: String,
+    /// 是否同时监视子文件夹
+    #[serde(default)]
+    pub include_subdirs: bool,
+    /// 文件最后一次变化后等待多少秒才处理（等待同步写入完成）
+    #[serde(default = "default_settle_secs")]
+    pub settle_secs: u64,
+    /// 只报告将要执行的移动，不实际移动
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 传输方式（默认移动）；非移动模式下启动时不处理文件夹中已有的文件
+    #[serde(default)]
+    pub mode: TransferMode,
+    /// 目标路径模板（为空时按 "{person}/{filename}" 放置），规则的文件夹名作为 {person}
+    #[serde(default)]
+    pub path_template: Option<String>,
+    /// 目标已有同名文件时的处理策略
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// 按文件头识别图片（处理无扩展名或扩展名错误的文件）
+    #[serde(default)]
+    pub detect_by_content: bool,
+    /// 分类规则，按顺序匹配第一条
+    pub rules: Vec<SortRule>,
+}
+
+fn default_settle_secs() -> u64 {
+    5
+}
+
+/// 自动分类动作（通过事件推送给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchAction {
+    /// 源文件路径
+    pub path: String,
+    /// 匹配到的人物（未匹配任何规则时为空）
+    pub person: Option<String>,
+    /// 目标路径（未匹配或失败时为空）
+    pub destination: Option<String>,
+    /// 是否为演练（未实际移动）
+    pub dry_run: bool,
+    /// 错误信息
+    pub error: Option<String>,
+    /// 同名冲突的处理结果
+    pub conflict: Option<ConflictOutcome>,
+}
+
+/// 监视状态
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchStatus {
+    pub running: bool,
+    pub paused: bool,
+    /// 已处理（移动或演练）的文件数
+    pub processed_count: usize,
+    pub config: Option<WatchConfig>,
+}
+
+/// 正在运行的监视任务
+pub struct WatchHandle {
+    config: WatchConfig,
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    processed: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// 等待稳定的文件
+struct PendingFile {
+    /// 最近一次收到事件的时间
+    last_event: Instant,
+    /// 最近一次记录的文件大小
+    last_size: Option<u64>,
+}
+
+/// 从配置目录读取保存的监视配置
+pub fn load_config(config_dir: &Path) -> Option<WatchConfig> {
+    let data = fs::read(config_dir.join(CONFIG_FILE_NAME)).ok()?;
+    serde_json::from_slice(&data)
+        .map_err(|e| log::warn!("{}", tr!("log-watch-config-corrupt", detail = e.to_string())))
+        .ok()
+}
+
+/// 保存监视配置
+pub fn save_config(config_dir: &Path, config: &WatchConfig) -> AppResult<()> {
+    let path = config_dir.join(CONFIG_FILE_NAME);
+    let data =
+        serde_json::to_vec_pretty(config).map_err(|e| AppError::internal("watch-save-config", e))?;
+    fs::create_dir_all(config_dir)
+        .and_then(|_| fs::write(&path, data))
+        .map_err(|e| AppError::io("watch-save-config", &e).with_path(&path))
+}
+
+/// 检查配置是否可用
+pub fn validate_config(config: &WatchConfig) -> AppResult<()> {
+    let source = Path::new(&config.source_dir);
+    let target = Path::new(&config.target_dir);
+    if !source.is_dir() {
+        let args = [("path", FluentValue::from(config.source_dir.as_str()))];
+        return Err(
+            AppError::with_args(ErrorKind::NotFound, "watch-source-missing", &args).with_path(source),
+        );
+    }
+    if config.target_dir.is_empty() {
+        return Err(AppError::new(ErrorKind::InvalidInput, "watch-target-unset"));
+    }
+    // 目标在源文件夹内部时，移动过去的文件会再次触发监视
+    if config.include_subdirs && target.starts_with(source) {
+        return Err(AppError::new(ErrorKind::InvalidInput, "watch-target-inside-source"));
+    }
+    if config.rules.is_empty() {
+        return Err(AppError::new(ErrorKind::InvalidInput, "watch-no-rules"));
+    }
+    file_ops::parse_template(config.path_template.as_deref())?;
+    Ok(())
+}
+
+/// 启动监视任务
+/// `on_action` 在每个文件处理后调用；实际移动时同时传入操作日志，便于记录到撤销历史。
+/// 指定 `journal_dir` 时移动过程写入预写日志
+pub fn start<F>(config: WatchConfig, journal_dir: Option<PathBuf>, on_action: F) -> AppResult<WatchHandle>
+where
+    F: Fn(WatchAction, Option<OperationLog>) + Send + 'static,
+{
+    validate_config(&config)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| AppError::internal("watch-create-failed", e))?;
+    let mode = if config.include_subdirs {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(Path::new(&config.source_dir), mode)
+        .map_err(|e| AppError::internal("watch-folder-failed", e).with_path(&config.source_dir))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let paused = Arc::new(AtomicBool::new(false));
+    let processed = Arc::new(AtomicUsize::new(0));
+
+    let thread = {
+        let config = config.clone();
+        let stop = stop.clone();
+        let paused = paused.clone();
+        let processed = processed.clone();
+        std::thread::Builder::new()
+            .name("tag2dir-watch".to_string())
+            .spawn(move || {
+                // watcher 需要在线程存活期间保持，离开作用域即停止监视
+                let _watcher = watcher;
+                run_loop(&config, journal_dir.as_deref(), rx, &stop, &paused, &processed, &on_action);
+            })
+            .map_err(|e| AppError::io("watch-thread-failed", &e))?
+    };
+
+    log::info!("{}", tr!("log-watch-started", path = config.source_dir.as_str()));
+    Ok(WatchHandle {
+        config,
+        stop,
+        paused,
+        processed,
+        thread: Some(thread),
+    })
+}
+
+/// 事件循环：收集变化的文件，等待稳定后处理
+fn run_loop<F>(
+    config: &WatchConfig,
+    journal_dir: Option<&Path>,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    stop: &AtomicBool,
+    paused: &AtomicBool,
+    processed: &AtomicUsize,
+    on_action: &F,
+) where
+    F: Fn(WatchAction, Option<OperationLog>),
+{
+    let settle = Duration::from_secs(config.settle_secs);
+    let mut pending: HashMap<PathBuf, PendingFile> = HashMap::new();
+    let mut handled = HandledFiles::default();
+
+    // 启动时先处理文件夹中已有的图片。只在移动模式下这样做：复制、硬链接和符号链接
+    // 模式会保留源文件，每次启动都会把已分类过的文件再放一遍
+    if config.mode == TransferMode::Move {
+        let now = Instant::now();
+        for path in scanner::scan_image_files(
+            &config.source_dir,
+            config.include_subdirs,
+            config.detect_by_content,
+        ) {
+            queue_file(&mut pending, path, now);
+        }
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => match event.kind {
+                kind if is_content_change(kind) => {
+                    let now = Instant::now();
+                    for path in event.paths {
+                        queue_file(&mut pending, path, now);
+                    }
+                }
+                EventKind::Remove(_) => {
+                    for path in &event.paths {
+                        pending.remove(path);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Err(e)) => log::warn!("{}", tr!("log-watch-error", detail = e.to_string())),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        // 暂停期间继续收集事件，恢复后再处理
+        if paused.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let ready = take_settled(&mut pending, Instant::now(), settle);
+
+        for path in ready {
+            if !scanner::is_image_file(&path, config.detect_by_content) {
+                continue;
+            }
+            // 非移动模式下源文件留在原处，处理过且内容未变的文件不再处理
+            if config.mode != TransferMode::Move && handled.contains(&path) {
+                continue;
+            }
+            let (action, operation) = process_file(config, journal_dir, &path);
+            if action.error.is_none() {
+                handled.insert(&path);
+            }
+            if action.destination.is_some() {
+                processed.fetch_add(1, Ordering::Relaxed);
+            }
+            on_action(action, operation);
+        }
+    }
+
+    log::info!("{}", tr!("log-watch-stopped", path = config.source_dir.as_str()));
+}
+
+/// 事件是否表示文件出现或内容变化。
+/// 只改动元数据的事件（如创建硬链接改变了源文件的链接数）不处理，否则硬链接模式会反复触发自己
+fn is_content_change(kind: EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_)
+            | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+    )
+}
+
+/// 已处理过的文件及处理时的指纹
+#[derive(Default)]
+struct HandledFiles {
+    stamps: HashMap<PathBuf, Option<FileStamp>>,
+}
+
+impl HandledFiles {
+    fn insert(&mut self, path: &Path) {
+        self.stamps.insert(path.to_path_buf(), scan_index::file_stamp(path));
+    }
+
+    /// 文件处理过，且之后大小和修改时间都没有变化
+    fn contains(&self, path: &Path) -> bool {
+        self.stamps
+            .get(path)
+            .is_some_and(|stamp| *stamp == scan_index::file_stamp(path))
+    }
+}
+
+/// 记录文件发生了变化，重新开始等待稳定
+fn queue_file(pending: &mut HashMap<PathBuf, PendingFile>, path: PathBuf, now: Instant) {
+    let last_size = fs::metadata(&path).ok().map(|m| m.len());
+    pending.insert(
+        path,
+        PendingFile {
+            last_event: now,
+            last_size,
+        },
+    );
+}
+
+/// 取出已稳定的文件：最后一次变化后已等待 `settle`，且等待期间大小没有变化。
+/// 已消失或不再是普通文件的记录直接丢弃
+fn take_settled(
+    pending: &mut HashMap<PathBuf, PendingFile>,
+    now: Instant,
+    settle: Duration,
+) -> Vec<PathBuf> {
+    let mut ready = Vec::new();
+    pending.retain(|path, file| {
+        let meta = match fs::metadata(path) {
+            Ok(meta) if meta.is_file() => meta,
+            _ => return false,
+        };
+        if now.duration_since(file.last_event) < settle {
+            return true;
+        }
+        // 等待期间大小仍在变化，说明还在写入，重新计时
+        if file.last_size != Some(meta.len()) {
+            file.last_size = Some(meta.len());
+            file.last_event = now;
+            return true;
+        }
+        ready.push(path.clone());
+        false
+    });
+    ready
+}
+
+/// 按顺序找到第一条匹配照片中人物的规则
+fn match_rule<'a>(rules: &'a [SortRule], persons: &[String]) -> Option<&'a SortRule> {
+    rules.iter().find(|rule| persons.iter().any(|p| p == &rule.person))
+}
+
+/// 处理一个已稳定的文件：读取人物标签、匹配规则、移动（或演练）
+fn process_file(config: &WatchConfig, journal_dir: Option<&Path>, path: &Path) -> (WatchAction, Option<OperationLog>) {
+    let path_str = path.to_string_lossy().to_string();
+    let persons = metadata::extract_metadata(path).persons;
+
+    let rule = match match_rule(&config.rules, &persons) {
+        Some(rule) => rule,
+        None => {
+            log::info!("{}", tr!("log-watch-no-rule", path = path_str.as_str()));
+            return (
+                WatchAction {
+                    path: path_str,
+                    person: None,
+                    destination: None,
+                    dry_run: config.dry_run,
+                    error: None,
+                    conflict: None,
+                },
+                None,
+            );
+        }
+    };
+
+    let folder = if rule.folder.is_empty() {
+        rule.person.clone()
+    } else {
+        rule.folder.clone()
+    };
+    let filename = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    if config.dry_run {
+        // 模板在启动时已校验过，这里解析失败时按默认布局演练
+        let template = file_ops::parse_template(config.path_template.as_deref()).unwrap_or(None);
+        let destination = file_ops::destination_path(
+            template.as_ref(),
+            Path::new(&config.target_dir),
+            path,
+            &folder,
+            &NameRules::default(),
+        );
+        log::info!(
+            "{}",
+            tr!(
+                "log-watch-dry-run",
+                path = path_str.as_str(),
+                destination = destination.display().to_string(),
+            )
+        );
+        return (
+            WatchAction {
+                path: path_str,
+                person: Some(rule.person.clone()),
+                destination: Some(destination.to_string_lossy().to_string()),
+                dry_run: true,
+                error: None,
+                conflict: None,
+            },
+            None,
+        );
+    }
+
+    let items = [MoveImageRequest {
+        path: path_str.clone(),
+        filename,
+        person: folder,
+        persons: Vec::new(),
+    }];
+    let options = MoveOptions {
+        mode: config.mode,
+        path_template: config.path_template.clone(),
+        conflict_policy: config.conflict_policy,
+        ..Default::default()
+    };
+    match file_ops::move_images(&items, &config.target_dir, &options, journal_dir) {
+        Ok(MoveReport { log, failures, .. }) => match log.records.first() {
+            Some(record) => {
+                log::info!(
+                    "{}",
+                    tr!(
+                        "log-watch-moved",
+                        path = path_str.as_str(),
+                        destination = record.new_path.as_str(),
+                    )
+                );
+                let destination = record.new_path.clone();
+                (
+                    WatchAction {
+                        path: path_str,
+                        person: Some(rule.person.clone()),
+                        destination: Some(destination),
+                        dry_run: false,
+                        error: None,
+                        conflict: record.conflict,
+                    },
+                    Some(log),
+                )
+            }
+            None => (
+                WatchAction {
+                    path: path_str,
+                    person: Some(rule.person.clone()),
+                    destination: None,
+                    dry_run: false,
+                    error: Some(match failures.first() {
+                        Some(f) => tr!("watch-move-failed", detail = f.message.as_str()),
+                        None => tr!("watch-move-failed-unknown"),
+                    }),
+                    conflict: None,
+                },
+                None,
+            ),
+        },
+        Err(e) => (
+            WatchAction {
+                path: path_str,
+                person: Some(rule.person.clone()),
+                destination: None,
+                dry_run: false,
+                error: Some(e.to_string()),
+                conflict: None,
+            },
+            None,
+        ),
+    }
+}
+
+impl WatchHandle {
+    /// 暂停自动分类（继续收集文件变化）
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// 恢复自动分类
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// 当前状态
+    pub fn status(&self) -> WatchStatus {
+        WatchStatus {
+            running: true,
+            paused: self.paused.load(Ordering::Relaxed),
+            processed_count: self.processed.load(Ordering::Relaxed),
+            config: Some(self.config.clone()),
+        }
+    }
+
+    /// 停止监视并等待线程退出
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn config(source: &Path, target: &Path) -> WatchConfig {
+        WatchConfig {
+            source_dir: source.to_string_lossy().to_string(),
+            target_dir: target.to_string_lossy().to_string(),
+            include_subdirs: false,
+            settle_secs: default_settle_secs(),
+            dry_run: false,
+            mode: TransferMode::Move,
+            path_template: None,
+            conflict_policy: ConflictPolicy::default(),
+            detect_by_content: false,
+            rules: vec![SortRule {
+                person: "Alice".to_string(),
+                folder: String::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let dir = TempDir::new();
+        let source = dir.join("inbox");
+        let target = dir.join("sorted");
+        let key = |config: &WatchConfig| validate_config(config).err().map(|e| e.key);
+
+        assert_eq!(key(&config(&source, &target)), Some("watch-source-missing"));
+        fs::create_dir(&source).unwrap();
+        assert_eq!(key(&config(&source, &target)), None);
+
+        assert_eq!(key(&config(&source, Path::new(""))), Some("watch-target-unset"));
+
+        // 目标在源文件夹内部只在监视子文件夹时才会被再次触发
+        let mut inside = config(&source, &source.join("sorted"));
+        assert_eq!(key(&inside), None);
+        inside.include_subdirs = true;
+        assert_eq!(key(&inside), Some("watch-target-inside-source"));
+
+        let mut no_rules = config(&source, &target);
+        no_rules.rules.clear();
+        assert_eq!(key(&no_rules), Some("watch-no-rules"));
+
+        let mut bad_template = config(&source, &target);
+        bad_template.path_template = Some("{unknown}/{filename}".to_string());
+        assert!(key(&bad_template).is_some_and(|k| k.starts_with("template-")));
+    }
+
+    #[test]
+    fn test_match_rule() {
+        let rules = vec![
+            SortRule {
+                person: "Alice".to_string(),
+                folder: "Family".to_string(),
+            },
+            SortRule {
+                person: "Bob".to_string(),
+                folder: String::new(),
+            },
+        ];
+        let persons = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        // 按规则顺序匹配，而不是按照片中人物的顺序
+        let rule = match_rule(&rules, &persons(&["Bob", "Alice"])).unwrap();
+        assert_eq!(rule.folder, "Family");
+        assert_eq!(match_rule(&rules, &persons(&["Bob"])).unwrap().person, "Bob");
+        // 人物名称完全匹配
+        assert!(match_rule(&rules, &persons(&["alice", "Bobby"])).is_none());
+        assert!(match_rule(&rules, &[]).is_none());
+    }
+
+    /// 带 XMP 关键字 "Alice" 的 JPEG
+    fn tagged_jpeg() -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        data.extend_from_slice(
+            br#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+            <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/">
+            <dc:subject><rdf:Bag><rdf:li>Alice</rdf:li></rdf:Bag></dc:subject>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#,
+        );
+        data
+    }
+
+    #[test]
+    fn test_process_file_hardlink_twice() {
+        let dir = TempDir::new();
+        let source = dir.write("inbox/a.jpg", &tagged_jpeg());
+        let target = dir.join("sorted");
+        let mut config = config(&dir.join("inbox"), &target);
+        config.mode = TransferMode::Hardlink;
+
+        let (action, operation) = process_file(&config, None, &source);
+        assert_eq!(action.error, None);
+        assert_eq!(action.person.as_deref(), Some("Alice"));
+        assert_eq!(operation.unwrap().records.len(), 1);
+        let linked = target.join("Alice/a.jpg");
+        assert_eq!(action.destination, Some(linked.to_string_lossy().to_string()));
+
+        // 再处理一次：目标已是同一个文件，不会再改名建一个链接
+        let (action, _) = process_file(&config, None, &source);
+        assert_eq!(action.error, None);
+        assert_eq!(action.conflict, Some(ConflictOutcome::Skipped));
+        assert_eq!(fs::read_dir(target.join("Alice")).unwrap().count(), 1);
+        assert!(source.exists());
+    }
+
+    #[test]
+    fn test_handled_files() {
+        let dir = TempDir::new();
+        let photo = dir.write("a.jpg", b"12");
+        let mut handled = HandledFiles::default();
+        assert!(!handled.contains(&photo));
+        handled.insert(&photo);
+        assert!(handled.contains(&photo));
+        // 内容变化后重新处理
+        fs::write(&photo, b"1234").unwrap();
+        assert!(!handled.contains(&photo));
+    }
+
+    #[test]
+    fn test_is_content_change() {
+        use notify::event::{CreateKind, DataChange, MetadataKind, RemoveKind, RenameMode};
+        assert!(is_content_change(EventKind::Create(CreateKind::File)));
+        assert!(is_content_change(EventKind::Modify(ModifyKind::Data(DataChange::Any))));
+        assert!(is_content_change(EventKind::Modify(ModifyKind::Name(RenameMode::To))));
+        // 创建硬链接只改变源文件的链接数
+        assert!(!is_content_change(EventKind::Modify(ModifyKind::Metadata(MetadataKind::Any))));
+        assert!(!is_content_change(EventKind::Remove(RemoveKind::File)));
+    }
+
+    #[test]
+    fn test_take_settled() {
+        let dir = TempDir::new();
+        let photo = dir.write("a.jpg", b"12");
+        let settle = Duration::from_secs(5);
+        let start = Instant::now();
+        let mut pending = HashMap::new();
+        queue_file(&mut pending, photo.clone(), start);
+
+        // 等待时间不够
+        assert!(take_settled(&mut pending, start + Duration::from_secs(1), settle).is_empty());
+        assert!(pending.contains_key(&photo));
+
+        // 等待期间大小变化，重新计时
+        fs::write(&photo, b"1234").unwrap();
+        let later = start + settle;
+        assert!(take_settled(&mut pending, later, settle).is_empty());
+        assert!(take_settled(&mut pending, later + Duration::from_secs(1), settle).is_empty());
+
+        // 大小稳定后取出，并从等待列表中移除
+        let ready = take_settled(&mut pending, later + settle, settle);
+        assert_eq!(ready, [photo.as_path()]);
+        assert!(pending.is_empty());
+
+        // 已删除的文件和目录直接丢弃
+        queue_file(&mut pending, photo.clone(), start);
+        queue_file(&mut pending, dir.path().to_path_buf(), start);
+        fs::remove_file(&photo).unwrap();
+        assert!(take_settled(&mut pending, start, settle).is_empty());
+        assert!(pending.is_empty());
+    }
+}
@@ -5,17 +5,32 @@ import { open } from "@tauri-apps/plugin-dialog";
 import type {
   ImageInfo,
   ScanProgressEvent,
+  ScanBatchEvent,
+  ScanDiscoveryEvent,
+  ScanRemovedEvent,
+  ScanFailure,
   MoveProgressEvent,
   ScanStats,
   MoveImageRequest,
   MoveResult,
   UndoResult,
+  HistorySummary,
+  InterruptedOperation,
+  AppError,
 } from "./types";
 import { ControlBar } from "./components/ControlBar";
 import { ImageGrid } from "./components/ImageGrid";
 import { MovePreviewPanel } from "./components/MovePreviewPanel";
 import { StatusBar } from "./components/StatusBar";
 
+/** 命令错误的显示文本（命令返回 AppError，其他异常直接转为字符串） */
+function errorText(e: unknown): string {
+  if (typeof e === "object" && e !== null && "message" in e) {
+    return (e as AppError).message;
+  }
+  return String(e);
+}
+
 function App() {
   // 文件夹路径
   const [sourceDir, setSourceDir] = useState("");
@@ -25,6 +40,10 @@ function App() {
 
   // 图片数据
   const [images, setImages] = useState<ImageInfo[]>([]);
+  // 本次扫描中读取失败的文件
+  const [scanFailures, setScanFailures] = useState<ScanFailure[]>([]);
+  // 失败数也记在 ref 中，扫描完成事件里要用到最新值
+  const scanFailedRef = useRef(0);
   const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
 
   // 状态
@@ -37,26 +56,103 @@ function App() {
   const [statusMessage, setStatusMessage] = useState("就绪");
   const [previewOpen, setPreviewOpen] = useState(false);
 
+  // 当前扫描/移动任务的 ID（从进度事件中获得），用于按 ID 取消
+  const scanJobRef = useRef<string | null>(null);
+  const moveJobRef = useRef<string | null>(null);
+
   // 虚拟列表引用
   const gridRef = useRef<{
     scrollToTop: () => void;
     scrollToBottom: () => void;
   } | null>(null);
 
+  // 启动时先处理上次中断的移动操作，再读取操作历史，重启后仍可撤销之前的操作
+  useEffect(() => {
+    const recover = async () => {
+      const interrupted = await invoke<InterruptedOperation[]>(
+        "list_interrupted"
+      );
+      for (const op of interrupted) {
+        // 所有文件都已处理完，只差写入历史，直接补完
+        const resume =
+          op.finished ||
+          window.confirm(
+            `上次移动到 ${op.target_dir} 的操作（${op.timestamp}）意外中断，已完成 ${op.completed}/${op.total} 个文件。\n\n确定：继续完成剩余文件\n取消：回滚，把已移动的文件放回原位`
+          );
+        try {
+          if (resume) {
+            const result = await invoke<MoveResult>("resume_interrupted", {
+              operationId: op.id,
+            });
+            setStatusMessage(`已恢复中断的操作，共移动 ${result.moved_count} 个文件`);
+          } else {
+            const result = await invoke<UndoResult>("rollback_interrupted", {
+              operationId: op.id,
+            });
+            setStatusMessage(`已回滚中断的操作，恢复 ${result.restored_count} 个文件`);
+          }
+        } catch (e) {
+          setStatusMessage(`恢复中断的操作失败: ${errorText(e)}`);
+        }
+      }
+      const history = await invoke<HistorySummary[]>("list_history");
+      setHasUndo(history.some((entry) => !entry.undone));
+    };
+    recover().catch(() => {});
+  }, []);
+
   // 监听扫描进度事件
   useEffect(() => {
     const unlisten = listen<ScanProgressEvent>("scan-progress", (event) => {
       const data = event.payload;
-      if (data.image) {
-        setImages((prev) => [...prev, data.image!]);
-      }
+      scanJobRef.current = data.done ? null : data.job_id;
       if (data.done) {
         setScanning(false);
-        if (data.cancelled) {
-          setStatusMessage(`扫描已取消，已加载 ${data.scanned} 张图片`);
-        } else {
-          setStatusMessage(`扫描完成，共 ${data.scanned} 张图片`);
-        }
+        const summary = data.cancelled
+          ? `扫描已取消，已加载 ${data.scanned} 张图片`
+          : `扫描完成，共 ${data.scanned} 张图片`;
+        const failed = scanFailedRef.current;
+        setStatusMessage(
+          failed > 0 ? `${summary}，${failed} 个文件读取失败` : summary
+        );
+      } else {
+        const eta =
+          data.eta_ms === null ? "" : `，预计剩余 ${Math.ceil(data.eta_ms / 1000)} 秒`;
+        const total = data.discovering ? `已找到 ${data.total}` : `${data.total}`;
+        setStatusMessage(`正在扫描 ${data.scanned}/${total}${eta}`);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 监听查找图片阶段的进度
+  useEffect(() => {
+    const unlisten = listen<ScanDiscoveryEvent>("scan-discovery", (event) => {
+      const data = event.payload;
+      scanJobRef.current = data.job_id;
+      if (!data.done) {
+        setStatusMessage(
+          `正在查找图片：已查找 ${data.dirs_scanned} 个文件夹，找到 ${data.found} 张图片`
+        );
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // 监听扫描结果（分批推送）
+  useEffect(() => {
+    const unlisten = listen<ScanBatchEvent>("scan-batch", (event) => {
+      const { images: batch, failures } = event.payload;
+      if (batch.length > 0) {
+        setImages((prev) => [...prev, ...batch]);
+      }
+      if (failures.length > 0) {
+        scanFailedRef.current += failures.length;
+        setScanFailures((prev) => [...prev, ...failures]);
       }
     });
     return () => {
@@ -64,10 +160,22 @@ function App() {
     };
   }, []);
 
+  // 监听增量扫描发现的已删除文件
+  useEffect(() => {
+    const unlisten = listen<ScanRemovedEvent>("scan-removed", (event) => {
+      const removed = new Set(event.payload.paths);
+      setImages((prev) => prev.filter((img) => !removed.has(img.path)));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // 监听移动进度事件
   useEffect(() => {
     const unlisten = listen<MoveProgressEvent>("move-progress", (event) => {
       setMoveProgress(event.payload);
+      moveJobRef.current = event.payload.done ? null : event.payload.job_id;
       if (event.payload.done) {
         setMoving(false);
       }
@@ -109,17 +217,19 @@ function App() {
     }
     setScanning(true);
     setImages([]);
+    setScanFailures([]);
+    scanFailedRef.current = 0;
     setSelectedIds(new Set());
-    setHasUndo(false);
     setStatusMessage("正在扫描...");
 
     try {
       await invoke<ScanStats>("scan_images", {
         sourceDir,
         includeSubdirs,
+        incremental: true,
       });
     } catch (e) {
-      setStatusMessage(`扫描失败: ${e}`);
+      setStatusMessage(`扫描失败: ${errorText(e)}`);
       setScanning(false);
     }
   }, [sourceDir, includeSubdirs]);
@@ -127,10 +237,11 @@ function App() {
   // 取消扫描
   const cancelScan = useCallback(async () => {
     try {
-      await invoke("cancel_scan");
+      const jobId = scanJobRef.current;
+      await (jobId ? invoke("cancel_job", { jobId }) : invoke("cancel_scan"));
       setStatusMessage("正在取消扫描...");
     } catch (e) {
-      setStatusMessage(`取消失败: ${e}`);
+      setStatusMessage(`取消失败: ${errorText(e)}`);
     }
   }, []);
 
@@ -196,6 +307,7 @@ function App() {
         path: img.path,
         filename: img.filename,
         person: img.selected_person ?? "",
+        persons: img.persons,
       }));
 
       const result = await invoke<MoveResult>("move_images", {
@@ -204,10 +316,17 @@ function App() {
       });
 
       setHasUndo(result.has_undo);
-      setStatusMessage(`移动完成，成功移动 ${result.moved_count} 个文件`);
+      const summary = result.cancelled
+        ? `移动已取消，已移动 ${result.moved_count} 个文件`
+        : `移动完成，成功移动 ${result.moved_count} 个文件`;
+      setStatusMessage(
+        result.failures.length > 0
+          ? `${summary}，${result.failures.length} 个失败: ${result.failures[0].message}`
+          : summary
+      );
 
-      // 更新被移动图片的状态
-      const movedPaths = new Set(toMove.map((img) => img.path));
+      // 只更新实际传输了的图片的状态
+      const movedPaths = new Set(result.moved);
       setImages((prev) =>
         prev.map((img) =>
           movedPaths.has(img.path)
@@ -217,30 +336,50 @@ function App() {
       );
       setSelectedIds(new Set());
     } catch (e) {
-      setStatusMessage(`移动失败: ${e}`);
+      setStatusMessage(`移动失败: ${errorText(e)}`);
     } finally {
       setMoving(false);
     }
   }, [images, selectedIds, targetDir]);
 
+  // 取消移动：当前文件处理完后停止，已移动的部分仍可撤销
+  const cancelMove = useCallback(async () => {
+    try {
+      const jobId = moveJobRef.current;
+      await (jobId ? invoke("cancel_job", { jobId }) : invoke("cancel_move"));
+      setStatusMessage("正在取消移动...");
+    } catch (e) {
+      console.error("取消移动失败:", errorText(e));
+    }
+  }, []);
+
   // 撤销移动
   const undoLastMove = useCallback(async () => {
     setStatusMessage("正在撤销...");
     try {
       const result = await invoke<UndoResult>("undo_move");
-      if (result.success) {
-        setStatusMessage(`撤销成功，恢复了 ${result.restored_count} 个文件`);
-        setHasUndo(false);
-        setImages((prev) =>
-          prev.map((img) =>
-            img.status === "Moved"
-              ? { ...img, status: "Scanned" as const }
-              : img
-          )
-        );
-      }
+      const skipped = result.files.length - result.restored_count;
+      setStatusMessage(
+        result.success
+          ? `撤销成功，恢复了 ${result.restored_count} 个文件`
+          : `撤销完成，恢复了 ${result.restored_count} 个文件，${skipped} 个文件已被修改、原位置被占用或已不存在，未恢复`
+      );
+      setHasUndo(result.has_undo);
+      // 只有真正恢复的文件回到未移动状态
+      const restored = new Set(
+        result.files
+          .filter((f) => f.outcome === "restored")
+          .map((f) => f.original_path)
+      );
+      setImages((prev) =>
+        prev.map((img) =>
+          img.status === "Moved" && restored.has(img.path)
+            ? { ...img, status: "Scanned" as const }
+            : img
+        )
+      );
     } catch (e) {
-      setStatusMessage(`撤销失败: ${e}`);
+      setStatusMessage(`撤销失败: ${errorText(e)}`);
     }
   }, []);
 
@@ -355,6 +494,19 @@ function App() {
             >
               正在移动素材 {moveProgress.moved_count} / {moveProgress.total}
             </span>
+            {moveProgress.current_file && (
+              <span
+                className="text-xs truncate"
+                style={{ color: "var(--text-tertiary)", maxWidth: 240 }}
+              >
+                {moveProgress.current_file}
+                {moveProgress.bytes_total > 0 &&
+                  moveProgress.bytes_done > 0 &&
+                  ` (${Math.round(
+                    (moveProgress.bytes_done / moveProgress.bytes_total) * 100
+                  )}%)`}
+              </span>
+            )}
             <div
               className="flex-1 overflow-hidden"
               style={{
@@ -376,6 +528,13 @@ function App() {
                 }}
               />
             </div>
+            <button
+              className="text-xs whitespace-nowrap"
+              style={{ color: "var(--text-secondary)" }}
+              onClick={cancelMove}
+            >
+              取消
+            </button>
           </div>
         </div>
       )}
@@ -396,6 +555,7 @@ function App() {
         personCount={personBuckets.size}
         selectedCount={selectedIds.size}
         statusMessage={statusMessage}
+        scanFailures={scanFailures}
         onScrollToTop={() => gridRef.current?.scrollToTop()}
         onScrollToBottom={() => gridRef.current?.scrollToBottom()}
       />
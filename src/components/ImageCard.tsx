@@ -20,6 +20,7 @@ export const ImageCard = memo(function ImageCard({
 }: ImageCardProps) {
   const statusInfo = getStatusInfo(image.status);
   const isMoved = image.status === "Moved";
+  const companionNames = (image.companions ?? []).map((path) => path.split(/[\\/]/).pop() ?? path);
 
   return (
     <div
@@ -105,14 +106,32 @@ export const ImageCard = memo(function ImageCard({
 
       {/* 卡片信息区 */}
       <div className="flex-1 flex flex-col" style={{ padding: "10px 12px 12px" }}>
-        {/* 文件名 */}
-        <p
-          className="truncate font-medium leading-tight"
-          title={image.filename}
-          style={{ fontSize: 11, color: "var(--text-primary)", marginBottom: 8 }}
-        >
-          {image.filename}
-        </p>
+        {/* 文件名（有伴随文件时显示数量，悬停查看文件名） */}
+        <div className="flex items-center gap-1.5" style={{ marginBottom: 8 }}>
+          <p
+            className="truncate font-medium leading-tight"
+            title={image.filename}
+            style={{ fontSize: 11, color: "var(--text-primary)" }}
+          >
+            {image.filename}
+          </p>
+          {companionNames.length > 0 && (
+            <span
+              title={`一起移动: ${companionNames.join(", ")}`}
+              style={{
+                flexShrink: 0,
+                fontSize: 9,
+                padding: "1px 5px",
+                borderRadius: 3,
+                background: "var(--accent-bg)",
+                color: "var(--accent-text)",
+                lineHeight: "11px",
+              }}
+            >
+              +{companionNames.length}
+            </span>
+          )}
+        </div>
 
         {/* 关键字标签 (固定高度，防止卡片跳变) */}
         <div className="flex flex-wrap items-center gap-1.5" style={{ marginBottom: 10, minHeight: 18 }}>
@@ -2,12 +2,18 @@
 // 显示统计信息和导航按钮
 
 import type { ReactNode } from "react";
+import type { ScanFailure } from "../types";
+
+/** 悬停提示中最多列出的失败文件数 */
+const MAX_LISTED_FAILURES = 20;
 
 interface StatusBarProps {
   totalImages: number;
   personCount: number;
   selectedCount: number;
   statusMessage: string;
+  /** 扫描中读取失败的文件 */
+  scanFailures: ScanFailure[];
   onScrollToTop: () => void;
   onScrollToBottom: () => void;
 }
@@ -17,9 +23,18 @@ export function StatusBar({
   personCount,
   selectedCount,
   statusMessage,
+  scanFailures,
   onScrollToTop,
   onScrollToBottom,
 }: StatusBarProps) {
+  const failureList = scanFailures
+    .slice(0, MAX_LISTED_FAILURES)
+    .map((f) => `${f.path}\n  ${f.error}`)
+    .join("\n");
+  const moreFailures = scanFailures.length - MAX_LISTED_FAILURES;
+  const failureTitle =
+    moreFailures > 0 ? `${failureList}\n……还有 ${moreFailures} 个` : failureList;
+
   return (
     <div
       className="flex items-center justify-between shrink-0"
@@ -61,6 +76,12 @@ export function StatusBar({
         <StatItem label="图片" value={totalImages} />
         <div style={{ width: 1, height: 20, background: "var(--stroke-divider)", margin: "0 8px" }} />
         <StatItem label="人物" value={personCount} />
+        {scanFailures.length > 0 && (
+          <>
+            <div style={{ width: 1, height: 20, background: "var(--stroke-divider)", margin: "0 8px" }} />
+            <StatItem label="读取失败" value={scanFailures.length} danger title={failureTitle} />
+          </>
+        )}
         {selectedCount > 0 && (
           <>
             <div style={{ width: 1, height: 20, background: "var(--stroke-divider)", margin: "0 8px" }} />
@@ -108,19 +129,27 @@ function StatItem({
   label,
   value,
   accent,
+  danger,
+  title,
 }: {
   label: string;
   value: number;
   accent?: boolean;
+  danger?: boolean;
+  title?: string;
 }) {
   return (
-    <span className="flex items-center gap-1" style={{ fontSize: 12 }}>
+    <span className="flex items-center gap-1" style={{ fontSize: 12 }} title={title}>
       <span style={{ color: "var(--text-tertiary)" }}>{label}:</span>
       <span
         style={{
           fontWeight: 600,
           fontVariantNumeric: "tabular-nums",
-          color: accent ? "var(--accent-text)" : "var(--text-secondary)",
+          color: danger
+            ? "var(--danger)"
+            : accent
+              ? "var(--accent-text)"
+              : "var(--text-secondary)",
         }}
       >
         {value}
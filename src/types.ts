@@ -6,9 +6,22 @@ export interface ImageInfo {
   filename: string;
   persons: string[];
   keywords: string[];
+  regions: FaceRegion[];
   thumbnail: string;
   selected_person: string | null;
   status: ImageStatus;
+  detected_format: string | null;
+  extension_mismatch: boolean;
+  /** 随图片一起移动的伴随文件（RAW、XMP、Live Photo 视频等） */
+  companions?: string[];
+}
+
+export interface FaceRegion {
+  name: string;
+  x: number;
+  y: number;
+  w: number;
+  h: number;
 }
 
 export type ImageStatus =
@@ -18,19 +31,67 @@ export type ImageStatus =
   | "Moved"
   | { Error: string };
 
+/** 扫描进度（按固定间隔发送，只包含计数） */
 export interface ScanProgressEvent {
+  job_id: string;
   scanned: number;
-  image: ImageInfo | null;
+  total: number;
+  /** 仍在查找图片时为 true（边查找边处理时 total 还会增加） */
+  discovering: boolean;
+  elapsed_ms: number;
+  /** 预计剩余时间，尚无法估算时为 null */
+  eta_ms: number | null;
   done: boolean;
   cancelled: boolean;
-  error: string | null;
+}
+
+/** 查找图片阶段的进度 */
+export interface ScanDiscoveryEvent {
+  job_id: string;
+  dirs_scanned: number;
+  found: number;
+  current_dir: string;
+  done: boolean;
+}
+
+/** 一批扫描结果 */
+export interface ScanBatchEvent {
+  job_id: string;
+  images: ImageInfo[];
+  failures: ScanFailure[];
+}
+
+export interface ScanFailure {
+  path: string;
+  error: string;
+}
+
+/** 扫描事件的发送频率 */
+export interface ScanEventOptions {
+  /** 每批最多包含多少条扫描结果，默认 50 */
+  batch_size?: number;
+  /** 两批结果之间的最长间隔（毫秒），默认 250 */
+  batch_interval_ms?: number;
+  /** 进度事件的最短间隔（毫秒），默认 100 */
+  progress_interval_ms?: number;
+  /** 查找完一个文件夹就开始处理其中的图片，默认 false */
+  process_during_discovery?: boolean;
+}
+
+export interface ScanRemovedEvent {
+  job_id: string;
+  paths: string[];
 }
 
 export interface MoveProgressEvent {
+  job_id: string;
   moved_count: number;
   total: number;
   current_file: string;
+  bytes_done: number;
+  bytes_total: number;
   done: boolean;
+  cancelled: boolean;
   error: string | null;
 }
 
@@ -38,20 +99,287 @@ export interface ScanStats {
   total_images: number;
   person_count: number;
   person_names: string[];
+  /** 读取失败的文件数（详情随 scan-batch 事件发送） */
+  failed_count: number;
 }
 
 export interface MoveImageRequest {
   path: string;
   filename: string;
   person: string;
+  persons?: string[];
+}
+
+export type MoveErrorKind =
+  | "source_missing"
+  | "destination_exists"
+  | "content_changed"
+  | "permission_denied"
+  | "no_space"
+  | "unsupported"
+  | "cancelled"
+  | "journal"
+  | "companion_failed"
+  | "verify_failed"
+  | "io";
+
+export interface MoveFailure {
+  source: string;
+  destination: string;
+  kind: MoveErrorKind;
+  /** 消息键，用于查找本地化文本 */
+  key: string;
+  message: string;
 }
 
 export interface MoveResult {
   moved_count: number;
   has_undo: boolean;
+  moved: string[];
+  failures: MoveFailure[];
+  cancelled: boolean;
+}
+
+export type UndoOutcome =
+  | "restored"
+  | "skipped_modified"
+  | "skipped_occupied"
+  | "skipped_group"
+  | "missing"
+  | "failed";
+
+export interface UndoFileResult {
+  original_path: string;
+  new_path: string;
+  outcome: UndoOutcome;
+  error: string | null;
+  error_key: string | null;
+  error_kind: ErrorKind | null;
 }
 
 export interface UndoResult {
   restored_count: number;
   success: boolean;
+  has_undo: boolean;
+  files: UndoFileResult[];
+}
+
+export interface HistorySummary {
+  id: string;
+  timestamp: string;
+  target_dir: string;
+  file_count: number;
+  undone: boolean;
+  undone_at: string | null;
+}
+
+export interface InterruptedOperation {
+  id: string;
+  timestamp: string;
+  target_dir: string;
+  total: number;
+  completed: number;
+  finished: boolean;
+}
+
+export interface CatalogQuery {
+  all_persons?: string[];
+  any_persons?: string[];
+  exclude_persons?: string[];
+  keywords?: string[];
+  path_prefix?: string | null;
+  limit?: number | null;
+  offset?: number | null;
+}
+
+export interface CatalogFile {
+  path: string;
+  filename: string;
+  size: number | null;
+  mtime_ms: number | null;
+  detected_format: string | null;
+  extension_mismatch: boolean;
+  persons: string[];
+  keywords: string[];
+  regions: FaceRegion[];
+}
+
+export interface PersonCount {
+  name: string;
+  file_count: number;
+}
+
+export interface SortRule {
+  person: string;
+  folder: string;
+}
+
+export interface WatchConfig {
+  source_dir: string;
+  target_dir: string;
+  include_subdirs: boolean;
+  settle_secs: number;
+  dry_run: boolean;
+  mode?: TransferMode;
+  path_template?: string | null;
+  conflict_policy?: ConflictPolicy;
+  detect_by_content: boolean;
+  rules: SortRule[];
+}
+
+export interface WatchAction {
+  path: string;
+  person: string | null;
+  destination: string | null;
+  dry_run: boolean;
+  error: string | null;
+  conflict: ConflictOutcome | null;
+}
+
+export interface WatchStatus {
+  running: boolean;
+  paused: boolean;
+  processed_count: number;
+  config: WatchConfig | null;
+}
+
+export type TransferMode = "move" | "copy" | "hardlink" | "symlink" | "reflink";
+
+export interface MoveOptions {
+  mode?: TransferMode;
+  /** 目标路径模板，如 "{person}/{year}/{month}" */
+  path_template?: string | null;
+  multi_person?: MultiPersonStrategy;
+  /** group 策略下人物数超过该值时放入 Group 文件夹 */
+  group_threshold?: number;
+  conflict_policy?: ConflictPolicy;
+  /** 改名模式，可用 {stem}、{n}、{ext}，默认 "{stem}_{n}{ext}" */
+  rename_pattern?: string | null;
+  name_rules?: NameRules;
+  companions?: CompanionRules;
+  /** 复制（包括跨设备移动）后重新读取副本并校验哈希 */
+  verify_checksum?: boolean;
+  /** 需要删除的文件（跨设备移动后的源文件、覆盖、去重）移到回收站 */
+  use_trash?: boolean;
+  /** 同时复制的文件数上限（改名和链接不受影响），默认 4 */
+  max_parallel?: number;
+}
+
+/** 伴随文件规则：同名的 RAW、XMP、JSON、Live Photo 视频等与图片一起移动 */
+export interface CompanionRules {
+  enabled?: boolean;
+  /** 扩展名（不含点，不区分大小写） */
+  extensions?: string[];
+}
+
+/** 人物名称转换为文件夹名时的清理规则 */
+export interface NameRules {
+  /** 非法字符的替换字符串，默认 "_" */
+  replacement?: string;
+  /** 自定义替换，在清理非法字符之前按顺序应用 */
+  replacements?: { from: string; to: string }[];
+  /** 单层文件夹名的最大字节数，默认 255 */
+  max_bytes?: number;
+}
+
+export type ConflictPolicy =
+  | "rename"
+  | "skip"
+  | "overwrite"
+  | "keep_larger"
+  | "keep_newer"
+  | "deduplicate";
+
+export type ConflictOutcome = "renamed" | "overwritten" | "skipped" | "kept_existing" | "deduplicated";
+
+export type MultiPersonStrategy = "primary" | "copy_all" | "hardlink_all" | "combined" | "group";
+
+export type PlanIssue =
+  | "source_missing"
+  | "name_conflict"
+  | "duplicate_destination"
+  | "cross_device"
+  | "permission_denied";
+
+export interface PlannedTransfer {
+  source: string;
+  destination: string;
+  filename: string;
+  mode: TransferMode;
+  size: number;
+  conflict: ConflictOutcome | null;
+  issues: PlanIssue[];
+  /** 所属文件组（图片与伴随文件共用） */
+  group?: string | null;
+}
+
+export interface MovePlan {
+  target_dir: string;
+  items: PlannedTransfer[];
+  estimated_bytes: number;
+  conflict_count: number;
+  missing_count: number;
+  cross_device_count: number;
+  permission_count: number;
+  verify_checksum?: boolean;
+  use_trash?: boolean;
+  max_parallel?: number;
+}
+
+export interface PathPreview {
+  source: string;
+  destination: string;
+}
+
+export type JobKind = "scan" | "move";
+
+export type JobStatus = "running" | "completed" | "failed" | "cancelled";
+
+/** 后台任务（扫描、移动）的状态快照 */
+export interface JobInfo {
+  id: string;
+  kind: JobKind;
+  status: JobStatus;
+  done: number;
+  total: number;
+  cancel_requested: boolean;
+  started_at: string;
+  finished_at: string | null;
+  /** 与对应命令返回值相同 */
+  result: unknown | null;
+  error: AppError | null;
+}
+
+export type ErrorKind =
+  | "busy"
+  | "not_found"
+  | "permission_denied"
+  | "already_exists"
+  | "invalid_input"
+  | "unsupported"
+  | "cancelled"
+  | "blocked"
+  | "decode"
+  | "io"
+  | "internal";
+
+/** 命令失败时返回的错误 */
+export interface AppError {
+  kind: ErrorKind;
+  /** 消息键，用于查找本地化文本 */
+  key: string;
+  message: string;
+  path: string | null;
+  detail: string | null;
+  /** 底层 IO 错误类型，如 "PermissionDenied" */
+  io_kind: string | null;
+}
+
+/** 后端消息语言（get_locale / set_locale） */
+export interface LocaleInfo {
+  current: string;
+  system: string;
+  /** 用户设置的语言，null 表示跟随系统 */
+  setting: string | null;
+  available: string[];
 }